@@ -0,0 +1,267 @@
+//! orderbook-cli
+//!
+//! A line based command interpreter driving a single [`OrderBook`], used by the `orderbook-cli`
+//! binary both interactively and to run scripts.
+//!
+//! Example:
+//! ```
+//! use orderbook_cli::Repl;
+//!
+//! let mut repl = Repl::new();
+//! repl.execute("sell 420 @ 69").unwrap();
+//! let output = repl.execute("buy 20 @ 70").unwrap();
+//! assert!(output.contains("trade 20 @ 69"));
+//! ```
+use orderbook::{Order, OrderBook, OrderBookError, Side, Trade};
+use std::fmt::Write;
+use thiserror::Error;
+
+pub const HELP: &str = "\
+buy <qty> @ <price>   match a buy order of qty > 0, the remainder rests in the book
+sell <qty> @ <price>  match a sell order of qty > 0, the remainder rests in the book
+cancel <id>           remove a resting order
+book                  print the price ladder
+orders                print the resting orders, best price first
+trades                print all trades
+help                  print this message";
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum CliError {
+    #[error("unknown command {0:?}, try `help`")]
+    UnknownCommand(String),
+    #[error("usage: {0}")]
+    Usage(&'static str),
+    #[error("invalid number {0:?}")]
+    InvalidNumber(String),
+    #[error(transparent)]
+    OrderBook(#[from] OrderBookError),
+}
+
+/// Command interpreter owning an [`OrderBook`], assigning order ids in sequence
+pub struct Repl {
+    book: OrderBook,
+    trades: Vec<Trade>,
+    next_id: u64,
+}
+
+impl Repl {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            book: OrderBook::new(),
+            trades: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    /// Function executes a single command line, empty lines and lines starting with `#` are
+    /// ignored
+    ///
+    /// Returns the output of the command, without trailing newline
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the command is invalid or rejected by the [`OrderBook`]
+    pub fn execute(&mut self, line: &str) -> Result<String, CliError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(String::new());
+        }
+        let mut args = line.split_whitespace();
+        let command = args.next().unwrap_or_default();
+        let args: Vec<&str> = args.collect();
+        match (command, args.as_slice()) {
+            ("buy", args) => self.order(Side::Bid, args),
+            ("sell", args) => self.order(Side::Ask, args),
+            ("cancel", [id]) => {
+                let id = parse(id)?;
+                self.book.remove(id)?;
+                Ok(format!("canceled order {id}"))
+            }
+            ("cancel", ..) => Err(CliError::Usage("cancel <id>")),
+            ("book", []) => Ok(self.ladder()),
+            ("orders", []) => Ok(self.orders()),
+            ("trades", []) => Ok(self.trades()),
+            ("help", []) => Ok(HELP.to_string()),
+            _ => Err(CliError::UnknownCommand(line.to_string())),
+        }
+    }
+
+    fn order(&mut self, side: Side, args: &[&str]) -> Result<String, CliError> {
+        let usage = match side {
+            Side::Bid => "buy <qty> @ <price>",
+            Side::Ask => "sell <qty> @ <price>",
+        };
+        let [qty, "@", price] = args else {
+            return Err(CliError::Usage(usage));
+        };
+        let (qty, price) = (parse(qty)?, parse(price)?);
+        if qty == 0 {
+            return Err(CliError::Usage(usage));
+        }
+        let id = self.next_id;
+        let trades = self.book.match_order(Order::new(price, qty, side, id))?;
+        self.next_id += 1;
+
+        let mut out = format!("order {id}: {} {qty} @ {price}", side_name(side));
+        for trade in &trades {
+            write!(out, "\n{}", format_trade(trade)).unwrap();
+        }
+        if let Some(order) = self.book.get_order(id) {
+            write!(out, "\nresting {} @ {}", order.qty, order.price).unwrap();
+        }
+        self.trades.extend(trades);
+        Ok(out)
+    }
+
+    /// Asks from the highest price down, followed by the bids from the highest price down
+    fn ladder(&self) -> String {
+        let mut out = format!("{:<4} {:>10} | {:>10}", "", "price", "qty");
+        let mut asks = self.book.get_depth(Side::Ask);
+        asks.reverse();
+        for (price, qty) in asks {
+            write!(out, "\n{:<4} {price:>10} | {qty:>10}", "ask").unwrap();
+        }
+        write!(out, "\n{}", "-".repeat(28)).unwrap();
+        for (price, qty) in self.book.get_depth(Side::Bid) {
+            write!(out, "\n{:<4} {price:>10} | {qty:>10}", "bid").unwrap();
+        }
+        out
+    }
+
+    fn orders(&self) -> String {
+        let mut lines = Vec::new();
+        for side in [Side::Ask, Side::Bid] {
+            for (price, _) in self.book.get_depth(side) {
+                for order in self.book.get_orders(price, side).unwrap_or_default() {
+                    lines.push(format!(
+                        "#{} {} {} @ {}",
+                        order.id,
+                        side_name(side),
+                        order.qty,
+                        order.price
+                    ));
+                }
+            }
+        }
+        if lines.is_empty() {
+            return String::from("no orders");
+        }
+        lines.join("\n")
+    }
+
+    fn trades(&self) -> String {
+        if self.trades.is_empty() {
+            return String::from("no trades");
+        }
+        let lines: Vec<_> = self.trades.iter().map(format_trade).collect();
+        lines.join("\n")
+    }
+}
+
+impl Default for Repl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Function runs every line of a script, echoing each command followed by its output
+///
+/// Errors are reported in the output and do not stop the script
+pub fn run_script(script: &str) -> String {
+    let mut repl = Repl::new();
+    let mut out = String::new();
+    for line in script.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        writeln!(out, "> {line}").unwrap();
+        match repl.execute(line) {
+            Ok(output) => writeln!(out, "{output}").unwrap(),
+            Err(e) => writeln!(out, "error: {e}").unwrap(),
+        }
+    }
+    out
+}
+
+fn parse(arg: &str) -> Result<u64, CliError> {
+    arg.parse()
+        .map_err(|_| CliError::InvalidNumber(arg.to_string()))
+}
+
+fn side_name(side: Side) -> &'static str {
+    match side {
+        Side::Bid => "buy",
+        Side::Ask => "sell",
+    }
+}
+
+fn format_trade(trade: &Trade) -> String {
+    format!(
+        "trade {} @ {} (maker #{}, taker #{} {})",
+        trade.qty,
+        trade.price,
+        trade.maker_id,
+        trade.taker_id,
+        side_name(trade.side)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CliError, Repl};
+    use orderbook::{OrderBookError, Side};
+
+    #[test]
+    fn buy_sell() {
+        // Setup
+        let mut repl = Repl::new();
+
+        // Act
+        let sell = repl.execute("sell 420 @ 69").unwrap();
+        let buy = repl.execute("buy 20 @ 70").unwrap();
+
+        // Assert
+        assert_eq!(sell, "order 1: sell 420 @ 69\nresting 420 @ 69");
+        assert_eq!(
+            buy,
+            "order 2: buy 20 @ 70\ntrade 20 @ 69 (maker #1, taker #2 buy)"
+        );
+        assert_eq!(repl.book().get_total_qty(69, Side::Ask), Some(400));
+    }
+
+    #[test]
+    fn invalid_commands() {
+        // Setup
+        let mut repl = Repl::new();
+
+        // Assert
+        assert_eq!(
+            repl.execute("buy 20 69"),
+            Err(CliError::Usage("buy <qty> @ <price>"))
+        );
+        assert_eq!(
+            repl.execute("sell 0 @ 69"),
+            Err(CliError::Usage("sell <qty> @ <price>"))
+        );
+        assert_eq!(
+            repl.execute("sell x @ 69"),
+            Err(CliError::InvalidNumber("x".to_string()))
+        );
+        assert_eq!(
+            repl.execute("cancel 1"),
+            Err(CliError::OrderBook(OrderBookError::UnknownId(1)))
+        );
+        assert_eq!(
+            repl.execute("hold"),
+            Err(CliError::UnknownCommand("hold".to_string()))
+        );
+        assert_eq!(repl.execute("# comment"), Ok(String::new()));
+    }
+}
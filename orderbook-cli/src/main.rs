@@ -0,0 +1,50 @@
+//! Usage: `orderbook-cli [SCRIPT]`
+//!
+//! Without a script commands are read interactively from stdin, with a script every command is
+//! echoed followed by its output.
+use orderbook_cli::{run_script, Repl, HELP};
+use std::io::{self, BufRead, Write};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match std::env::args().nth(1) {
+        Some(path) => match std::fs::read_to_string(&path) {
+            Ok(script) => {
+                print!("{}", run_script(&script));
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                eprintln!("Failed to read {path}: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        None => match interactive() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("{e}");
+                ExitCode::FAILURE
+            }
+        },
+    }
+}
+
+fn interactive() -> io::Result<()> {
+    let mut repl = Repl::new();
+    let mut stdout = io::stdout();
+    println!("{HELP}");
+    print!("> ");
+    stdout.flush()?;
+    for line in io::stdin().lock().lines() {
+        match line?.trim() {
+            "quit" | "exit" => break,
+            line => match repl.execute(line) {
+                Ok(output) if output.is_empty() => (),
+                Ok(output) => println!("{output}"),
+                Err(e) => println!("error: {e}"),
+            },
+        }
+        print!("> ");
+        stdout.flush()?;
+    }
+    Ok(())
+}
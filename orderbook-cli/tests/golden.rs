@@ -0,0 +1,29 @@
+//! Runs every `tests/scripts/*.txt` script and compares the output to the `.out` file next to it
+//!
+//! Set `UPDATE_GOLDEN=1` to rewrite the `.out` files from the current output.
+use orderbook_cli::run_script;
+use std::path::Path;
+
+#[test]
+fn golden_scripts() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut scripts: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    scripts.sort();
+    assert!(!scripts.is_empty());
+
+    for script in scripts {
+        let output = run_script(&std::fs::read_to_string(&script).unwrap());
+        let golden = script.with_extension("out");
+        if update {
+            std::fs::write(&golden, &output).unwrap();
+        } else {
+            let expected = std::fs::read_to_string(&golden).unwrap();
+            assert_eq!(output, expected, "{} differs", script.display());
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Measures the throughput of the engine on synthetic order flow for 1 up to N workers
+//!
+//! Usage: `engine-bench [MAX_WORKERS] [COMMANDS] [SYMBOLS]`
+//!
+//! Run with `--release`, the defaults are the available parallelism, 2 million commands and 64
+//! symbols.
+use orderbook_engine::engine::{Engine, EngineConfig};
+use orderbook_engine::synthetic::order_flow;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Instant;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parse = |idx: usize, default: usize| {
+        args.get(idx)
+            .map_or(Ok(default), |arg| arg.parse::<usize>())
+    };
+    let max_workers = EngineConfig::default().workers;
+    let (max_workers, commands, symbols) =
+        match (parse(0, max_workers), parse(1, 2_000_000), parse(2, 64)) {
+            (Ok(workers), Ok(commands), Ok(symbols)) => (workers, commands, symbols),
+            (Err(e), ..) | (_, Err(e), _) | (.., Err(e)) => {
+                eprintln!("Usage: engine-bench [MAX_WORKERS] [COMMANDS] [SYMBOLS]: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+    let symbols = u32::try_from(symbols).unwrap_or(u32::MAX);
+    let flow = order_flow(42, symbols, commands);
+
+    println!("workers,commands,outputs,seconds,commands_per_second,speedup");
+    let mut baseline = None;
+    for workers in 1..=max_workers.max(1) {
+        let mut engine = match Engine::new(EngineConfig {
+            workers,
+            ..EngineConfig::default()
+        }) {
+            Ok(engine) => engine,
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        let output = engine.take_output().expect("output is only taken once");
+        let start = Instant::now();
+        let receiver = thread::spawn(move || output.iter().count());
+        for &command in &flow {
+            engine.submit(command);
+        }
+        engine.shutdown();
+        let outputs = receiver.join().expect("receiver panicked");
+        let seconds = start.elapsed().as_secs_f64();
+        #[allow(clippy::cast_precision_loss)]
+        let rate = flow.len() as f64 / seconds;
+        let speedup = rate / *baseline.get_or_insert(rate);
+        println!("{workers},{commands},{outputs},{seconds:.3},{rate:.0},{speedup:.2}");
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,388 @@
+//! Sharded matching engine
+//!
+//! Every symbol is pinned to one of N worker threads by `symbol % workers`, each worker owns the
+//! [`OrderBook`]'s of its symbols. Commands reach a worker through its own bounded [`ring`], so a
+//! symbol is only ever touched by a single thread. All workers send their [`Output`]'s to a single
+//! bounded stream. Outputs of different symbols interleave, the outputs of one symbol keep the
+//! order of its commands and are numbered by `seq`.
+//!
+//! A worker waits while the output stream is full and [`Engine::submit`] waits while the ring of
+//! the worker is full, so outputs must be received while submitting, usually on another thread
+//! via [`Engine::take_output`].
+use crate::ring::{ring, Producer};
+use orderbook::{Order, OrderBook, OrderBookError, Trade};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use thiserror::Error;
+
+/// Identifies an instrument
+pub type Symbol = u32;
+
+/// Engine error
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EngineError {
+    /// A field of the [`EngineConfig`] is out of range
+    #[error("Invalid engine config: {0}")]
+    InvalidConfig(&'static str),
+}
+
+/// Inbound command for the book of `symbol`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    /// Matches the order, any remaining quantity rests in the book
+    Insert { symbol: Symbol, order: Order },
+    /// Removes a resting order
+    Cancel { symbol: Symbol, id: u64 },
+}
+
+impl Command {
+    /// Function gets the symbol of the book the command is for
+    #[must_use]
+    pub fn symbol(&self) -> Symbol {
+        match *self {
+            Command::Insert { symbol, .. } | Command::Cancel { symbol, .. } => symbol,
+        }
+    }
+}
+
+/// Result of a [`Command`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// The order was accepted, followed by its fills
+    Ack {
+        id: u64,
+    },
+    Fill {
+        trade: Trade,
+    },
+    Canceled {
+        id: u64,
+    },
+    Reject {
+        id: u64,
+        error: OrderBookError,
+    },
+}
+
+/// An [`Event`] of a symbol
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Output {
+    pub symbol: Symbol,
+    /// Position in the outputs of the symbol, starting at 0
+    pub seq: u64,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineConfig {
+    /// Number of worker threads
+    pub workers: usize,
+    /// Number of commands each worker ring holds before [`Engine::submit`] waits
+    pub ring_capacity: usize,
+    /// Number of outputs the output stream holds before the workers wait
+    pub output_capacity: usize,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            workers: thread::available_parallelism().map_or(1, usize::from),
+            ring_capacity: 4096,
+            output_capacity: 65536,
+        }
+    }
+}
+
+/// Books of the symbols pinned to one worker
+#[derive(Default)]
+struct Shard {
+    books: HashMap<Symbol, (OrderBook, u64)>,
+}
+
+impl Shard {
+    fn handle(&mut self, command: Command, output: &SyncSender<Output>) {
+        let symbol = command.symbol();
+        let (book, seq) = self.books.entry(symbol).or_default();
+        let mut send = |event| {
+            // Outputs are dropped once the receiver taken by `Engine::take_output` went away
+            let _ = output.send(Output {
+                symbol,
+                seq: *seq,
+                event,
+            });
+            *seq += 1;
+        };
+        match command {
+            Command::Insert { order, .. } => match book.match_order(order) {
+                Ok(trades) => {
+                    send(Event::Ack { id: order.id });
+                    for trade in trades {
+                        send(Event::Fill { trade });
+                    }
+                }
+                Err(error) => send(Event::Reject {
+                    id: order.id,
+                    error,
+                }),
+            },
+            Command::Cancel { id, .. } => match book.remove(id) {
+                Ok(()) => send(Event::Canceled { id }),
+                Err(error) => send(Event::Reject { id, error }),
+            },
+        }
+    }
+}
+
+/// Matching engine running the books of all symbols on N worker threads
+pub struct Engine {
+    inputs: Vec<Producer<Command>>,
+    workers: Vec<JoinHandle<()>>,
+    /// [`None`] once taken by [`Engine::take_output`]
+    output: Option<Receiver<Output>>,
+}
+
+impl Engine {
+    /// Constructor function, starting the worker threads
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if there are no workers or the ring or output capacity is 0
+    pub fn new(config: EngineConfig) -> Result<Self, EngineError> {
+        if config.workers == 0 {
+            return Err(EngineError::InvalidConfig("workers must be positive"));
+        }
+        if config.ring_capacity == 0 {
+            return Err(EngineError::InvalidConfig("ring_capacity must be positive"));
+        }
+        if config.output_capacity == 0 {
+            return Err(EngineError::InvalidConfig(
+                "output_capacity must be positive",
+            ));
+        }
+        let (sender, output) = mpsc::sync_channel(config.output_capacity);
+        let (inputs, workers) = (0..config.workers)
+            .map(|_| {
+                let (producer, mut consumer) = ring(config.ring_capacity);
+                let sender = sender.clone();
+                let worker = thread::spawn(move || {
+                    let mut shard = Shard::default();
+                    while let Some(command) = consumer.pop() {
+                        shard.handle(command, &sender);
+                    }
+                });
+                (producer, worker)
+            })
+            .unzip();
+        Ok(Self {
+            inputs,
+            workers,
+            output: Some(output),
+        })
+    }
+
+    /// Function gets the number of worker threads
+    #[must_use]
+    pub fn workers(&self) -> usize {
+        self.inputs.len()
+    }
+
+    /// Function gets the worker a symbol is pinned to
+    #[must_use]
+    pub fn worker_of(&self, symbol: Symbol) -> usize {
+        symbol as usize % self.inputs.len()
+    }
+
+    /// Function routes a command to the worker of its symbol, waiting while its ring is full
+    pub fn submit(&mut self, command: Command) {
+        let worker = self.worker_of(command.symbol());
+        self.inputs[worker].push(command);
+    }
+
+    /// Function takes the receiving end of the outputs, so they can be received on another thread
+    ///
+    /// Returns [`None`] if it was already taken
+    pub fn take_output(&mut self) -> Option<Receiver<Output>> {
+        self.output.take()
+    }
+
+    /// Function gets the next output, waiting until one is available
+    ///
+    /// Returns [`None`] if every worker stopped or the output was taken
+    pub fn recv(&self) -> Option<Output> {
+        self.output.as_ref()?.recv().ok()
+    }
+
+    /// Function gets the next output without waiting
+    ///
+    /// Returns [`None`] if no output is available or the output was taken
+    pub fn try_recv(&self) -> Option<Output> {
+        self.output.as_ref()?.try_recv().ok()
+    }
+
+    /// Function stops the workers once they handled every submitted command
+    ///
+    /// Returns the outputs not received yet, none if the output was taken
+    ///
+    /// # Panics
+    ///
+    /// Panics if a worker panicked
+    pub fn shutdown(self) -> Vec<Output> {
+        drop(self.inputs);
+        // Receives until every worker dropped its sender, so none of them waits on a full stream
+        let outputs = self
+            .output
+            .map(|output| output.iter().collect())
+            .unwrap_or_default();
+        for worker in self.workers {
+            worker.join().expect("worker panicked");
+        }
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Command, Engine, EngineConfig, EngineError, Event, Output, Symbol};
+    use orderbook::{Order, OrderBook, OrderBookError, Side};
+    use std::collections::HashMap;
+    use std::thread;
+
+    fn config(workers: usize) -> EngineConfig {
+        EngineConfig {
+            workers,
+            ring_capacity: 4,
+            output_capacity: 4,
+        }
+    }
+
+    fn commands() -> Vec<Command> {
+        let mut commands = Vec::new();
+        for round in 0..50 {
+            for symbol in 0..7 {
+                let id = round * 100 + u64::from(symbol) * 2;
+                let side = if round % 2 == 0 { Side::Bid } else { Side::Ask };
+                commands.push(Command::Insert {
+                    symbol,
+                    order: Order::new(100 + round % 3, 5 + round % 4, side, id),
+                });
+                commands.push(Command::Cancel {
+                    symbol,
+                    id: id.saturating_sub(200),
+                });
+            }
+        }
+        commands
+    }
+
+    #[test]
+    fn invalid_config() {
+        // Act & Assert
+        assert!(matches!(
+            Engine::new(config(0)),
+            Err(EngineError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Engine::new(EngineConfig {
+                ring_capacity: 0,
+                ..config(1)
+            }),
+            Err(EngineError::InvalidConfig(_))
+        ));
+        assert!(matches!(
+            Engine::new(EngineConfig {
+                output_capacity: 0,
+                ..config(1)
+            }),
+            Err(EngineError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn insert_fill_cancel() {
+        // Setup
+        let mut engine = Engine::new(config(2)).unwrap();
+
+        // Act
+        engine.submit(Command::Insert {
+            symbol: 3,
+            order: Order::new(100, 10, Side::Bid, 1),
+        });
+        engine.submit(Command::Insert {
+            symbol: 3,
+            order: Order::new(100, 4, Side::Ask, 2),
+        });
+        engine.submit(Command::Cancel { symbol: 3, id: 2 });
+        let outputs = engine.shutdown();
+
+        // Assert
+        let events: Vec<(u64, Event)> = outputs.iter().map(|out| (out.seq, out.event)).collect();
+        assert!(matches!(
+            events.as_slice(),
+            [
+                (0, Event::Ack { id: 1 }),
+                (1, Event::Ack { id: 2 }),
+                (2, Event::Fill { trade }),
+                (
+                    3,
+                    Event::Reject {
+                        id: 2,
+                        error: OrderBookError::UnknownId(2)
+                    }
+                ),
+            ] if trade.qty == 4
+        ));
+    }
+
+    #[test]
+    fn per_symbol_order_matches_single_book() {
+        // Setup
+        let mut expected: HashMap<Symbol, Vec<Event>> = HashMap::new();
+        let mut books: HashMap<Symbol, OrderBook> = HashMap::new();
+        for command in commands() {
+            let book = books.entry(command.symbol()).or_default();
+            let events = expected.entry(command.symbol()).or_default();
+            match command {
+                Command::Insert { order, .. } => match book.match_order(order) {
+                    Ok(trades) => {
+                        events.push(Event::Ack { id: order.id });
+                        events.extend(trades.into_iter().map(|trade| Event::Fill { trade }));
+                    }
+                    Err(error) => events.push(Event::Reject {
+                        id: order.id,
+                        error,
+                    }),
+                },
+                Command::Cancel { id, .. } => events.push(match book.remove(id) {
+                    Ok(()) => Event::Canceled { id },
+                    Err(error) => Event::Reject { id, error },
+                }),
+            }
+        }
+
+        for workers in 1..=4 {
+            // Act
+            let mut engine = Engine::new(config(workers)).unwrap();
+            let output = engine.take_output().unwrap();
+            let receiver = thread::spawn(move || output.iter().collect::<Vec<Output>>());
+            for command in commands() {
+                engine.submit(command);
+            }
+            assert!(engine.shutdown().is_empty());
+            let outputs = receiver.join().unwrap();
+
+            // Assert
+            let mut actual: HashMap<Symbol, Vec<Event>> = HashMap::new();
+            for output in outputs {
+                let events = actual.entry(output.symbol).or_default();
+                assert_eq!(output.seq, events.len() as u64);
+                events.push(output.event);
+            }
+            assert_eq!(actual, expected, "{workers} workers");
+        }
+    }
+}
@@ -0,0 +1,7 @@
+//! orderbook-engine
+//!
+//! Multi-threaded matching of many symbols, each [`orderbook::OrderBook`] pinned to one worker
+//! thread.
+pub mod engine;
+pub mod ring;
+pub mod synthetic;
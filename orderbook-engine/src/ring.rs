@@ -0,0 +1,228 @@
+//! Bounded single producer, single consumer ring buffer
+//!
+//! The producer and the consumer each own one index and only read the other, so neither side
+//! takes a lock. Both sides cache the last seen index of the other side to avoid touching its
+//! cache line on every operation.
+use std::cell::UnsafeCell;
+use std::mem::MaybeUninit;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::{hint, thread};
+
+/// Spins before yielding the thread while waiting on the other side
+const SPINS: u32 = 64;
+
+/// Keeps the indices of the producer and consumer on separate cache lines
+#[repr(align(64))]
+struct CachePadded<T>(T);
+
+struct Shared<T> {
+    buffer: Box<[UnsafeCell<MaybeUninit<T>>]>,
+    /// Next slot to read, written by the consumer only
+    head: CachePadded<AtomicUsize>,
+    /// Next slot to write, written by the producer only
+    tail: CachePadded<AtomicUsize>,
+    /// Set once the producer is dropped
+    closed: AtomicBool,
+}
+
+// SAFETY: a slot is only accessed by the producer before the release of `tail` covering it and
+// only by the consumer after acquiring that `tail` and before the release of `head` past it.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn slot(&self, idx: usize) -> *mut MaybeUninit<T> {
+        self.buffer[idx % self.buffer.len()].get()
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    fn drop(&mut self) {
+        let head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        for idx in head..tail {
+            // SAFETY: slots between head and tail were written and never read
+            unsafe { (*self.slot(idx)).assume_init_drop() };
+        }
+    }
+}
+
+/// Sending half of a ring
+pub struct Producer<T> {
+    shared: Arc<Shared<T>>,
+    tail: usize,
+    head: usize,
+}
+
+/// Receiving half of a ring
+pub struct Consumer<T> {
+    shared: Arc<Shared<T>>,
+    head: usize,
+    tail: usize,
+}
+
+/// Function creates a ring holding up to `capacity` values
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0
+#[must_use]
+pub fn ring<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "ring capacity must be positive");
+    let shared = Arc::new(Shared {
+        buffer: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+        head: CachePadded(AtomicUsize::new(0)),
+        tail: CachePadded(AtomicUsize::new(0)),
+        closed: AtomicBool::new(false),
+    });
+    let producer = Producer {
+        shared: Arc::clone(&shared),
+        tail: 0,
+        head: 0,
+    };
+    let consumer = Consumer {
+        shared,
+        head: 0,
+        tail: 0,
+    };
+    (producer, consumer)
+}
+
+fn backoff(spins: &mut u32) {
+    if *spins < SPINS {
+        *spins += 1;
+        hint::spin_loop();
+    } else {
+        thread::yield_now();
+    }
+}
+
+impl<T> Producer<T> {
+    /// Function appends a value without waiting
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] with the value if the ring is full
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let capacity = self.shared.buffer.len();
+        if self.tail - self.head == capacity {
+            self.head = self.shared.head.0.load(Ordering::Acquire);
+            if self.tail - self.head == capacity {
+                return Err(value);
+            }
+        }
+        // SAFETY: the slot is free, the consumer moved `head` past it
+        unsafe { (*self.shared.slot(self.tail)).write(value) };
+        self.tail += 1;
+        self.shared.tail.0.store(self.tail, Ordering::Release);
+        Ok(())
+    }
+
+    /// Function appends a value, waiting while the ring is full
+    pub fn push(&mut self, mut value: T) {
+        let mut spins = 0;
+        while let Err(rejected) = self.try_push(value) {
+            value = rejected;
+            backoff(&mut spins);
+        }
+    }
+}
+
+impl<T> Drop for Producer<T> {
+    fn drop(&mut self) {
+        self.shared.closed.store(true, Ordering::Release);
+    }
+}
+
+impl<T> Consumer<T> {
+    /// Function takes the oldest value without waiting
+    ///
+    /// Returns [`None`] if the ring is empty
+    pub fn try_pop(&mut self) -> Option<T> {
+        if self.head == self.tail {
+            self.tail = self.shared.tail.0.load(Ordering::Acquire);
+            if self.head == self.tail {
+                return None;
+            }
+        }
+        // SAFETY: the producer wrote the slot before moving `tail` past it
+        let value = unsafe { (*self.shared.slot(self.head)).assume_init_read() };
+        self.head += 1;
+        self.shared.head.0.store(self.head, Ordering::Release);
+        Some(value)
+    }
+
+    /// Function takes the oldest value, waiting while the ring is empty
+    ///
+    /// Returns [`None`] once the ring is empty and the [`Producer`] is dropped
+    pub fn pop(&mut self) -> Option<T> {
+        let mut spins = 0;
+        loop {
+            if let Some(value) = self.try_pop() {
+                return Some(value);
+            }
+            if self.shared.closed.load(Ordering::Acquire) {
+                return self.try_pop();
+            }
+            backoff(&mut spins);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ring;
+    use std::rc::Rc;
+    use std::thread;
+
+    #[test]
+    fn fifo_and_full() {
+        // Setup
+        let (mut producer, mut consumer) = ring(2);
+
+        // Act & Assert
+        assert_eq!(producer.try_push(1), Ok(()));
+        assert_eq!(producer.try_push(2), Ok(()));
+        assert_eq!(producer.try_push(3), Err(3));
+        assert_eq!(consumer.try_pop(), Some(1));
+        assert_eq!(producer.try_push(3), Ok(()));
+        assert_eq!(consumer.try_pop(), Some(2));
+        assert_eq!(consumer.try_pop(), Some(3));
+        assert_eq!(consumer.try_pop(), None);
+        drop(producer);
+        assert_eq!(consumer.pop(), None);
+    }
+
+    #[test]
+    fn drops_unread_values() {
+        // Setup
+        let value = Rc::new(());
+        let (mut producer, consumer) = ring(4);
+
+        // Act
+        producer.try_push(Rc::clone(&value)).unwrap();
+        producer.try_push(Rc::clone(&value)).unwrap();
+        drop(producer);
+        drop(consumer);
+
+        // Assert
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    fn across_threads() {
+        // Setup
+        let (mut producer, mut consumer) = ring(8);
+        let count = 100_000_u64;
+
+        // Act
+        let sender = thread::spawn(move || (0..count).for_each(|value| producer.push(value)));
+        let received: Vec<u64> = std::iter::from_fn(|| consumer.pop()).collect();
+        sender.join().unwrap();
+
+        // Assert
+        assert!(received.into_iter().eq(0..count));
+    }
+}
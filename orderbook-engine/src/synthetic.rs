@@ -0,0 +1,46 @@
+//! Synthetic order flow
+//!
+//! Seeded random inserts and cancels around a fixed mid price per symbol, used by the
+//! `engine-bench` binary.
+use crate::engine::{Command, Symbol};
+use orderbook::{Order, Side};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Mid price of every symbol
+const MID: u64 = 10_000;
+
+/// Function generates `count` commands spread evenly over `symbols` symbols
+///
+/// About a quarter of the commands cancel an order inserted earlier in the same symbol, the
+/// inserts are limit orders within 10 ticks of the mid price, so a share of them cross.
+#[must_use]
+pub fn order_flow(seed: u64, symbols: Symbol, count: usize) -> Vec<Command> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let symbols = symbols.max(1);
+    let mut live: Vec<Vec<u64>> = vec![Vec::new(); symbols as usize];
+    (0..count as u64)
+        .map(|id| {
+            let symbol = rng.gen_range(0..symbols);
+            let live = &mut live[symbol as usize];
+            if !live.is_empty() && rng.gen_bool(0.25) {
+                let idx = rng.gen_range(0..live.len());
+                return Command::Cancel {
+                    symbol,
+                    id: live.swap_remove(idx),
+                };
+            }
+            let side = if rng.gen_bool(0.5) {
+                Side::Bid
+            } else {
+                Side::Ask
+            };
+            let price = MID - 10 + rng.gen_range(0..=20);
+            live.push(id);
+            Command::Insert {
+                symbol,
+                order: Order::new(price, rng.gen_range(1..=100), side, id),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,10 @@
+//! Generates the C header `orderbook.h` into `OUT_DIR` from the `extern "C"` functions
+fn main() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    cbindgen::generate(&crate_dir)
+        .expect("generate C bindings")
+        .write_to_file(format!("{out_dir}/orderbook.h"));
+}
@@ -0,0 +1,147 @@
+#ifndef ORDERBOOK_H
+#define ORDERBOOK_H
+
+/* Generated by cbindgen from orderbook-ffi, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call
+typedef enum OrderbookStatus {
+  ORDERBOOK_STATUS_OK = 0,
+  // No resting order with the given `OrderId`
+  ORDERBOOK_STATUS_UNKNOWN_ID = 1,
+  // An order with the given `OrderId` is already resting
+  ORDERBOOK_STATUS_DUPLICATE_ORDER_ID = 2,
+  // The price is not accepted by the book
+  ORDERBOOK_STATUS_INVALID_PRICE = 3,
+  // No orders on the given `Price` and `Side` combination, or on the given `Side`
+  ORDERBOOK_STATUS_EMPTY = 4,
+  // A required pointer argument is null
+  ORDERBOOK_STATUS_NULL_POINTER = 5,
+  // The quantity is zero
+  ORDERBOOK_STATUS_INVALID_QTY = 6,
+  // The side is not an `OrderbookSide`
+  ORDERBOOK_STATUS_INVALID_SIDE = 7,
+  // An error of the book without a status of its own in this version
+  ORDERBOOK_STATUS_OTHER = 255,
+} OrderbookStatus;
+
+// Ask or Bid, passed as its `uint8_t` value
+enum OrderbookSide
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : uint8_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  ORDERBOOK_SIDE_ASK = 0,
+  ORDERBOOK_SIDE_BID = 1,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum OrderbookSide OrderbookSide;
+#else
+typedef uint8_t OrderbookSide;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+// Opaque handle of an [`OrderBook`]
+//
+// Drained orders are kept in the handle so no allocation crosses the ABI.
+typedef struct OrderbookHandle OrderbookHandle;
+
+// Order as laid out in C, see [`Order`]
+typedef struct OrderbookOrder {
+  uint64_t price;
+  uint64_t qty;
+  // An [`OrderbookSide`]
+  uint8_t side;
+  uint64_t id;
+} OrderbookOrder;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates an empty book, release it with [`orderbook_free`]
+struct OrderbookHandle *orderbook_new(void);
+
+// Releases a book created by [`orderbook_new`], null is ignored
+//
+// # Safety
+//
+// `book` must be null or a handle returned by [`orderbook_new`] that was not freed yet
+void orderbook_free(struct OrderbookHandle *book);
+
+// Inserts an order into the book without matching, see [`OrderBook::insert`]
+//
+// # Safety
+//
+// `book` must be null or a live handle returned by [`orderbook_new`]
+enum OrderbookStatus orderbook_insert(struct OrderbookHandle *book, struct OrderbookOrder order);
+
+// Removes the order with the given `id`, see [`OrderBook::remove`]
+//
+// # Safety
+//
+// `book` must be null or a live handle returned by [`orderbook_new`]
+enum OrderbookStatus orderbook_remove(struct OrderbookHandle *book, uint64_t id);
+
+// Writes the best price of the given side to `price`
+//
+// Returns `ORDERBOOK_STATUS_EMPTY` if there are no orders on the side
+//
+// # Safety
+//
+// `book` must be null or a live handle returned by [`orderbook_new`], `price` must be null or
+// valid for writes
+enum OrderbookStatus orderbook_best_price(const struct OrderbookHandle *book,
+                                          uint8_t side,
+                                          uint64_t *price);
+
+// Writes the total quantity resting on the given `price` and side to `qty`
+//
+// Returns `ORDERBOOK_STATUS_EMPTY` if there are no orders on the price
+//
+// # Safety
+//
+// `book` must be null or a live handle returned by [`orderbook_new`], `qty` must be null or
+// valid for writes
+enum OrderbookStatus orderbook_total_qty(const struct OrderbookHandle *book,
+                                         uint64_t price,
+                                         uint8_t side,
+                                         uint64_t *qty);
+
+// Drains orders on the given `price` and side up to `qty`, see
+// [`OrderBook::get_orders_till_qty`]
+//
+// On success `orders` points to `len` drained orders in FIFO order and `drained_qty` holds their
+// total quantity. The orders are owned by the handle and stay valid until the next call on it.
+//
+// Returns `ORDERBOOK_STATUS_EMPTY` if there are no orders on the price
+//
+// # Safety
+//
+// `book` must be null or a live handle returned by [`orderbook_new`], the out pointers must be
+// null or valid for writes
+enum OrderbookStatus orderbook_drain(struct OrderbookHandle *book,
+                                     uint64_t price,
+                                     uint8_t side,
+                                     uint64_t qty,
+                                     const struct OrderbookOrder **orders,
+                                     size_t *len,
+                                     uint64_t *drained_qty);
+
+// Gets a static, nul terminated description of a status
+//
+// Takes the status as a plain `int32_t`, values that are not an `OrderbookStatus` get a
+// description of their own
+const char *orderbook_status_message(int32_t status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ORDERBOOK_H */
@@ -0,0 +1,453 @@
+//! orderbook-ffi
+//!
+//! C ABI of [`orderbook::OrderBook`] for embedding the book in C and C++ systems. The header
+//! `include/orderbook.h` is generated by cbindgen into `OUT_DIR` when the crate is built, a test
+//! checks the committed copy is up to date.
+//!
+//! A book is an opaque [`OrderbookHandle`] created by [`orderbook_new`] and released by
+//! [`orderbook_free`]. Every fallible function returns an [`OrderbookStatus`] and writes its
+//! result through out pointers, which are left untouched unless the status is
+//! `ORDERBOOK_STATUS_OK`. Sides cross the ABI as a plain `uint8_t` holding an [`OrderbookSide`],
+//! any other value is rejected with `ORDERBOOK_STATUS_INVALID_SIDE`.
+//!
+//! Example:
+//! ```c
+//! OrderbookHandle *book = orderbook_new();
+//! OrderbookOrder order = {.price = 69, .qty = 420, .side = ORDERBOOK_SIDE_ASK, .id = 1};
+//! if (orderbook_insert(book, order) != ORDERBOOK_STATUS_OK) {
+//!     /* handle the error */
+//! }
+//! uint64_t best;
+//! orderbook_best_price(book, ORDERBOOK_SIDE_ASK, &best);
+//! orderbook_free(book);
+//! ```
+use orderbook::{Order, OrderBook, OrderBookError, Side};
+use std::ffi::c_char;
+
+/// Outcome of a call
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderbookStatus {
+    Ok = 0,
+    /// No resting order with the given `OrderId`
+    UnknownId = 1,
+    /// An order with the given `OrderId` is already resting
+    DuplicateOrderId = 2,
+    /// The price is not accepted by the book
+    InvalidPrice = 3,
+    /// No orders on the given `Price` and `Side` combination, or on the given `Side`
+    Empty = 4,
+    /// A required pointer argument is null
+    NullPointer = 5,
+    /// The quantity is zero
+    InvalidQty = 6,
+    /// The side is not an `OrderbookSide`
+    InvalidSide = 7,
+    /// An error of the book without a status of its own in this version
+    Other = 255,
+}
+
+impl OrderbookStatus {
+    /// Every status, to validate a status passed in from C
+    const ALL: [OrderbookStatus; 9] = [
+        OrderbookStatus::Ok,
+        OrderbookStatus::UnknownId,
+        OrderbookStatus::DuplicateOrderId,
+        OrderbookStatus::InvalidPrice,
+        OrderbookStatus::Empty,
+        OrderbookStatus::NullPointer,
+        OrderbookStatus::InvalidQty,
+        OrderbookStatus::InvalidSide,
+        OrderbookStatus::Other,
+    ];
+}
+
+impl From<OrderBookError> for OrderbookStatus {
+    fn from(err: OrderBookError) -> Self {
+        match err {
+            OrderBookError::UnknownId(_) => OrderbookStatus::UnknownId,
+            OrderBookError::DuplicateOrderId(_) => OrderbookStatus::DuplicateOrderId,
+            OrderBookError::InvalidQty(_) => OrderbookStatus::InvalidQty,
+            OrderBookError::InvalidPrice { .. } => OrderbookStatus::InvalidPrice,
+            _ => OrderbookStatus::Other,
+        }
+    }
+}
+
+impl From<Result<(), OrderBookError>> for OrderbookStatus {
+    fn from(result: Result<(), OrderBookError>) -> Self {
+        result.map_or_else(OrderbookStatus::from, |()| OrderbookStatus::Ok)
+    }
+}
+
+/// Ask or Bid, passed as its `uint8_t` value
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrderbookSide {
+    Ask = 0,
+    Bid = 1,
+}
+
+impl TryFrom<u8> for OrderbookSide {
+    type Error = OrderbookStatus;
+
+    fn try_from(side: u8) -> Result<Self, Self::Error> {
+        match side {
+            0 => Ok(OrderbookSide::Ask),
+            1 => Ok(OrderbookSide::Bid),
+            _ => Err(OrderbookStatus::InvalidSide),
+        }
+    }
+}
+
+impl From<OrderbookSide> for Side {
+    fn from(side: OrderbookSide) -> Self {
+        match side {
+            OrderbookSide::Ask => Side::Ask,
+            OrderbookSide::Bid => Side::Bid,
+        }
+    }
+}
+
+impl From<Side> for OrderbookSide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Ask => OrderbookSide::Ask,
+            Side::Bid => OrderbookSide::Bid,
+        }
+    }
+}
+
+/// Order as laid out in C, see [`Order`]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderbookOrder {
+    pub price: u64,
+    pub qty: u64,
+    /// An [`OrderbookSide`]
+    pub side: u8,
+    pub id: u64,
+}
+
+impl TryFrom<OrderbookOrder> for Order {
+    type Error = OrderbookStatus;
+
+    fn try_from(order: OrderbookOrder) -> Result<Self, Self::Error> {
+        let side = OrderbookSide::try_from(order.side)?;
+        Ok(Order::new(order.price, order.qty, side.into(), order.id))
+    }
+}
+
+impl From<Order> for OrderbookOrder {
+    fn from(order: Order) -> Self {
+        OrderbookOrder {
+            price: order.price,
+            qty: order.qty,
+            side: OrderbookSide::from(order.side) as u8,
+            id: order.id,
+        }
+    }
+}
+
+/// Opaque handle of an [`OrderBook`]
+///
+/// Drained orders are kept in the handle so no allocation crosses the ABI.
+pub struct OrderbookHandle {
+    book: OrderBook,
+    drained: Vec<Order>,
+    drained_out: Vec<OrderbookOrder>,
+}
+
+/// Creates an empty book, release it with [`orderbook_free`]
+#[no_mangle]
+pub extern "C" fn orderbook_new() -> *mut OrderbookHandle {
+    Box::into_raw(Box::new(OrderbookHandle {
+        book: OrderBook::new(),
+        drained: Vec::new(),
+        drained_out: Vec::new(),
+    }))
+}
+
+/// Releases a book created by [`orderbook_new`], null is ignored
+///
+/// # Safety
+///
+/// `book` must be null or a handle returned by [`orderbook_new`] that was not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn orderbook_free(book: *mut OrderbookHandle) {
+    if !book.is_null() {
+        drop(Box::from_raw(book));
+    }
+}
+
+/// Inserts an order into the book without matching, see [`OrderBook::insert`]
+///
+/// # Safety
+///
+/// `book` must be null or a live handle returned by [`orderbook_new`]
+#[no_mangle]
+pub unsafe extern "C" fn orderbook_insert(
+    book: *mut OrderbookHandle,
+    order: OrderbookOrder,
+) -> OrderbookStatus {
+    let Some(handle) = book.as_mut() else {
+        return OrderbookStatus::NullPointer;
+    };
+    match Order::try_from(order) {
+        Ok(order) => handle.book.insert(order).into(),
+        Err(status) => status,
+    }
+}
+
+/// Removes the order with the given `id`, see [`OrderBook::remove`]
+///
+/// # Safety
+///
+/// `book` must be null or a live handle returned by [`orderbook_new`]
+#[no_mangle]
+pub unsafe extern "C" fn orderbook_remove(book: *mut OrderbookHandle, id: u64) -> OrderbookStatus {
+    let Some(handle) = book.as_mut() else {
+        return OrderbookStatus::NullPointer;
+    };
+    handle.book.remove(id).into()
+}
+
+/// Writes the best price of the given side to `price`
+///
+/// Returns `ORDERBOOK_STATUS_EMPTY` if there are no orders on the side
+///
+/// # Safety
+///
+/// `book` must be null or a live handle returned by [`orderbook_new`], `price` must be null or
+/// valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn orderbook_best_price(
+    book: *const OrderbookHandle,
+    side: u8,
+    price: *mut u64,
+) -> OrderbookStatus {
+    let (Some(handle), false) = (book.as_ref(), price.is_null()) else {
+        return OrderbookStatus::NullPointer;
+    };
+    let side = match OrderbookSide::try_from(side) {
+        Ok(side) => side,
+        Err(status) => return status,
+    };
+    match handle.book.get_best_price(side.into()) {
+        Some(&best) => {
+            price.write(best);
+            OrderbookStatus::Ok
+        }
+        None => OrderbookStatus::Empty,
+    }
+}
+
+/// Writes the total quantity resting on the given `price` and side to `qty`
+///
+/// Returns `ORDERBOOK_STATUS_EMPTY` if there are no orders on the price
+///
+/// # Safety
+///
+/// `book` must be null or a live handle returned by [`orderbook_new`], `qty` must be null or
+/// valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn orderbook_total_qty(
+    book: *const OrderbookHandle,
+    price: u64,
+    side: u8,
+    qty: *mut u64,
+) -> OrderbookStatus {
+    let (Some(handle), false) = (book.as_ref(), qty.is_null()) else {
+        return OrderbookStatus::NullPointer;
+    };
+    let side = match OrderbookSide::try_from(side) {
+        Ok(side) => side,
+        Err(status) => return status,
+    };
+    match handle.book.get_total_qty(price, side.into()) {
+        Some(total) => {
+            qty.write(total);
+            OrderbookStatus::Ok
+        }
+        None => OrderbookStatus::Empty,
+    }
+}
+
+/// Drains orders on the given `price` and side up to `qty`, see
+/// [`OrderBook::get_orders_till_qty`]
+///
+/// On success `orders` points to `len` drained orders in FIFO order and `drained_qty` holds their
+/// total quantity. The orders are owned by the handle and stay valid until the next call on it.
+///
+/// Returns `ORDERBOOK_STATUS_EMPTY` if there are no orders on the price
+///
+/// # Safety
+///
+/// `book` must be null or a live handle returned by [`orderbook_new`], the out pointers must be
+/// null or valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn orderbook_drain(
+    book: *mut OrderbookHandle,
+    price: u64,
+    side: u8,
+    qty: u64,
+    orders: *mut *const OrderbookOrder,
+    len: *mut usize,
+    drained_qty: *mut u64,
+) -> OrderbookStatus {
+    let Some(handle) = book.as_mut() else {
+        return OrderbookStatus::NullPointer;
+    };
+    if orders.is_null() || len.is_null() || drained_qty.is_null() {
+        return OrderbookStatus::NullPointer;
+    }
+    let side = match OrderbookSide::try_from(side) {
+        Ok(side) => side,
+        Err(status) => return status,
+    };
+    handle.drained.clear();
+    handle.drained_out.clear();
+    let Some(total) =
+        handle
+            .book
+            .get_orders_till_qty_into(price, side.into(), qty, &mut handle.drained)
+    else {
+        return OrderbookStatus::Empty;
+    };
+    handle.drained_out.extend(
+        handle
+            .drained
+            .iter()
+            .map(|&order| OrderbookOrder::from(order)),
+    );
+    orders.write(handle.drained_out.as_ptr());
+    len.write(handle.drained_out.len());
+    drained_qty.write(total);
+    OrderbookStatus::Ok
+}
+
+/// Gets a static, nul terminated description of a status
+///
+/// Takes the status as a plain `int32_t`, values that are not an `OrderbookStatus` get a
+/// description of their own
+#[no_mangle]
+pub extern "C" fn orderbook_status_message(status: i32) -> *const c_char {
+    let message: &'static [u8] = match OrderbookStatus::ALL
+        .into_iter()
+        .find(|&known| known as i32 == status)
+    {
+        Some(OrderbookStatus::Ok) => b"ok\0",
+        Some(OrderbookStatus::UnknownId) => b"OrderId not found\0",
+        Some(OrderbookStatus::DuplicateOrderId) => b"Duplicate OrderId\0",
+        Some(OrderbookStatus::InvalidPrice) => b"Price is not accepted by the book\0",
+        Some(OrderbookStatus::Empty) => b"No orders\0",
+        Some(OrderbookStatus::NullPointer) => b"Null pointer argument\0",
+        Some(OrderbookStatus::InvalidQty) => b"Qty is zero\0",
+        Some(OrderbookStatus::InvalidSide) => b"Side is neither ask nor bid\0",
+        Some(OrderbookStatus::Other) => b"Book error\0",
+        None => b"Unknown status\0",
+    };
+    message.as_ptr().cast()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::ptr;
+
+    #[test]
+    fn maps_errors_to_status() {
+        // Setup
+        let book = orderbook_new();
+        let order = OrderbookOrder {
+            price: 100,
+            qty: 10,
+            side: OrderbookSide::Bid as u8,
+            id: 1,
+        };
+
+        // Act
+        let inserted = unsafe { orderbook_insert(book, order) };
+        let duplicate = unsafe { orderbook_insert(book, order) };
+        let unknown = unsafe { orderbook_remove(book, 2) };
+        let null = unsafe { orderbook_remove(ptr::null_mut(), 1) };
+        unsafe { orderbook_free(book) };
+
+        // Assert
+        assert_eq!(inserted, OrderbookStatus::Ok);
+        assert_eq!(duplicate, OrderbookStatus::DuplicateOrderId);
+        assert_eq!(unknown, OrderbookStatus::UnknownId);
+        assert_eq!(null, OrderbookStatus::NullPointer);
+    }
+
+    #[test]
+    fn drain_reuses_handle_buffer() {
+        // Setup
+        let book = orderbook_new();
+        for id in 1..=3 {
+            let order = OrderbookOrder {
+                price: 100,
+                qty: 10,
+                side: OrderbookSide::Ask as u8,
+                id,
+            };
+            assert_eq!(
+                unsafe { orderbook_insert(book, order) },
+                OrderbookStatus::Ok
+            );
+        }
+        let mut orders = ptr::null();
+        let mut len = 0;
+        let mut drained_qty = 0;
+
+        // Act
+        let status = unsafe {
+            orderbook_drain(
+                book,
+                100,
+                OrderbookSide::Ask as u8,
+                15,
+                &mut orders,
+                &mut len,
+                &mut drained_qty,
+            )
+        };
+        let drained = unsafe { std::slice::from_raw_parts(orders, len) }.to_vec();
+        let mut remaining = 0;
+        let total =
+            unsafe { orderbook_total_qty(book, 100, OrderbookSide::Ask as u8, &mut remaining) };
+        unsafe { orderbook_free(book) };
+
+        // Assert
+        assert_eq!(status, OrderbookStatus::Ok);
+        assert_eq!(drained_qty, 15);
+        let ids: Vec<(u64, u64)> = drained.iter().map(|order| (order.id, order.qty)).collect();
+        assert_eq!(ids, vec![(1, 10), (2, 5)]);
+        assert_eq!(total, OrderbookStatus::Ok);
+        assert_eq!(remaining, 15);
+    }
+
+    #[test]
+    fn rejects_invalid_side() {
+        // Setup
+        let book = orderbook_new();
+        let order = OrderbookOrder {
+            price: 100,
+            qty: 10,
+            side: 2,
+            id: 1,
+        };
+        let mut price = 0;
+
+        // Act
+        let inserted = unsafe { orderbook_insert(book, order) };
+        let best = unsafe { orderbook_best_price(book, u8::MAX, &mut price) };
+        let empty = unsafe { orderbook_best_price(book, OrderbookSide::Bid as u8, &mut price) };
+        unsafe { orderbook_free(book) };
+
+        // Assert
+        assert_eq!(inserted, OrderbookStatus::InvalidSide);
+        assert_eq!(best, OrderbookStatus::InvalidSide);
+        assert_eq!(empty, OrderbookStatus::Empty);
+    }
+}
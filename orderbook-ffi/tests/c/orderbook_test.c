@@ -0,0 +1,78 @@
+/* Exercises the C ABI through the generated header, exits non-zero on the first failure */
+#include <stdio.h>
+#include <string.h>
+
+#include "orderbook.h"
+
+#define CHECK(cond)                                                     \
+    do {                                                                \
+        if (!(cond)) {                                                  \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__,      \
+                    __LINE__, #cond);                                   \
+            return 1;                                                   \
+        }                                                               \
+    } while (0)
+
+static OrderbookOrder order(uint64_t price, uint64_t qty, OrderbookSide side, uint64_t id) {
+    OrderbookOrder order = {.price = price, .qty = qty, .side = side, .id = id};
+    return order;
+}
+
+int main(void) {
+    OrderbookHandle *book = orderbook_new();
+    CHECK(book != NULL);
+
+    /* Insert */
+    CHECK(orderbook_insert(book, order(101, 10, ORDERBOOK_SIDE_ASK, 1)) == ORDERBOOK_STATUS_OK);
+    CHECK(orderbook_insert(book, order(101, 20, ORDERBOOK_SIDE_ASK, 2)) == ORDERBOOK_STATUS_OK);
+    CHECK(orderbook_insert(book, order(102, 30, ORDERBOOK_SIDE_ASK, 3)) == ORDERBOOK_STATUS_OK);
+    CHECK(orderbook_insert(book, order(99, 40, ORDERBOOK_SIDE_BID, 4)) == ORDERBOOK_STATUS_OK);
+    CHECK(orderbook_insert(book, order(99, 1, ORDERBOOK_SIDE_BID, 4)) ==
+          ORDERBOOK_STATUS_DUPLICATE_ORDER_ID);
+
+    /* Best price and total quantity */
+    uint64_t price = 0;
+    CHECK(orderbook_best_price(book, ORDERBOOK_SIDE_ASK, &price) == ORDERBOOK_STATUS_OK);
+    CHECK(price == 101);
+    CHECK(orderbook_best_price(book, ORDERBOOK_SIDE_BID, &price) == ORDERBOOK_STATUS_OK);
+    CHECK(price == 99);
+    uint64_t qty = 0;
+    CHECK(orderbook_total_qty(book, 101, ORDERBOOK_SIDE_ASK, &qty) == ORDERBOOK_STATUS_OK);
+    CHECK(qty == 30);
+    CHECK(orderbook_total_qty(book, 100, ORDERBOOK_SIDE_ASK, &qty) == ORDERBOOK_STATUS_EMPTY);
+    CHECK(qty == 30);
+
+    /* Drain */
+    const OrderbookOrder *drained = NULL;
+    size_t len = 0;
+    uint64_t drained_qty = 0;
+    CHECK(orderbook_drain(book, 101, ORDERBOOK_SIDE_ASK, 25, &drained, &len, &drained_qty) ==
+          ORDERBOOK_STATUS_OK);
+    CHECK(len == 2);
+    CHECK(drained_qty == 25);
+    CHECK(drained[0].id == 1 && drained[0].qty == 10);
+    CHECK(drained[1].id == 2 && drained[1].qty == 15 && drained[1].side == ORDERBOOK_SIDE_ASK);
+    CHECK(orderbook_total_qty(book, 101, ORDERBOOK_SIDE_ASK, &qty) == ORDERBOOK_STATUS_OK);
+    CHECK(qty == 5);
+    CHECK(orderbook_drain(book, 100, ORDERBOOK_SIDE_ASK, 25, &drained, &len, &drained_qty) ==
+          ORDERBOOK_STATUS_EMPTY);
+
+    /* Remove */
+    CHECK(orderbook_remove(book, 1) == ORDERBOOK_STATUS_UNKNOWN_ID);
+    CHECK(orderbook_remove(book, 2) == ORDERBOOK_STATUS_OK);
+    CHECK(orderbook_best_price(book, ORDERBOOK_SIDE_ASK, &price) == ORDERBOOK_STATUS_OK);
+    CHECK(price == 102);
+
+    /* Errors */
+    CHECK(orderbook_remove(NULL, 3) == ORDERBOOK_STATUS_NULL_POINTER);
+    CHECK(orderbook_best_price(book, ORDERBOOK_SIDE_ASK, NULL) == ORDERBOOK_STATUS_NULL_POINTER);
+    CHECK(orderbook_insert(book, order(99, 1, 2, 5)) == ORDERBOOK_STATUS_INVALID_SIDE);
+    CHECK(orderbook_best_price(book, 255, &price) == ORDERBOOK_STATUS_INVALID_SIDE);
+    CHECK(price == 102);
+    CHECK(strcmp(orderbook_status_message(ORDERBOOK_STATUS_UNKNOWN_ID), "OrderId not found") == 0);
+    CHECK(strcmp(orderbook_status_message(-1), "Unknown status") == 0);
+
+    orderbook_free(book);
+    orderbook_free(NULL);
+    return 0;
+}
@@ -0,0 +1,65 @@
+//! Compiles `tests/c/orderbook_test.c` against the generated header and the cdylib, then runs it
+//!
+//! The header is generated into `OUT_DIR`, the copy in `include/` is checked to match it
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Directory of the cdylib, the parent of the `deps` directory holding the test binary
+fn library_dir() -> PathBuf {
+    let exe = env::current_exe().unwrap();
+    exe.parent().unwrap().parent().unwrap().to_path_buf()
+}
+
+#[test]
+fn c_program_passes() {
+    // Setup
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = library_dir();
+    let exe = Path::new(env!("CARGO_TARGET_TMPDIR")).join("orderbook_test");
+    let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+
+    // Act
+    let compiled = Command::new(&compiler)
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(env!("OUT_DIR"))
+        .arg(crate_dir.join("tests/c/orderbook_test.c"))
+        .arg("-o")
+        .arg(&exe)
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+        .arg("-lorderbook_ffi")
+        .status()
+        .unwrap_or_else(|err| panic!("run C compiler {compiler}: {err}"));
+    assert!(compiled.success(), "compiling the C test program failed");
+    let output = Command::new(&exe).output().unwrap();
+
+    // Assert
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn committed_header_is_up_to_date() {
+    // Setup
+    let committed = Path::new(env!("CARGO_MANIFEST_DIR")).join("include/orderbook.h");
+    let generated = Path::new(env!("OUT_DIR")).join("orderbook.h");
+
+    // Act
+    let committed = fs::read_to_string(committed).unwrap();
+    let generated = fs::read_to_string(generated).unwrap();
+
+    // Assert
+    assert!(
+        committed == generated,
+        "include/orderbook.h is stale, copy the header generated in OUT_DIR over it"
+    );
+}
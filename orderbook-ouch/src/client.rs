@@ -0,0 +1,49 @@
+use crate::message::{Request, Response};
+use crate::server::{read_frame, write_frame};
+use std::io;
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// Order-entry client for a [`Server`](crate::Server)
+pub struct Client {
+    stream: TcpStream,
+    buf: Vec<u8>,
+}
+
+impl Client {
+    /// Function connects to the server at the given address
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the connection fails
+    pub async fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            buf: Vec::new(),
+        })
+    }
+
+    /// Function sends a [`Request`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if writing to the connection fails
+    pub async fn send(&mut self, request: &Request) -> io::Result<()> {
+        self.buf.clear();
+        request.encode(&mut self.buf);
+        write_frame(&mut self.stream, &self.buf).await
+    }
+
+    /// Function receives the next [`Response`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the connection is closed or the response is malformed
+    pub async fn recv(&mut self) -> io::Result<Response> {
+        if !read_frame(&mut self.stream, &mut self.buf).await? {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Response::decode(&self.buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -0,0 +1,347 @@
+use crate::message::{RejectReason, Request, Response, Token};
+use orderbook::{Order, OrderBook, OrderBookError, Side, Trade};
+use std::collections::HashMap;
+
+/// Identifies a client connection
+pub type SessionId = u64;
+
+/// Maps protocol [`Request`]'s onto an [`OrderBook`], tracking which session owns each order
+pub struct Engine {
+    book: OrderBook,
+    owners: HashMap<Token, SessionId>,
+    match_number: u64,
+}
+
+impl Engine {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            book: OrderBook::new(),
+            owners: HashMap::new(),
+            match_number: 0,
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    /// Function handles a [`Request`] from the given session
+    ///
+    /// Returns the [`Response`]'s in order, each addressed to a session, executions of resting
+    /// orders are addressed to the session that entered them
+    pub fn handle(&mut self, session: SessionId, request: Request) -> Vec<(SessionId, Response)> {
+        let mut out = Vec::new();
+        match request {
+            Request::EnterOrder {
+                token,
+                side,
+                qty,
+                price,
+            } => {
+                if qty == 0 {
+                    out.push(reject(session, token, RejectReason::InvalidQty));
+                    return out;
+                }
+                match self.book.match_order(Order::new(price, qty, side, token)) {
+                    Ok(trades) => {
+                        out.push((
+                            session,
+                            Response::Accepted {
+                                token,
+                                side,
+                                qty,
+                                price,
+                            },
+                        ));
+                        self.executions(session, token, &trades, &mut out);
+                    }
+                    Err(e) => out.push(reject_err(session, token, e)),
+                }
+            }
+            Request::Replace {
+                token,
+                new_token,
+                qty,
+                price,
+            } => {
+                let Some(side) = self.owned_side(session, token) else {
+                    out.push(reject(session, token, RejectReason::UnknownId));
+                    return out;
+                };
+                if qty == 0 {
+                    out.push(reject(session, new_token, RejectReason::InvalidQty));
+                    return out;
+                }
+                if new_token != token && self.book.get_order(new_token).is_some() {
+                    out.push(reject(session, new_token, RejectReason::DuplicateOrderId));
+                    return out;
+                }
+                if let Err(e) = self.book.remove(token) {
+                    out.push(reject_err(session, token, e));
+                    return out;
+                }
+                self.owners.remove(&token);
+                out.push((
+                    session,
+                    Response::Replaced {
+                        token: new_token,
+                        previous_token: token,
+                        side,
+                        qty,
+                        price,
+                    },
+                ));
+                match self
+                    .book
+                    .match_order(Order::new(price, qty, side, new_token))
+                {
+                    Ok(trades) => self.executions(session, new_token, &trades, &mut out),
+                    Err(e) => out.push(reject_err(session, new_token, e)),
+                }
+            }
+            Request::Cancel { token, qty } => {
+                if self.owned_side(session, token).is_none() {
+                    out.push(reject(session, token, RejectReason::UnknownId));
+                    return out;
+                }
+                let resting = self.book.get_order(token).map_or(0, |order| order.qty);
+                let decrement = resting.saturating_sub(qty);
+                if decrement > 0 {
+                    if let Err(e) = self.book.reduce(token, decrement) {
+                        out.push(reject_err(session, token, e));
+                        return out;
+                    }
+                    self.release(token);
+                }
+                out.push((session, Response::Canceled { token, decrement }));
+            }
+        }
+        out
+    }
+
+    /// Function gets the `Side` of a resting order if it is owned by the given session
+    fn owned_side(&self, session: SessionId, token: Token) -> Option<Side> {
+        match self.owners.get(&token) {
+            Some(&owner) if owner == session => self.book.get_order(token).map(|order| order.side),
+            _ => None,
+        }
+    }
+
+    /// Reports every [`Trade`] to both the incoming and the resting order's session
+    fn executions(
+        &mut self,
+        session: SessionId,
+        token: Token,
+        trades: &[Trade],
+        out: &mut Vec<(SessionId, Response)>,
+    ) {
+        for trade in trades {
+            self.match_number += 1;
+            let execution = |token| Response::Executed {
+                token,
+                qty: trade.qty,
+                price: trade.price,
+                match_number: self.match_number,
+            };
+            out.push((session, execution(trade.taker_id)));
+            if let Some(&owner) = self.owners.get(&trade.maker_id) {
+                out.push((owner, execution(trade.maker_id)));
+            }
+            self.release(trade.maker_id);
+        }
+        if self.book.get_order(token).is_some() {
+            self.owners.insert(token, session);
+        }
+    }
+
+    /// Forgets the owner of an order that is no longer resting
+    fn release(&mut self, token: Token) {
+        if self.book.get_order(token).is_none() {
+            self.owners.remove(&token);
+        }
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn reject(session: SessionId, token: Token, reason: RejectReason) -> (SessionId, Response) {
+    (session, Response::Rejected { token, reason })
+}
+
+fn reject_err(session: SessionId, token: Token, e: OrderBookError) -> (SessionId, Response) {
+    reject(session, token, e.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Engine;
+    use crate::message::{RejectReason, Request, Response};
+    use orderbook::Side;
+
+    fn enter(token: u64, side: Side, qty: u64, price: u64) -> Request {
+        Request::EnterOrder {
+            token,
+            side,
+            qty,
+            price,
+        }
+    }
+
+    #[test]
+    fn enter_and_execute() {
+        // Setup
+        let mut engine = Engine::new();
+        engine.handle(1, enter(1, Side::Ask, 420, 69));
+
+        // Act
+        let out = engine.handle(2, enter(2, Side::Bid, 20, 70));
+
+        // Assert
+        assert_eq!(
+            out,
+            vec![
+                (
+                    2,
+                    Response::Accepted {
+                        token: 2,
+                        side: Side::Bid,
+                        qty: 20,
+                        price: 70
+                    }
+                ),
+                (
+                    2,
+                    Response::Executed {
+                        token: 2,
+                        qty: 20,
+                        price: 69,
+                        match_number: 1
+                    }
+                ),
+                (
+                    1,
+                    Response::Executed {
+                        token: 1,
+                        qty: 20,
+                        price: 69,
+                        match_number: 1
+                    }
+                ),
+            ]
+        );
+        assert_eq!(engine.book().get_total_qty(69, Side::Ask), Some(400));
+        assert!(engine.book().get_order(2).is_none());
+    }
+
+    #[test]
+    fn reject_duplicate_and_unknown() {
+        // Setup
+        let mut engine = Engine::new();
+        engine.handle(1, enter(1, Side::Ask, 420, 69));
+
+        // Act
+        let duplicate = engine.handle(1, enter(1, Side::Ask, 420, 69));
+        let unknown = engine.handle(1, Request::Cancel { token: 7, qty: 0 });
+        let not_owned = engine.handle(2, Request::Cancel { token: 1, qty: 0 });
+        let zero = engine.handle(1, enter(3, Side::Ask, 0, 69));
+
+        // Assert
+        let rejected = |token, reason| vec![(1, Response::Rejected { token, reason })];
+        assert_eq!(duplicate, rejected(1, RejectReason::DuplicateOrderId));
+        assert_eq!(unknown, rejected(7, RejectReason::UnknownId));
+        assert_eq!(
+            not_owned,
+            vec![(
+                2,
+                Response::Rejected {
+                    token: 1,
+                    reason: RejectReason::UnknownId
+                }
+            )]
+        );
+        assert_eq!(zero, rejected(3, RejectReason::InvalidQty));
+        assert_eq!(engine.book().get_total_qty(69, Side::Ask), Some(420));
+    }
+
+    #[test]
+    fn replace_and_cancel() {
+        // Setup
+        let mut engine = Engine::new();
+        engine.handle(1, enter(1, Side::Bid, 420, 69));
+        engine.handle(1, enter(2, Side::Bid, 420, 68));
+
+        // Act
+        let duplicate = engine.handle(
+            1,
+            Request::Replace {
+                token: 1,
+                new_token: 2,
+                qty: 100,
+                price: 70,
+            },
+        );
+        let replaced = engine.handle(
+            1,
+            Request::Replace {
+                token: 1,
+                new_token: 3,
+                qty: 100,
+                price: 70,
+            },
+        );
+        let reduced = engine.handle(1, Request::Cancel { token: 3, qty: 40 });
+        let canceled = engine.handle(1, Request::Cancel { token: 2, qty: 0 });
+
+        // Assert
+        assert_eq!(
+            duplicate,
+            vec![(
+                1,
+                Response::Rejected {
+                    token: 2,
+                    reason: RejectReason::DuplicateOrderId
+                }
+            )]
+        );
+        assert_eq!(
+            replaced,
+            vec![(
+                1,
+                Response::Replaced {
+                    token: 3,
+                    previous_token: 1,
+                    side: Side::Bid,
+                    qty: 100,
+                    price: 70
+                }
+            )]
+        );
+        assert_eq!(
+            reduced,
+            vec![(
+                1,
+                Response::Canceled {
+                    token: 3,
+                    decrement: 60
+                }
+            )]
+        );
+        assert_eq!(
+            canceled,
+            vec![(
+                1,
+                Response::Canceled {
+                    token: 2,
+                    decrement: 420
+                }
+            )]
+        );
+        assert_eq!(engine.book().get_depth(Side::Bid), vec![(70, 40)]);
+    }
+}
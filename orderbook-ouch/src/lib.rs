@@ -0,0 +1,41 @@
+//! orderbook-ouch
+//!
+//! A compact binary order-entry protocol modelled on NASDAQ OUCH, served over TCP on top of an
+//! [`orderbook::OrderBook`].
+//!
+//! Every message is prefixed with its length as a big-endian `u16`, all integers are big-endian.
+//! Orders are identified by a client assigned [`Token`], which is used as the `OrderId` in the
+//! book, so tokens must be unique among resting orders.
+//!
+//! Example:
+//! ```no_run
+//! use orderbook::Side;
+//! use orderbook_ouch::{Client, Request, Server};
+//!
+//! # async fn run() -> std::io::Result<()> {
+//! let server = Server::bind("127.0.0.1:0").await?;
+//! let addr = server.local_addr()?;
+//! tokio::spawn(server.run());
+//!
+//! let mut client = Client::connect(addr).await?;
+//! client
+//!     .send(&Request::EnterOrder {
+//!         token: 1,
+//!         side: Side::Bid,
+//!         qty: 420,
+//!         price: 69,
+//!     })
+//!     .await?;
+//! println!("{:?}", client.recv().await?);
+//! # Ok(())
+//! # }
+//! ```
+mod client;
+mod engine;
+mod message;
+mod server;
+
+pub use client::Client;
+pub use engine::{Engine, SessionId};
+pub use message::{ProtocolError, RejectReason, Request, Response, Token};
+pub use server::Server;
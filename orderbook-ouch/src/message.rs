@@ -0,0 +1,421 @@
+use orderbook::{OrderBookError, Side};
+use thiserror::Error;
+
+/// Client assigned order token, used as the `OrderId` in the order book
+pub type Token = u64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ProtocolError {
+    #[error("Empty message")]
+    Empty,
+    #[error("Invalid length {len} for message type {kind:?}")]
+    InvalidLength { kind: char, len: usize },
+    #[error("Unknown message type {0:?}")]
+    UnknownMessageType(char),
+    #[error("Invalid side {0}")]
+    InvalidSide(u8),
+    #[error("Invalid reason {0}")]
+    InvalidReason(u8),
+}
+
+/// Inbound message, sent by the client
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// `O`, enters a new limit order
+    EnterOrder {
+        token: Token,
+        side: Side,
+        qty: u64,
+        price: u64,
+    },
+    /// `U`, replaces a resting order with a new order on the same side, losing time priority
+    Replace {
+        token: Token,
+        new_token: Token,
+        qty: u64,
+        price: u64,
+    },
+    /// `X`, reduces a resting order to the given `qty`, a `qty` of zero cancels the order
+    Cancel { token: Token, qty: u64 },
+}
+
+/// Outbound message, sent by the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Response {
+    /// `A`, the order was entered
+    Accepted {
+        token: Token,
+        side: Side,
+        qty: u64,
+        price: u64,
+    },
+    /// `U`, the order was replaced
+    Replaced {
+        token: Token,
+        previous_token: Token,
+        side: Side,
+        qty: u64,
+        price: u64,
+    },
+    /// `E`, the order was (partially) executed
+    Executed {
+        token: Token,
+        qty: u64,
+        price: u64,
+        match_number: u64,
+    },
+    /// `C`, the order was reduced by `decrement`
+    Canceled { token: Token, decrement: u64 },
+    /// `J`, the request was rejected
+    Rejected { token: Token, reason: RejectReason },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RejectReason {
+    /// `D`, the token is already in use
+    DuplicateOrderId,
+    /// `U`, the token does not belong to a resting order of this session
+    UnknownId,
+    /// `Z`, the quantity is zero
+    InvalidQty,
+    /// `X`, the price is not accepted by the book
+    InvalidPrice,
+    /// `O`, any other error of the book
+    Other,
+}
+
+impl RejectReason {
+    fn code(self) -> u8 {
+        match self {
+            RejectReason::DuplicateOrderId => b'D',
+            RejectReason::UnknownId => b'U',
+            RejectReason::InvalidQty => b'Z',
+            RejectReason::InvalidPrice => b'X',
+            RejectReason::Other => b'O',
+        }
+    }
+
+    fn from_code(code: u8) -> Result<Self, ProtocolError> {
+        match code {
+            b'D' => Ok(RejectReason::DuplicateOrderId),
+            b'U' => Ok(RejectReason::UnknownId),
+            b'Z' => Ok(RejectReason::InvalidQty),
+            b'X' => Ok(RejectReason::InvalidPrice),
+            b'O' => Ok(RejectReason::Other),
+            code => Err(ProtocolError::InvalidReason(code)),
+        }
+    }
+}
+
+impl From<OrderBookError> for RejectReason {
+    fn from(e: OrderBookError) -> Self {
+        match e {
+            OrderBookError::DuplicateOrderId(..) => RejectReason::DuplicateOrderId,
+            OrderBookError::UnknownId(..) => RejectReason::UnknownId,
+            OrderBookError::InvalidQty(..) => RejectReason::InvalidQty,
+            OrderBookError::InvalidPrice { .. } => RejectReason::InvalidPrice,
+            _ => RejectReason::Other,
+        }
+    }
+}
+
+impl Request {
+    /// Function appends the encoded message, without length prefix, to `buf`
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        match *self {
+            Request::EnterOrder {
+                token,
+                side,
+                qty,
+                price,
+            } => {
+                buf.push(b'O');
+                put(buf, token);
+                buf.push(encode_side(side));
+                put(buf, qty);
+                put(buf, price);
+            }
+            Request::Replace {
+                token,
+                new_token,
+                qty,
+                price,
+            } => {
+                buf.push(b'U');
+                put(buf, token);
+                put(buf, new_token);
+                put(buf, qty);
+                put(buf, price);
+            }
+            Request::Cancel { token, qty } => {
+                buf.push(b'X');
+                put(buf, token);
+                put(buf, qty);
+            }
+        }
+    }
+
+    /// Function decodes a single message, without length prefix
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] on an unknown message type or a length not matching the message type
+    pub fn decode(buf: &[u8]) -> Result<Self, ProtocolError> {
+        let (kind, body) = split(buf, |kind| match kind {
+            b'O' => Some(25),
+            b'U' => Some(32),
+            b'X' => Some(16),
+            _ => None,
+        })?;
+        let msg = match kind {
+            b'O' => Request::EnterOrder {
+                token: get(body, 0),
+                side: decode_side(body[8])?,
+                qty: get(body, 9),
+                price: get(body, 17),
+            },
+            b'U' => Request::Replace {
+                token: get(body, 0),
+                new_token: get(body, 8),
+                qty: get(body, 16),
+                price: get(body, 24),
+            },
+            _ => Request::Cancel {
+                token: get(body, 0),
+                qty: get(body, 8),
+            },
+        };
+        Ok(msg)
+    }
+}
+
+impl Response {
+    /// Function appends the encoded message, without length prefix, to `buf`
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        match *self {
+            Response::Accepted {
+                token,
+                side,
+                qty,
+                price,
+            } => {
+                buf.push(b'A');
+                put(buf, token);
+                buf.push(encode_side(side));
+                put(buf, qty);
+                put(buf, price);
+            }
+            Response::Replaced {
+                token,
+                previous_token,
+                side,
+                qty,
+                price,
+            } => {
+                buf.push(b'U');
+                put(buf, token);
+                put(buf, previous_token);
+                buf.push(encode_side(side));
+                put(buf, qty);
+                put(buf, price);
+            }
+            Response::Executed {
+                token,
+                qty,
+                price,
+                match_number,
+            } => {
+                buf.push(b'E');
+                put(buf, token);
+                put(buf, qty);
+                put(buf, price);
+                put(buf, match_number);
+            }
+            Response::Canceled { token, decrement } => {
+                buf.push(b'C');
+                put(buf, token);
+                put(buf, decrement);
+            }
+            Response::Rejected { token, reason } => {
+                buf.push(b'J');
+                put(buf, token);
+                buf.push(reason.code());
+            }
+        }
+    }
+
+    /// Function decodes a single message, without length prefix
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] on an unknown message type or a length not matching the message type
+    pub fn decode(buf: &[u8]) -> Result<Self, ProtocolError> {
+        let (kind, body) = split(buf, |kind| match kind {
+            b'A' => Some(25),
+            b'U' => Some(33),
+            b'E' => Some(32),
+            b'C' => Some(16),
+            b'J' => Some(9),
+            _ => None,
+        })?;
+        let msg = match kind {
+            b'A' => Response::Accepted {
+                token: get(body, 0),
+                side: decode_side(body[8])?,
+                qty: get(body, 9),
+                price: get(body, 17),
+            },
+            b'U' => Response::Replaced {
+                token: get(body, 0),
+                previous_token: get(body, 8),
+                side: decode_side(body[16])?,
+                qty: get(body, 17),
+                price: get(body, 25),
+            },
+            b'E' => Response::Executed {
+                token: get(body, 0),
+                qty: get(body, 8),
+                price: get(body, 16),
+                match_number: get(body, 24),
+            },
+            b'C' => Response::Canceled {
+                token: get(body, 0),
+                decrement: get(body, 8),
+            },
+            _ => Response::Rejected {
+                token: get(body, 0),
+                reason: RejectReason::from_code(body[8])?,
+            },
+        };
+        Ok(msg)
+    }
+}
+
+/// Splits a message in its type and body, checking the body length
+fn split(buf: &[u8], body_len: impl Fn(u8) -> Option<usize>) -> Result<(u8, &[u8]), ProtocolError> {
+    let (&kind, body) = buf.split_first().ok_or(ProtocolError::Empty)?;
+    let expected = body_len(kind).ok_or(ProtocolError::UnknownMessageType(kind as char))?;
+    if body.len() != expected {
+        return Err(ProtocolError::InvalidLength {
+            kind: kind as char,
+            len: buf.len(),
+        });
+    }
+    Ok((kind, body))
+}
+
+fn put(buf: &mut Vec<u8>, value: u64) {
+    buf.extend_from_slice(&value.to_be_bytes());
+}
+
+fn get(body: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(body[offset..offset + 8].try_into().expect("length checked"))
+}
+
+fn encode_side(side: Side) -> u8 {
+    match side {
+        Side::Bid => b'B',
+        Side::Ask => b'S',
+    }
+}
+
+fn decode_side(side: u8) -> Result<Side, ProtocolError> {
+    match side {
+        b'B' => Ok(Side::Bid),
+        b'S' => Ok(Side::Ask),
+        side => Err(ProtocolError::InvalidSide(side)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ProtocolError, RejectReason, Request, Response};
+    use orderbook::Side;
+
+    #[test]
+    fn request_round_trip() {
+        // Setup
+        let requests = [
+            Request::EnterOrder {
+                token: 1,
+                side: Side::Bid,
+                qty: 420,
+                price: 69,
+            },
+            Request::Replace {
+                token: 1,
+                new_token: 2,
+                qty: 20,
+                price: 70,
+            },
+            Request::Cancel { token: 2, qty: 0 },
+        ];
+
+        for request in requests {
+            let mut buf = vec![];
+
+            // Act
+            request.encode(&mut buf);
+
+            // Assert
+            assert_eq!(Request::decode(&buf), Ok(request));
+        }
+    }
+
+    #[test]
+    fn response_round_trip() {
+        // Setup
+        let responses = [
+            Response::Accepted {
+                token: 1,
+                side: Side::Ask,
+                qty: 420,
+                price: 69,
+            },
+            Response::Replaced {
+                token: 2,
+                previous_token: 1,
+                side: Side::Ask,
+                qty: 20,
+                price: 70,
+            },
+            Response::Executed {
+                token: 2,
+                qty: 10,
+                price: 70,
+                match_number: 1,
+            },
+            Response::Canceled {
+                token: 2,
+                decrement: 10,
+            },
+            Response::Rejected {
+                token: 3,
+                reason: RejectReason::DuplicateOrderId,
+            },
+        ];
+
+        for response in responses {
+            let mut buf = vec![];
+
+            // Act
+            response.encode(&mut buf);
+
+            // Assert
+            assert_eq!(Response::decode(&buf), Ok(response));
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(Request::decode(&[]), Err(ProtocolError::Empty));
+        assert_eq!(
+            Request::decode(b"Q"),
+            Err(ProtocolError::UnknownMessageType('Q'))
+        );
+        assert_eq!(
+            Request::decode(b"X1234"),
+            Err(ProtocolError::InvalidLength { kind: 'X', len: 5 })
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use crate::engine::{Engine, SessionId};
+use crate::message::{Request, Response};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::mpsc;
+
+enum Command {
+    Connect(SessionId, mpsc::UnboundedSender<Response>),
+    Request(SessionId, Request),
+    Disconnect(SessionId),
+}
+
+/// TCP order-entry server, every connection is a session on a single shared [`Engine`]
+pub struct Server {
+    listener: TcpListener,
+}
+
+impl Server {
+    /// Function binds the server to the given address
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the address can not be bound
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Ok(Self { listener })
+    }
+
+    /// Function gets the address the server is bound to
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the address can not be retrieved from the socket
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Function accepts connections forever, a connection that fails to be accepted is logged
+    /// and skipped so the other sessions keep running
+    pub async fn run(self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_engine(rx));
+        let mut next_session: SessionId = 1;
+        loop {
+            let stream = match self.listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept a connection: {e}");
+                    continue;
+                }
+            };
+            // Nagle only delays responses, the session works without disabling it
+            let _ = stream.set_nodelay(true);
+            let session = next_session;
+            next_session += 1;
+            tokio::spawn(run_session(session, stream, tx.clone()));
+        }
+    }
+}
+
+/// Owns the [`Engine`] and routes its responses to the sessions
+async fn run_engine(mut rx: mpsc::UnboundedReceiver<Command>) {
+    let mut engine = Engine::new();
+    let mut sessions = HashMap::new();
+    while let Some(command) = rx.recv().await {
+        match command {
+            Command::Connect(session, tx) => {
+                sessions.insert(session, tx);
+            }
+            Command::Request(session, request) => {
+                for (session, response) in engine.handle(session, request) {
+                    if let Some(tx) = sessions.get(&session) {
+                        // A closed session is removed by its disconnect command
+                        let _ = tx.send(response);
+                    }
+                }
+            }
+            Command::Disconnect(session) => {
+                sessions.remove(&session);
+            }
+        }
+    }
+}
+
+async fn run_session(
+    session: SessionId,
+    stream: TcpStream,
+    engine: mpsc::UnboundedSender<Command>,
+) {
+    let (mut reader, mut writer) = stream.into_split();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    if engine.send(Command::Connect(session, tx)).is_err() {
+        return;
+    }
+    let writer = tokio::spawn(async move {
+        let mut buf = Vec::new();
+        while let Some(response) = rx.recv().await {
+            buf.clear();
+            response.encode(&mut buf);
+            if write_frame(&mut writer, &buf).await.is_err() {
+                break;
+            }
+        }
+    });
+    let mut buf = Vec::new();
+    // Malformed messages end the session
+    while let Ok(true) = read_frame(&mut reader, &mut buf).await {
+        let Ok(request) = Request::decode(&buf) else {
+            break;
+        };
+        if engine.send(Command::Request(session, request)).is_err() {
+            break;
+        }
+    }
+    let _ = engine.send(Command::Disconnect(session));
+    let _ = writer.await;
+}
+
+/// Function reads a message prefixed with its length as a big-endian `u16` into `buf`
+///
+/// Returns `false` if the stream was closed before the next message
+pub(crate) async fn read_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+    buf: &mut Vec<u8>,
+) -> io::Result<bool> {
+    let mut len = [0; 2];
+    match reader.read_exact(&mut len).await {
+        Ok(..) => (),
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(false),
+        Err(e) => return Err(e),
+    }
+    buf.resize(usize::from(u16::from_be_bytes(len)), 0);
+    reader.read_exact(buf).await?;
+    Ok(true)
+}
+
+/// Function writes a message prefixed with its length as a big-endian `u16`
+pub(crate) async fn write_frame(
+    writer: &mut (impl AsyncWrite + Unpin),
+    msg: &[u8],
+) -> io::Result<()> {
+    let len =
+        u16::try_from(msg.len()).map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let mut frame = Vec::with_capacity(2 + msg.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(msg);
+    writer.write_all(&frame).await
+}
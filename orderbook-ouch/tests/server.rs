@@ -0,0 +1,95 @@
+use orderbook::Side;
+use orderbook_ouch::{Client, RejectReason, Request, Response, Server};
+use std::net::SocketAddr;
+
+async fn start() -> SocketAddr {
+    let server = Server::bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+    addr
+}
+
+fn enter(token: u64, side: Side, qty: u64, price: u64) -> Request {
+    Request::EnterOrder {
+        token,
+        side,
+        qty,
+        price,
+    }
+}
+
+#[tokio::test]
+async fn enter_execute_across_sessions() {
+    // Setup
+    let addr = start().await;
+    let mut maker = Client::connect(addr).await.unwrap();
+    let mut taker = Client::connect(addr).await.unwrap();
+    maker.send(&enter(1, Side::Ask, 420, 69)).await.unwrap();
+    assert!(matches!(
+        maker.recv().await.unwrap(),
+        Response::Accepted { token: 1, .. }
+    ));
+
+    // Act
+    taker.send(&enter(2, Side::Bid, 20, 70)).await.unwrap();
+
+    // Assert
+    assert!(matches!(
+        taker.recv().await.unwrap(),
+        Response::Accepted { token: 2, .. }
+    ));
+    let execution = |token| Response::Executed {
+        token,
+        qty: 20,
+        price: 69,
+        match_number: 1,
+    };
+    assert_eq!(taker.recv().await.unwrap(), execution(2));
+    assert_eq!(maker.recv().await.unwrap(), execution(1));
+}
+
+#[tokio::test]
+async fn protocol_rejects() {
+    // Setup
+    let addr = start().await;
+    let mut client = Client::connect(addr).await.unwrap();
+    client.send(&enter(1, Side::Bid, 420, 69)).await.unwrap();
+    client.recv().await.unwrap();
+
+    // Act
+    client.send(&enter(1, Side::Bid, 420, 69)).await.unwrap();
+    let duplicate = client.recv().await.unwrap();
+    client
+        .send(&Request::Cancel { token: 2, qty: 0 })
+        .await
+        .unwrap();
+    let unknown = client.recv().await.unwrap();
+    client
+        .send(&Request::Cancel { token: 1, qty: 0 })
+        .await
+        .unwrap();
+    let canceled = client.recv().await.unwrap();
+
+    // Assert
+    assert_eq!(
+        duplicate,
+        Response::Rejected {
+            token: 1,
+            reason: RejectReason::DuplicateOrderId
+        }
+    );
+    assert_eq!(
+        unknown,
+        Response::Rejected {
+            token: 2,
+            reason: RejectReason::UnknownId
+        }
+    );
+    assert_eq!(
+        canceled,
+        Response::Canceled {
+            token: 1,
+            decrement: 420
+        }
+    );
+}
@@ -0,0 +1,283 @@
+//! orderbook-py
+//!
+//! Python bindings of [`orderbook::OrderBook`], built as the `orderbook` extension module with
+//! maturin, so notebooks replay data through the same matching as production.
+//!
+//! Example:
+//! ```python
+//! from orderbook import Order, OrderBook, Side, UnknownIdError
+//!
+//! book = OrderBook()
+//! book.insert(Order(69, 420, Side.Ask, 1))
+//! trades = book.match_order(Order(69, 20, Side.Bid, 2))
+//! depth = book.depth_array(Side.Ask)  # numpy array of (price, qty) rows, best price first
+//! try:
+//!     book.remove(3)
+//! except UnknownIdError as err:
+//!     print(err)
+//! ```
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
+use orderbook::{Order, OrderBook, Side, Trade};
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+
+create_exception!(
+    orderbook,
+    OrderBookError,
+    PyException,
+    "Base class of the order book errors"
+);
+create_exception!(
+    orderbook,
+    UnknownIdError,
+    OrderBookError,
+    "No resting order with the OrderId"
+);
+create_exception!(
+    orderbook,
+    DuplicateOrderIdError,
+    OrderBookError,
+    "An order with the OrderId is already resting"
+);
+create_exception!(
+    orderbook,
+    InvalidQtyError,
+    OrderBookError,
+    "The quantity of the order is zero"
+);
+create_exception!(
+    orderbook,
+    InvalidPriceError,
+    OrderBookError,
+    "The price is not accepted by the book"
+);
+
+/// Function maps an [`orderbook::OrderBookError`] to the Python exception of its kind
+fn py_err(err: orderbook::OrderBookError) -> PyErr {
+    let msg = err.to_string();
+    match err {
+        orderbook::OrderBookError::UnknownId(_) => UnknownIdError::new_err(msg),
+        orderbook::OrderBookError::DuplicateOrderId(_) => DuplicateOrderIdError::new_err(msg),
+        orderbook::OrderBookError::InvalidQty(_) => InvalidQtyError::new_err(msg),
+        orderbook::OrderBookError::InvalidPrice { .. } => InvalidPriceError::new_err(msg),
+        _ => OrderBookError::new_err(msg),
+    }
+}
+
+/// Ask or Bid
+#[pyclass(name = "Side", eq, eq_int, frozen, hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PySide {
+    Ask,
+    Bid,
+}
+
+impl From<PySide> for Side {
+    fn from(side: PySide) -> Self {
+        match side {
+            PySide::Ask => Side::Ask,
+            PySide::Bid => Side::Bid,
+        }
+    }
+}
+
+impl From<Side> for PySide {
+    fn from(side: Side) -> Self {
+        match side {
+            Side::Ask => PySide::Ask,
+            Side::Bid => PySide::Bid,
+        }
+    }
+}
+
+/// Order with a caller assigned `id`, see [`Order`]
+#[pyclass(name = "Order", eq, get_all, set_all)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PyOrder {
+    price: u64,
+    qty: u64,
+    side: PySide,
+    id: u64,
+}
+
+#[pymethods]
+impl PyOrder {
+    #[new]
+    fn new(price: u64, qty: u64, side: PySide, id: u64) -> Self {
+        Self {
+            price,
+            qty,
+            side,
+            id,
+        }
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Order(price={}, qty={}, side=Side.{:?}, id={})",
+            self.price, self.qty, self.side, self.id
+        )
+    }
+}
+
+impl From<PyOrder> for Order {
+    fn from(order: PyOrder) -> Self {
+        Order::new(order.price, order.qty, order.side.into(), order.id)
+    }
+}
+
+impl From<Order> for PyOrder {
+    fn from(order: Order) -> Self {
+        PyOrder::new(order.price, order.qty, order.side.into(), order.id)
+    }
+}
+
+/// Execution between a resting and an incoming order, see [`Trade`]
+#[pyclass(name = "Trade", eq, frozen, get_all)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PyTrade {
+    maker_id: u64,
+    taker_id: u64,
+    price: u64,
+    qty: u64,
+    side: PySide,
+}
+
+#[pymethods]
+impl PyTrade {
+    fn __repr__(&self) -> String {
+        format!(
+            "Trade(maker_id={}, taker_id={}, price={}, qty={}, side=Side.{:?})",
+            self.maker_id, self.taker_id, self.price, self.qty, self.side
+        )
+    }
+}
+
+impl From<Trade> for PyTrade {
+    fn from(trade: Trade) -> Self {
+        Self {
+            maker_id: trade.maker_id,
+            taker_id: trade.taker_id,
+            price: trade.price,
+            qty: trade.qty,
+            side: trade.side.into(),
+        }
+    }
+}
+
+/// FIFO limit order book, see [`OrderBook`]
+#[pyclass(name = "OrderBook")]
+#[derive(Default)]
+pub struct PyOrderBook {
+    book: OrderBook,
+}
+
+#[pymethods]
+impl PyOrderBook {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the order without matching, raises `DuplicateOrderIdError` if its id is resting
+    fn insert(&mut self, order: PyOrder) -> PyResult<()> {
+        self.book.insert(order.into()).map_err(py_err)
+    }
+
+    /// Matches the order against the book, resting the remainder, returns the trades
+    fn match_order(&mut self, order: PyOrder) -> PyResult<Vec<PyTrade>> {
+        let trades = self.book.match_order(order.into()).map_err(py_err)?;
+        Ok(trades.into_iter().map(PyTrade::from).collect())
+    }
+
+    /// Removes the order, raises `UnknownIdError` if it is not resting
+    fn remove(&mut self, id: u64) -> PyResult<()> {
+        self.book.remove(id).map_err(py_err)
+    }
+
+    /// Reduces the quantity of the order by `qty` keeping its time priority
+    fn reduce(&mut self, id: u64, qty: u64) -> PyResult<()> {
+        self.book.reduce(id, qty).map_err(py_err)
+    }
+
+    /// Gets the resting order, or `None`
+    fn get_order(&self, id: u64) -> Option<PyOrder> {
+        self.book.get_order(id).copied().map(PyOrder::from)
+    }
+
+    fn __contains__(&self, id: u64) -> bool {
+        self.book.get_order(id).is_some()
+    }
+
+    /// Gets the best price of the side, or `None` if the side is empty
+    fn best_price(&self, side: PySide) -> Option<u64> {
+        self.book.get_best_price(side.into()).copied()
+    }
+
+    /// Gets the total quantity on the price, or `None` if there are no orders
+    fn total_qty(&self, price: u64, side: PySide) -> Option<u64> {
+        self.book.get_total_qty(price, side.into())
+    }
+
+    /// Gets the orders on the price in FIFO order
+    fn orders(&self, price: u64, side: PySide) -> Vec<PyOrder> {
+        self.book
+            .get_orders(price, side.into())
+            .unwrap_or_default()
+            .into_iter()
+            .map(PyOrder::from)
+            .collect()
+    }
+
+    /// Drains orders on the price up to `qty`, returns the drained orders and their total
+    /// quantity
+    fn drain(&mut self, price: u64, side: PySide, qty: u64) -> (Vec<PyOrder>, u64) {
+        let (orders, total) = self
+            .book
+            .get_orders_till_qty(price, side.into(), qty)
+            .unwrap_or_default();
+        (orders.into_iter().map(PyOrder::from).collect(), total)
+    }
+
+    /// Gets `(price, qty)` of every level of the side, best price first
+    fn depth(&self, side: PySide) -> Vec<(u64, u64)> {
+        self.book.get_depth(side.into())
+    }
+
+    /// Gets the depth of the side as a `uint64` numpy array of shape `(levels, 2)`, a row of
+    /// price and quantity per level, best price first
+    fn depth_array<'py>(
+        &self,
+        py: Python<'py>,
+        side: PySide,
+    ) -> PyResult<Bound<'py, PyArray2<u64>>> {
+        let depth = self.book.get_depth(side.into());
+        let levels = depth.len();
+        let flat: Vec<u64> = depth
+            .into_iter()
+            .flat_map(|(price, qty)| [price, qty])
+            .collect();
+        PyArray1::from_vec(py, flat).reshape([levels, 2])
+    }
+}
+
+/// The `orderbook` Python module
+#[pymodule]
+#[pyo3(name = "orderbook")]
+pub fn orderbook_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_class::<PySide>()?;
+    m.add_class::<PyOrder>()?;
+    m.add_class::<PyTrade>()?;
+    m.add_class::<PyOrderBook>()?;
+    m.add("OrderBookError", py.get_type::<OrderBookError>())?;
+    m.add("UnknownIdError", py.get_type::<UnknownIdError>())?;
+    m.add(
+        "DuplicateOrderIdError",
+        py.get_type::<DuplicateOrderIdError>(),
+    )?;
+    m.add("InvalidQtyError", py.get_type::<InvalidQtyError>())?;
+    m.add("InvalidPriceError", py.get_type::<InvalidPriceError>())?;
+    Ok(())
+}
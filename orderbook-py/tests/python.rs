@@ -0,0 +1,105 @@
+//! Runs Python against the `orderbook` module in an embedded interpreter
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::ffi::CStr;
+
+/// Runs `code` with the module imported as `orderbook`
+fn run(code: &CStr) {
+    Python::attach(|py| {
+        let module = PyModule::new(py, "orderbook").unwrap();
+        orderbook_py::orderbook_py(&module).unwrap();
+        let sys = py.import("sys").unwrap();
+        sys.getattr("modules")
+            .unwrap()
+            .set_item("orderbook", &module)
+            .unwrap();
+        let globals = PyDict::new(py);
+        py.run(code, Some(&globals), None)
+            .unwrap_or_else(|err| panic!("{err}"));
+    });
+}
+
+#[test]
+fn insert_match_and_query() {
+    run(c"
+from orderbook import Order, OrderBook, Side, Trade
+
+book = OrderBook()
+book.insert(Order(101, 10, Side.Ask, 1))
+book.insert(Order(101, 20, Side.Ask, 2))
+book.insert(Order(102, 30, Side.Ask, 3))
+book.insert(Order(99, 40, Side.Bid, 4))
+
+trades = book.match_order(Order(101, 15, Side.Bid, 5))
+assert [(t.maker_id, t.qty) for t in trades] == [(1, 10), (2, 5)], trades
+assert trades[0].side == Side.Bid
+assert book.best_price(Side.Ask) == 101
+assert book.best_price(Side.Bid) == 99
+assert book.total_qty(101, Side.Ask) == 15
+assert book.total_qty(100, Side.Ask) is None
+assert book.depth(Side.Ask) == [(101, 15), (102, 30)]
+assert 2 in book and 1 not in book
+assert book.get_order(2) == Order(101, 15, Side.Ask, 2)
+assert repr(book.get_order(2)) == 'Order(price=101, qty=15, side=Side.Ask, id=2)'
+");
+}
+
+#[test]
+fn drain_and_reduce() {
+    run(c"
+from orderbook import Order, OrderBook, Side
+
+book = OrderBook()
+for id in range(1, 4):
+    book.insert(Order(100, 10, Side.Ask, id))
+
+book.reduce(3, 4)
+orders, qty = book.drain(100, Side.Ask, 15)
+assert qty == 15
+assert [(o.id, o.qty) for o in orders] == [(1, 10), (2, 5)]
+assert [(o.id, o.qty) for o in book.orders(100, Side.Ask)] == [(2, 5), (3, 6)]
+assert book.drain(200, Side.Ask, 15) == ([], 0)
+");
+}
+
+#[test]
+fn errors_raise_exceptions() {
+    run(c"
+from orderbook import (
+    DuplicateOrderIdError, Order, OrderBook, OrderBookError, Side, UnknownIdError,
+)
+
+book = OrderBook()
+book.insert(Order(100, 10, Side.Bid, 1))
+try:
+    book.insert(Order(100, 10, Side.Bid, 1))
+    raise AssertionError('duplicate accepted')
+except DuplicateOrderIdError as err:
+    assert 'Duplicate OrderId 1' in str(err)
+
+try:
+    book.remove(2)
+    raise AssertionError('unknown id removed')
+except OrderBookError as err:
+    assert isinstance(err, UnknownIdError)
+");
+}
+
+#[test]
+#[ignore = "needs numpy in the embedded interpreter, CI installs it and runs ignored tests"]
+fn depth_array_is_numpy() {
+    run(c"
+import numpy as np
+from orderbook import Order, OrderBook, Side
+
+book = OrderBook()
+book.insert(Order(99, 40, Side.Bid, 1))
+book.insert(Order(98, 10, Side.Bid, 2))
+book.insert(Order(99, 2, Side.Bid, 3))
+
+depth = book.depth_array(Side.Bid)
+assert depth.dtype == np.uint64 and depth.shape == (2, 2)
+assert depth.tolist() == [[99, 42], [98, 10]]
+assert book.depth_array(Side.Ask).shape == (0, 2)
+");
+}
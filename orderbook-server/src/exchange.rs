@@ -0,0 +1,404 @@
+use crate::protocol::{Command, Event};
+use orderbook::{OrderBook, OrderBookError, Side};
+use std::collections::{BTreeSet, HashMap};
+
+/// Identifies a client connection
+pub type ClientId = u64;
+
+/// Number of levels per side in a depth update
+pub const DEPTH_LEVELS: usize = 10;
+
+/// An [`OrderBook`] per symbol, the client of every resting order and the clients subscribed to
+/// each symbol
+#[derive(Default)]
+pub struct Exchange {
+    books: HashMap<String, OrderBook>,
+    /// Client that submitted each resting order, per symbol
+    owners: HashMap<String, HashMap<u64, ClientId>>,
+    subscribers: HashMap<String, BTreeSet<ClientId>>,
+}
+
+impl Exchange {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Function gets the book of the given symbol
+    pub fn book(&self, symbol: &str) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    /// Function handles a [`Command`] from the given client
+    ///
+    /// Returns the [`Event`]'s in order, each addressed to a client
+    pub fn handle(&mut self, client: ClientId, command: Command) -> Vec<(ClientId, Event)> {
+        let mut out = Vec::new();
+        match command {
+            Command::Insert { symbol, order } => {
+                let book = self.books.entry(symbol.clone()).or_default();
+                match book.match_order(order) {
+                    Ok(trades) => {
+                        let owners = self.owners.entry(symbol.clone()).or_default();
+                        for trade in &trades {
+                            if book.get_order(trade.maker_id).is_none() {
+                                owners.remove(&trade.maker_id);
+                            }
+                        }
+                        if book.get_order(order.id).is_some() {
+                            owners.insert(order.id, client);
+                        }
+                        out.push((
+                            client,
+                            Event::Ack {
+                                symbol: symbol.clone(),
+                                id: order.id,
+                            },
+                        ));
+                        for trade in trades {
+                            self.publish(
+                                &symbol,
+                                &Event::Fill {
+                                    symbol: symbol.clone(),
+                                    trade,
+                                },
+                                &mut out,
+                            );
+                        }
+                        self.publish_depth(&symbol, &mut out);
+                    }
+                    Err(error) => out.push((client, Event::Reject { symbol, error })),
+                }
+            }
+            Command::Cancel { symbol, id } => {
+                // Orders of other clients are reported as unknown, so their ids are not revealed
+                let owned = self
+                    .owners
+                    .get(&symbol)
+                    .and_then(|owners| owners.get(&id))
+                    .is_some_and(|&owner| owner == client);
+                let removed = match self.books.get_mut(&symbol) {
+                    Some(book) if owned => book.remove(id),
+                    _ => Err(OrderBookError::UnknownId(id)),
+                };
+                match removed {
+                    Ok(()) => {
+                        if let Some(owners) = self.owners.get_mut(&symbol) {
+                            owners.remove(&id);
+                        }
+                        out.push((
+                            client,
+                            Event::Canceled {
+                                symbol: symbol.clone(),
+                                id,
+                            },
+                        ));
+                        self.publish_depth(&symbol, &mut out);
+                    }
+                    Err(error) => out.push((client, Event::Reject { symbol, error })),
+                }
+            }
+            Command::Subscribe { symbol } => {
+                self.subscribers
+                    .entry(symbol.clone())
+                    .or_default()
+                    .insert(client);
+                out.push((client, self.depth(&symbol)));
+            }
+            Command::Unsubscribe { symbol } => {
+                if let Some(subscribers) = self.subscribers.get_mut(&symbol) {
+                    subscribers.remove(&client);
+                }
+            }
+        }
+        out
+    }
+
+    /// Function removes all subscriptions and cancels all resting orders of a client
+    ///
+    /// Returns the depth updates of the books the orders were canceled from
+    pub fn disconnect(&mut self, client: ClientId) -> Vec<(ClientId, Event)> {
+        for subscribers in self.subscribers.values_mut() {
+            subscribers.remove(&client);
+        }
+        let mut changed = Vec::new();
+        for (symbol, owners) in &mut self.owners {
+            let Some(book) = self.books.get_mut(symbol) else {
+                continue;
+            };
+            let before = owners.len();
+            owners.retain(|&id, &mut owner| {
+                if owner == client {
+                    // Owned orders are resting, removing them can not fail
+                    let _ = book.remove(id);
+                }
+                owner != client
+            });
+            if owners.len() != before {
+                changed.push(symbol.clone());
+            }
+        }
+        let mut out = Vec::new();
+        for symbol in changed {
+            self.publish_depth(&symbol, &mut out);
+        }
+        out
+    }
+
+    fn depth(&self, symbol: &str) -> Event {
+        let levels = |side| {
+            self.books.get(symbol).map_or_else(Vec::new, |book| {
+                let mut depth = book.get_depth(side);
+                depth.truncate(DEPTH_LEVELS);
+                depth
+            })
+        };
+        Event::Depth {
+            symbol: symbol.to_string(),
+            bids: levels(Side::Bid),
+            asks: levels(Side::Ask),
+        }
+    }
+
+    fn publish_depth(&self, symbol: &str, out: &mut Vec<(ClientId, Event)>) {
+        if self.subscribers.contains_key(symbol) {
+            self.publish(symbol, &self.depth(symbol), out);
+        }
+    }
+
+    fn publish(&self, symbol: &str, event: &Event, out: &mut Vec<(ClientId, Event)>) {
+        if let Some(subscribers) = self.subscribers.get(symbol) {
+            out.extend(subscribers.iter().map(|&client| (client, event.clone())));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Exchange;
+    use crate::protocol::{Command, Event};
+    use orderbook::{Order, OrderBookError, Side, Trade};
+
+    fn insert(symbol: &str, order: Order) -> Command {
+        Command::Insert {
+            symbol: symbol.to_string(),
+            order,
+        }
+    }
+
+    #[test]
+    fn insert_fill_depth() {
+        // Setup
+        let mut exchange = Exchange::new();
+        let symbol = "AAPL";
+        exchange.handle(
+            1,
+            Command::Subscribe {
+                symbol: symbol.to_string(),
+            },
+        );
+        exchange.handle(2, insert(symbol, Order::new(69, 420, Side::Ask, 1)));
+
+        // Act
+        let out = exchange.handle(2, insert(symbol, Order::new(69, 20, Side::Bid, 2)));
+
+        // Assert
+        assert_eq!(
+            out,
+            vec![
+                (
+                    2,
+                    Event::Ack {
+                        symbol: symbol.to_string(),
+                        id: 2
+                    }
+                ),
+                (
+                    1,
+                    Event::Fill {
+                        symbol: symbol.to_string(),
+                        trade: Trade {
+                            maker_id: 1,
+                            taker_id: 2,
+                            price: 69,
+                            qty: 20,
+                            side: Side::Bid
+                        }
+                    }
+                ),
+                (
+                    1,
+                    Event::Depth {
+                        symbol: symbol.to_string(),
+                        bids: vec![],
+                        asks: vec![(69, 400)]
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn books_per_symbol() {
+        // Setup
+        let mut exchange = Exchange::new();
+        exchange.handle(1, insert("AAPL", Order::new(69, 420, Side::Ask, 1)));
+
+        // Act
+        let out = exchange.handle(1, insert("MSFT", Order::new(70, 420, Side::Bid, 1)));
+
+        // Assert
+        assert!(matches!(out[0].1, Event::Ack { .. }));
+        assert_eq!(
+            exchange.book("AAPL").unwrap().get_best_price(Side::Bid),
+            None
+        );
+        assert_eq!(
+            exchange.book("MSFT").unwrap().get_best_price(Side::Bid),
+            Some(&70)
+        );
+    }
+
+    #[test]
+    fn reject_and_unsubscribe() {
+        // Setup
+        let mut exchange = Exchange::new();
+        let symbol = "AAPL".to_string();
+        exchange.handle(
+            1,
+            Command::Subscribe {
+                symbol: symbol.clone(),
+            },
+        );
+        exchange.handle(2, insert(&symbol, Order::new(69, 420, Side::Ask, 1)));
+        exchange.disconnect(1);
+
+        // Act
+        let duplicate = exchange.handle(2, insert(&symbol, Order::new(69, 420, Side::Ask, 1)));
+        let unknown = exchange.handle(
+            2,
+            Command::Cancel {
+                symbol: symbol.clone(),
+                id: 2,
+            },
+        );
+
+        // Assert
+        assert_eq!(
+            duplicate,
+            vec![(
+                2,
+                Event::Reject {
+                    symbol: symbol.clone(),
+                    error: OrderBookError::DuplicateOrderId(1)
+                }
+            )]
+        );
+        assert_eq!(
+            unknown,
+            vec![(
+                2,
+                Event::Reject {
+                    symbol,
+                    error: OrderBookError::UnknownId(2)
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn cancel_unknown_symbol() {
+        // Setup
+        let mut exchange = Exchange::new();
+        let symbol = "AAPL".to_string();
+
+        // Act
+        let out = exchange.handle(
+            1,
+            Command::Cancel {
+                symbol: symbol.clone(),
+                id: 1,
+            },
+        );
+
+        // Assert
+        assert_eq!(
+            out,
+            vec![(
+                1,
+                Event::Reject {
+                    symbol,
+                    error: OrderBookError::UnknownId(1)
+                }
+            )]
+        );
+        assert!(exchange.book("AAPL").is_none());
+    }
+
+    #[test]
+    fn cancel_order_of_other_client() {
+        // Setup
+        let mut exchange = Exchange::new();
+        let symbol = "AAPL".to_string();
+        exchange.handle(1, insert(&symbol, Order::new(69, 420, Side::Ask, 1)));
+        let cancel = Command::Cancel {
+            symbol: symbol.clone(),
+            id: 1,
+        };
+
+        // Act
+        let other = exchange.handle(2, cancel.clone());
+        let own = exchange.handle(1, cancel);
+
+        // Assert
+        assert_eq!(
+            other,
+            vec![(
+                2,
+                Event::Reject {
+                    symbol: symbol.clone(),
+                    error: OrderBookError::UnknownId(1)
+                }
+            )]
+        );
+        assert_eq!(own, vec![(1, Event::Canceled { symbol, id: 1 })]);
+    }
+
+    #[test]
+    fn disconnect_cancels_resting_orders() {
+        // Setup
+        let mut exchange = Exchange::new();
+        let symbol = "AAPL".to_string();
+        exchange.handle(
+            3,
+            Command::Subscribe {
+                symbol: symbol.clone(),
+            },
+        );
+        exchange.handle(1, insert(&symbol, Order::new(69, 420, Side::Ask, 1)));
+        exchange.handle(1, insert(&symbol, Order::new(70, 10, Side::Ask, 2)));
+        exchange.handle(2, insert(&symbol, Order::new(71, 10, Side::Ask, 3)));
+        exchange.handle(2, insert(&symbol, Order::new(69, 20, Side::Bid, 4)));
+
+        // Act
+        let out = exchange.disconnect(1);
+
+        // Assert
+        assert_eq!(
+            out,
+            vec![(
+                3,
+                Event::Depth {
+                    symbol: symbol.clone(),
+                    bids: vec![],
+                    asks: vec![(71, 10)]
+                }
+            )]
+        );
+        let book = exchange.book(&symbol).unwrap();
+        assert_eq!(book.get_order(1), None);
+        assert!(book.get_order(3).is_some());
+        assert!(exchange.disconnect(1).is_empty());
+    }
+}
@@ -0,0 +1,15 @@
+//! orderbook-server
+//!
+//! A matching engine hosting an [`orderbook::OrderBook`] per symbol, accepting newline-delimited
+//! JSON [`Command`]'s over TCP and WebSocket and streaming [`Event`]'s back to the clients.
+//!
+//! Acks and rejects are sent to the client that sent the command, fills and depth updates are
+//! sent to every client subscribed to the symbol. Only the client that inserted an order can
+//! cancel it, its resting orders are canceled when it disconnects.
+mod exchange;
+mod protocol;
+mod server;
+
+pub use exchange::{ClientId, Exchange, DEPTH_LEVELS};
+pub use protocol::{Command, Event};
+pub use server::Server;
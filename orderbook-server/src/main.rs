@@ -0,0 +1,39 @@
+//! Usage: `orderbook-server [--tcp <ADDR>] [--ws <ADDR>]`
+use orderbook_server::Server;
+use std::process::ExitCode;
+
+const DEFAULT_TCP: &str = "127.0.0.1:7000";
+const DEFAULT_WS: &str = "127.0.0.1:7001";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let mut tcp = DEFAULT_TCP.to_string();
+    let mut ws = DEFAULT_WS.to_string();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let addr = match arg.as_str() {
+            "--tcp" => &mut tcp,
+            "--ws" => &mut ws,
+            _ => {
+                eprintln!("Usage: orderbook-server [--tcp <ADDR>] [--ws <ADDR>]");
+                return ExitCode::FAILURE;
+            }
+        };
+        let Some(value) = args.next() else {
+            eprintln!("Missing address for {arg}");
+            return ExitCode::FAILURE;
+        };
+        *addr = value;
+    }
+
+    let server = match Server::bind(&tcp, &ws).await {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to bind: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("Listening on tcp://{tcp} and ws://{ws}");
+    server.run().await;
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,52 @@
+use orderbook::{Order, OrderBookError, Trade};
+use serde::{Deserialize, Serialize};
+
+/// Inbound command, one JSON object per line
+///
+/// Example: `{"type":"insert","symbol":"AAPL","order":{"price":69,"qty":420,"side":"Ask","id":1}}`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Command {
+    /// Matches the order against the book of `symbol`, any remaining quantity rests in the book
+    Insert {
+        symbol: String,
+        order: Order,
+    },
+    /// Removes a resting order
+    Cancel {
+        symbol: String,
+        id: u64,
+    },
+    /// Streams fills and depth updates of `symbol`, starting with a depth snapshot
+    Subscribe {
+        symbol: String,
+    },
+    Unsubscribe {
+        symbol: String,
+    },
+}
+
+/// Outbound event, one JSON object per line
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// The order was accepted by the book
+    Ack { symbol: String, id: u64 },
+    /// The order was removed from the book
+    Canceled { symbol: String, id: u64 },
+    /// The command was rejected by the book
+    Reject {
+        symbol: String,
+        error: OrderBookError,
+    },
+    /// An execution, sent to the subscribers of `symbol`
+    Fill { symbol: String, trade: Trade },
+    /// `Price` and `Qty` per level, best price first, sent to the subscribers of `symbol`
+    Depth {
+        symbol: String,
+        bids: Vec<(u64, u64)>,
+        asks: Vec<(u64, u64)>,
+    },
+    /// The command could not be parsed
+    Error { message: String },
+}
@@ -0,0 +1,222 @@
+use crate::exchange::{ClientId, Exchange};
+use crate::protocol::{Command, Event};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+enum Request {
+    Connect(ClientId, mpsc::UnboundedSender<Event>),
+    Command(ClientId, Command),
+    Disconnect(ClientId),
+}
+
+/// Sends the requests of a single client to the [`Exchange`] task
+struct Session {
+    client: ClientId,
+    exchange: mpsc::UnboundedSender<Request>,
+}
+
+impl Session {
+    /// Function registers a new client
+    ///
+    /// Returns [`None`] if the exchange task stopped
+    fn connect(
+        client: ClientId,
+        exchange: mpsc::UnboundedSender<Request>,
+    ) -> Option<(Self, mpsc::UnboundedReceiver<Event>)> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        exchange.send(Request::Connect(client, tx)).ok()?;
+        Some((Self { client, exchange }, rx))
+    }
+
+    /// Function parses a single JSON command and forwards it to the exchange
+    ///
+    /// Returns an [`Event::Error`] if the line is not a valid command
+    fn command(&self, line: &str) -> Result<(), Event> {
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let command = serde_json::from_str(line).map_err(|e| Event::Error {
+            message: e.to_string(),
+        })?;
+        // The exchange task only stops with the server
+        let _ = self.exchange.send(Request::Command(self.client, command));
+        Ok(())
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = self.exchange.send(Request::Disconnect(self.client));
+    }
+}
+
+/// Matching engine server accepting newline-delimited JSON over TCP and WebSocket
+pub struct Server {
+    tcp: TcpListener,
+    ws: TcpListener,
+}
+
+impl Server {
+    /// Function binds the TCP and WebSocket listeners
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if either address can not be bound
+    pub async fn bind(tcp: impl ToSocketAddrs, ws: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            tcp: TcpListener::bind(tcp).await?,
+            ws: TcpListener::bind(ws).await?,
+        })
+    }
+
+    /// Function gets the address of the TCP listener
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the address can not be retrieved from the socket
+    pub fn tcp_addr(&self) -> io::Result<SocketAddr> {
+        self.tcp.local_addr()
+    }
+
+    /// Function gets the address of the WebSocket listener
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the address can not be retrieved from the socket
+    pub fn ws_addr(&self) -> io::Result<SocketAddr> {
+        self.ws.local_addr()
+    }
+
+    /// Function accepts connections on both listeners forever, a connection that fails to be
+    /// accepted is logged and skipped so the other clients keep running
+    pub async fn run(self) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(run_exchange(rx));
+        let mut next_client: ClientId = 1;
+        loop {
+            let (res, is_ws) = tokio::select! {
+                res = self.tcp.accept() => (res, false),
+                res = self.ws.accept() => (res, true),
+            };
+            let stream = match res {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept a connection: {e}");
+                    continue;
+                }
+            };
+            let client = next_client;
+            next_client += 1;
+            if is_ws {
+                tokio::spawn(run_ws(client, stream, tx.clone()));
+            } else {
+                tokio::spawn(run_tcp(client, stream, tx.clone()));
+            }
+        }
+    }
+}
+
+/// Owns the [`Exchange`] and routes its events to the clients
+async fn run_exchange(mut rx: mpsc::UnboundedReceiver<Request>) {
+    let mut exchange = Exchange::new();
+    let mut clients = HashMap::new();
+    while let Some(request) = rx.recv().await {
+        match request {
+            Request::Connect(client, tx) => {
+                clients.insert(client, tx);
+            }
+            Request::Command(client, command) => {
+                for (client, event) in exchange.handle(client, command) {
+                    if let Some(tx) = clients.get(&client) {
+                        // A closed client is removed by its disconnect request
+                        let _ = tx.send(event);
+                    }
+                }
+            }
+            Request::Disconnect(client) => {
+                clients.remove(&client);
+                for (client, event) in exchange.disconnect(client) {
+                    if let Some(tx) = clients.get(&client) {
+                        let _ = tx.send(event);
+                    }
+                }
+            }
+        }
+    }
+}
+
+async fn run_tcp(client: ClientId, stream: TcpStream, exchange: mpsc::UnboundedSender<Request>) {
+    let Some((session, mut events)) = Session::connect(client, exchange) else {
+        return;
+    };
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Ok(Some(line)) = line else {
+                    break;
+                };
+                if let Err(event) = session.command(&line) {
+                    if write_line(&mut writer, &event).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            Some(event) = events.recv() => {
+                if write_line(&mut writer, &event).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn run_ws(client: ClientId, stream: TcpStream, exchange: mpsc::UnboundedSender<Request>) {
+    let Ok(ws) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    let Some((session, mut events)) = Session::connect(client, exchange) else {
+        return;
+    };
+    let (mut sink, mut stream) = ws.split();
+    loop {
+        tokio::select! {
+            msg = stream.next() => {
+                let text = match msg {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(..)) | Err(..)) | None => break,
+                    Some(Ok(..)) => continue,
+                };
+                for line in text.lines() {
+                    if let Err(event) = session.command(line) {
+                        if sink.send(Message::text(to_json(&event))).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+            Some(event) = events.recv() => {
+                if sink.send(Message::text(to_json(&event))).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn write_line(writer: &mut (impl AsyncWriteExt + Unpin), event: &Event) -> io::Result<()> {
+    let mut line = to_json(event);
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+fn to_json(event: &Event) -> String {
+    serde_json::to_string(event).expect("events serialize to JSON")
+}
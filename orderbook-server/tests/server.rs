@@ -0,0 +1,152 @@
+use futures_util::{SinkExt, StreamExt};
+use orderbook::{Order, Side, Trade};
+use orderbook_server::{Command, Event, Server};
+use std::net::SocketAddr;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+
+async fn start() -> (SocketAddr, SocketAddr) {
+    let server = Server::bind("127.0.0.1:0", "127.0.0.1:0").await.unwrap();
+    let addrs = (server.tcp_addr().unwrap(), server.ws_addr().unwrap());
+    tokio::spawn(server.run());
+    addrs
+}
+
+struct TcpClient {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl TcpClient {
+    async fn connect(addr: SocketAddr) -> Self {
+        let (reader, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+        Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        }
+    }
+
+    async fn send_line(&mut self, line: &str) {
+        self.writer.write_all(line.as_bytes()).await.unwrap();
+        self.writer.write_all(b"\n").await.unwrap();
+    }
+
+    async fn send(&mut self, command: &Command) {
+        self.send_line(&serde_json::to_string(command).unwrap())
+            .await;
+    }
+
+    async fn recv(&mut self) -> Event {
+        let line = self.lines.next_line().await.unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+}
+
+async fn recv_ws<S>(ws: &mut S) -> Event
+where
+    S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+{
+    match ws.next().await.unwrap().unwrap() {
+        Message::Text(text) => serde_json::from_str(&text).unwrap(),
+        msg => panic!("unexpected message {msg:?}"),
+    }
+}
+
+#[tokio::test]
+async fn tcp_and_ws_clients() {
+    // Setup
+    let (tcp, ws) = start().await;
+    let symbol = "AAPL".to_string();
+    let (mut subscriber, _) = tokio_tungstenite::connect_async(format!("ws://{ws}"))
+        .await
+        .unwrap();
+    let subscribe = Command::Subscribe {
+        symbol: symbol.clone(),
+    };
+    subscriber
+        .send(Message::text(serde_json::to_string(&subscribe).unwrap()))
+        .await
+        .unwrap();
+    assert_eq!(
+        recv_ws(&mut subscriber).await,
+        Event::Depth {
+            symbol: symbol.clone(),
+            bids: vec![],
+            asks: vec![]
+        }
+    );
+    let mut client = TcpClient::connect(tcp).await;
+
+    // Act
+    client
+        .send_line(r#"{"type":"insert","symbol":"AAPL","order":{"price":69,"qty":420,"side":"Ask","id":1}}"#)
+        .await;
+    client
+        .send(&Command::Insert {
+            symbol: symbol.clone(),
+            order: Order::new(70, 20, Side::Bid, 2),
+        })
+        .await;
+
+    // Assert
+    for id in [1, 2] {
+        assert_eq!(
+            client.recv().await,
+            Event::Ack {
+                symbol: symbol.clone(),
+                id
+            }
+        );
+    }
+    assert_eq!(
+        recv_ws(&mut subscriber).await,
+        Event::Depth {
+            symbol: symbol.clone(),
+            bids: vec![],
+            asks: vec![(69, 420)]
+        }
+    );
+    assert_eq!(
+        recv_ws(&mut subscriber).await,
+        Event::Fill {
+            symbol: symbol.clone(),
+            trade: Trade {
+                maker_id: 1,
+                taker_id: 2,
+                price: 69,
+                qty: 20,
+                side: Side::Bid
+            }
+        }
+    );
+    assert_eq!(
+        recv_ws(&mut subscriber).await,
+        Event::Depth {
+            symbol,
+            bids: vec![],
+            asks: vec![(69, 400)]
+        }
+    );
+}
+
+#[tokio::test]
+async fn invalid_command() {
+    // Setup
+    let (tcp, _) = start().await;
+    let mut client = TcpClient::connect(tcp).await;
+
+    // Act
+    client.send_line(r#"{"type":"buy"}"#).await;
+    client
+        .send(&Command::Cancel {
+            symbol: "AAPL".to_string(),
+            id: 1,
+        })
+        .await;
+
+    // Assert
+    assert!(matches!(client.recv().await, Event::Error { .. }));
+    assert!(matches!(client.recv().await, Event::Reject { .. }));
+}
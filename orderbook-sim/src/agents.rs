@@ -0,0 +1,588 @@
+//! Agent-based market simulator
+//!
+//! A [`Simulator`] drives one or more [`OrderBook`]'s with populations of synthetic agents: noise
+//! traders arriving as a Poisson process, momentum traders following the recent trade prices and
+//! inventory-constrained market makers. Every agent wakes up at exponentially distributed
+//! intervals drawn from a single seeded RNG, so the same [`SimConfig`] always produces the same
+//! [`SimOutput`].
+use crate::backtest::Time;
+use orderbook::{Order, OrderBook, Side, Trade};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Exp};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use thiserror::Error;
+
+const NANOS_PER_SEC: f64 = 1e9;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum SimError {
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(&'static str),
+}
+
+/// Traders submitting random orders around the mid price
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NoiseTraders {
+    pub count: usize,
+    /// Mean actions per second of every agent
+    pub rate: f64,
+    /// Maximum distance of limit prices from the mid price
+    pub max_offset: u64,
+    pub max_qty: u64,
+    /// Probability an action is a marketable order, the unfilled rest is cancelled
+    pub market_prob: f64,
+    /// Probability an action cancels one of the resting orders of the agent
+    pub cancel_prob: f64,
+}
+
+/// Traders buying after prices rose and selling after prices fell
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MomentumTraders {
+    pub count: usize,
+    /// Mean actions per second of every agent
+    pub rate: f64,
+    /// Period the price change is measured over
+    pub lookback: Time,
+    /// Minimum price change to trade on
+    pub threshold: u64,
+    pub qty: u64,
+}
+
+/// Market makers quoting both sides around the mid price, skewed against their inventory
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MarketMakers {
+    pub count: usize,
+    /// Mean requotes per second of every agent
+    pub rate: f64,
+    pub half_spread: u64,
+    pub qty: u64,
+    /// Absolute inventory a side stops being quoted at
+    pub max_inventory: u64,
+    /// Price shift of both quotes per `qty` of inventory
+    pub skew: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SimConfig {
+    pub seed: u64,
+    /// Number of books, agent `n` trades in book `n % books`
+    pub books: usize,
+    /// Simulated time in nanoseconds
+    pub duration: Time,
+    /// Reference price of a book without quotes or trades
+    pub initial_price: u64,
+    /// Period between depth snapshots
+    pub depth_interval: Time,
+    /// Number of levels per side in a depth snapshot
+    pub depth_levels: usize,
+    pub noise: NoiseTraders,
+    pub momentum: MomentumTraders,
+    pub market_makers: MarketMakers,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            books: 1,
+            duration: 60_000_000_000,
+            initial_price: 10_000,
+            depth_interval: 1_000_000_000,
+            depth_levels: 10,
+            noise: NoiseTraders {
+                count: 20,
+                rate: 2.0,
+                max_offset: 20,
+                max_qty: 100,
+                market_prob: 0.1,
+                cancel_prob: 0.3,
+            },
+            momentum: MomentumTraders {
+                count: 5,
+                rate: 0.5,
+                lookback: 5_000_000_000,
+                threshold: 5,
+                qty: 50,
+            },
+            market_makers: MarketMakers {
+                count: 2,
+                rate: 5.0,
+                half_spread: 2,
+                qty: 200,
+                max_inventory: 1_000,
+                skew: 1,
+            },
+        }
+    }
+}
+
+impl SimConfig {
+    fn validate(&self) -> Result<(), SimError> {
+        let rate = |count: usize, rate: f64| count == 0 || (rate.is_finite() && rate > 0.0);
+        let prob = |prob: f64| (0.0..=1.0).contains(&prob);
+        if self.books == 0 {
+            return Err(SimError::InvalidConfig("at least one book is required"));
+        }
+        if self.depth_interval == 0 {
+            return Err(SimError::InvalidConfig("depth interval must be positive"));
+        }
+        if !rate(self.noise.count, self.noise.rate)
+            || !rate(self.momentum.count, self.momentum.rate)
+            || !rate(self.market_makers.count, self.market_makers.rate)
+        {
+            return Err(SimError::InvalidConfig("agent rates must be positive"));
+        }
+        if !prob(self.noise.market_prob) || !prob(self.noise.cancel_prob) {
+            return Err(SimError::InvalidConfig("probabilities must be in 0..=1"));
+        }
+        if self.noise.max_qty == 0 || self.momentum.qty == 0 || self.market_makers.qty == 0 {
+            return Err(SimError::InvalidConfig("order quantities must be positive"));
+        }
+        Ok(())
+    }
+}
+
+/// A trade in one of the simulated books
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimTrade {
+    pub time: Time,
+    pub book: usize,
+    #[serde(flatten)]
+    pub trade: Trade,
+}
+
+/// Top levels of one of the simulated books, best price first
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepthSnapshot {
+    pub time: Time,
+    pub book: usize,
+    pub bids: Vec<(u64, u64)>,
+    pub asks: Vec<(u64, u64)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimOutput {
+    pub trades: Vec<SimTrade>,
+    pub depth: Vec<DepthSnapshot>,
+    /// Final inventory of every agent, noise traders first, then momentum traders and market
+    /// makers
+    pub inventories: Vec<i64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AgentKind {
+    Noise,
+    Momentum,
+    MarketMaker,
+}
+
+#[derive(Debug)]
+struct Agent {
+    kind: AgentKind,
+    book: usize,
+    /// Time between actions
+    interval: Exp<f64>,
+    /// Resting orders, possibly already filled
+    orders: Vec<u64>,
+    inventory: i64,
+}
+
+/// Drives [`OrderBook`]'s with synthetic order flow
+pub struct Simulator {
+    config: SimConfig,
+    rng: ChaCha8Rng,
+    books: Vec<OrderBook>,
+    /// Recent trade prices per book
+    prices: Vec<VecDeque<(Time, u64)>>,
+    agents: Vec<Agent>,
+    /// Owning agent of every resting order
+    owners: HashMap<u64, usize>,
+    /// Wake up time of every agent
+    queue: BinaryHeap<Reverse<(Time, usize)>>,
+    next_id: u64,
+    time: Time,
+    output: SimOutput,
+}
+
+impl Simulator {
+    /// Constructor function
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the configuration is not valid
+    pub fn new(config: SimConfig) -> Result<Self, SimError> {
+        config.validate()?;
+        let populations = [
+            (AgentKind::Noise, config.noise.count, config.noise.rate),
+            (
+                AgentKind::Momentum,
+                config.momentum.count,
+                config.momentum.rate,
+            ),
+            (
+                AgentKind::MarketMaker,
+                config.market_makers.count,
+                config.market_makers.rate,
+            ),
+        ];
+        let mut agents = Vec::new();
+        for (kind, count, rate) in populations {
+            for _ in 0..count {
+                agents.push(Agent {
+                    kind,
+                    book: agents.len() % config.books,
+                    interval: Exp::new(rate / NANOS_PER_SEC).expect("rate is validated"),
+                    orders: Vec::new(),
+                    inventory: 0,
+                });
+            }
+        }
+
+        let mut sim = Self {
+            config,
+            rng: ChaCha8Rng::seed_from_u64(config.seed),
+            books: (0..config.books).map(|_| OrderBook::new()).collect(),
+            prices: vec![VecDeque::new(); config.books],
+            agents,
+            owners: HashMap::new(),
+            queue: BinaryHeap::new(),
+            next_id: 1,
+            time: 0,
+            output: SimOutput::default(),
+        };
+        for idx in 0..sim.agents.len() {
+            sim.schedule(idx);
+        }
+        Ok(sim)
+    }
+
+    /// Function gets the simulated books
+    pub fn books(&self) -> &[OrderBook] {
+        &self.books
+    }
+
+    /// Function runs the simulation for the configured duration
+    pub fn run(mut self) -> SimOutput {
+        // `None` once the next snapshot would be past the end of time
+        let mut next_snapshot = Some(0);
+        while let Some(&Reverse((time, idx))) = self.queue.peek() {
+            if time > self.config.duration {
+                break;
+            }
+            self.snapshots_till(&mut next_snapshot, time);
+            self.queue.pop();
+            self.time = time;
+            match self.agents[idx].kind {
+                AgentKind::Noise => self.act_noise(idx),
+                AgentKind::Momentum => self.act_momentum(idx),
+                AgentKind::MarketMaker => self.act_market_maker(idx),
+            }
+            self.schedule(idx);
+        }
+        self.snapshots_till(&mut next_snapshot, self.config.duration);
+        self.output.inventories = self.agents.iter().map(|agent| agent.inventory).collect();
+        self.output
+    }
+
+    fn schedule(&mut self, idx: usize) {
+        let delay = self.agents[idx].interval.sample(&mut self.rng);
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let time = self.time.saturating_add(delay.ceil() as Time);
+        self.queue.push(Reverse((time, idx)));
+    }
+
+    /// Function takes the snapshots due at or before `time`, advancing `next`
+    fn snapshots_till(&mut self, next: &mut Option<Time>, time: Time) {
+        while let Some(at) = next.filter(|&at| at <= time) {
+            self.snapshot(at);
+            *next = at.checked_add(self.config.depth_interval);
+        }
+    }
+
+    fn snapshot(&mut self, time: Time) {
+        for (book_idx, book) in self.books.iter().enumerate() {
+            let levels = |side| {
+                let mut depth = book.get_depth(side);
+                depth.truncate(self.config.depth_levels);
+                depth
+            };
+            self.output.depth.push(DepthSnapshot {
+                time,
+                book: book_idx,
+                bids: levels(Side::Bid),
+                asks: levels(Side::Ask),
+            });
+        }
+    }
+
+    /// Reference price of a book, the mid price or else the last traded price
+    fn mid(&self, book_idx: usize) -> u64 {
+        let book = &self.books[book_idx];
+        match (
+            book.get_best_price(Side::Bid),
+            book.get_best_price(Side::Ask),
+        ) {
+            (Some(&bid), Some(&ask)) => bid + (ask - bid) / 2,
+            (Some(&price), None) | (None, Some(&price)) => price,
+            (None, None) => self.prices[book_idx]
+                .back()
+                .map_or(self.config.initial_price, |&(_, price)| price),
+        }
+    }
+
+    fn random_side(&mut self) -> Side {
+        if self.rng.gen_bool(0.5) {
+            Side::Bid
+        } else {
+            Side::Ask
+        }
+    }
+
+    fn act_noise(&mut self, idx: usize) {
+        let config = self.config.noise;
+        let book_idx = self.agents[idx].book;
+        self.prune(idx);
+        if !self.agents[idx].orders.is_empty() && self.rng.gen_bool(config.cancel_prob) {
+            let orders = &mut self.agents[idx].orders;
+            let id = orders.swap_remove(self.rng.gen_range(0..orders.len()));
+            self.cancel(book_idx, id);
+            return;
+        }
+
+        let side = self.random_side();
+        let qty = self.rng.gen_range(1..=config.max_qty);
+        let mid = self.mid(book_idx);
+        if self.rng.gen_bool(config.market_prob) {
+            self.submit(idx, cross(side, mid, config.max_offset), qty, side, true);
+        } else {
+            let offset = self.rng.gen_range(1..=config.max_offset.max(1));
+            self.submit(idx, cross(!side, mid, offset), qty, side, false);
+        }
+    }
+
+    fn act_momentum(&mut self, idx: usize) {
+        let config = self.config.momentum;
+        let book_idx = self.agents[idx].book;
+        let since = self.time.saturating_sub(config.lookback);
+        let prices = &self.prices[book_idx];
+        let (Some(&(_, first)), Some(&(_, last))) = (
+            prices.iter().find(|&&(time, _)| time >= since),
+            prices.back(),
+        ) else {
+            return;
+        };
+        let side = if last >= first + config.threshold {
+            Side::Bid
+        } else if first >= last + config.threshold {
+            Side::Ask
+        } else {
+            return;
+        };
+        let mid = self.mid(book_idx);
+        self.submit(
+            idx,
+            cross(side, mid, config.threshold),
+            config.qty,
+            side,
+            true,
+        );
+    }
+
+    fn act_market_maker(&mut self, idx: usize) {
+        let config = self.config.market_makers;
+        let book_idx = self.agents[idx].book;
+        for id in std::mem::take(&mut self.agents[idx].orders) {
+            self.cancel(book_idx, id);
+        }
+
+        let inventory = self.agents[idx].inventory;
+        let qty = i64::try_from(config.qty).unwrap_or(i64::MAX);
+        let skew = i64::try_from(config.skew).unwrap_or(i64::MAX);
+        let shift = inventory.saturating_mul(skew) / qty;
+        let mid = self.mid(book_idx);
+        let reservation = mid.saturating_add_signed(-shift);
+        let max_inventory = i64::try_from(config.max_inventory).unwrap_or(i64::MAX);
+        if inventory.saturating_add(qty) <= max_inventory {
+            let price = reservation.saturating_sub(config.half_spread).max(1);
+            self.submit(idx, price, config.qty, Side::Bid, false);
+        }
+        if inventory.saturating_sub(qty) >= -max_inventory {
+            let price = reservation.saturating_add(config.half_spread);
+            self.submit(idx, price, config.qty, Side::Ask, false);
+        }
+    }
+
+    /// Drops orders of the agent that are no longer resting
+    fn prune(&mut self, idx: usize) {
+        let book = &self.books[self.agents[idx].book];
+        self.agents[idx]
+            .orders
+            .retain(|&id| book.get_order(id).is_some());
+    }
+
+    fn cancel(&mut self, book_idx: usize, id: u64) {
+        if self.books[book_idx].remove(id).is_ok() {
+            self.owners.remove(&id);
+        }
+    }
+
+    /// Matches an order of the agent, an immediate-or-cancel order never rests
+    fn submit(&mut self, idx: usize, price: u64, qty: u64, side: Side, ioc: bool) {
+        let book_idx = self.agents[idx].book;
+        let id = self.next_id;
+        self.next_id += 1;
+        let trades = self.books[book_idx]
+            .match_order(Order::new(price, qty, side, id))
+            .expect("order ids are unique");
+        for trade in trades {
+            self.record(idx, book_idx, trade);
+        }
+        if self.books[book_idx].get_order(id).is_some() {
+            if ioc {
+                self.cancel(book_idx, id);
+            } else {
+                self.owners.insert(id, idx);
+                self.agents[idx].orders.push(id);
+            }
+        }
+    }
+
+    fn record(&mut self, taker: usize, book_idx: usize, trade: Trade) {
+        let qty = i64::try_from(trade.qty).expect("trade quantity fits in i64");
+        let signed = |side| match side {
+            Side::Bid => qty,
+            Side::Ask => -qty,
+        };
+        self.agents[taker].inventory += signed(trade.side);
+        if let Some(&maker) = self.owners.get(&trade.maker_id) {
+            self.agents[maker].inventory += signed(!trade.side);
+            if self.books[book_idx].get_order(trade.maker_id).is_none() {
+                self.owners.remove(&trade.maker_id);
+            }
+        }
+
+        let prices = &mut self.prices[book_idx];
+        prices.push_back((self.time, trade.price));
+        let since = self.time.saturating_sub(self.config.momentum.lookback);
+        while prices.front().is_some_and(|&(time, _)| time < since) {
+            prices.pop_front();
+        }
+        self.output.trades.push(SimTrade {
+            time: self.time,
+            book: book_idx,
+            trade,
+        });
+    }
+}
+
+/// Price `offset` away from `mid` that crosses the spread for an order on `side`
+fn cross(side: Side, mid: u64, offset: u64) -> u64 {
+    match side {
+        Side::Bid => mid.saturating_add(offset),
+        Side::Ask => mid.saturating_sub(offset).max(1),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{SimConfig, SimError, Simulator, Time};
+
+    fn config(seed: u64) -> SimConfig {
+        SimConfig {
+            seed,
+            books: 2,
+            duration: 10_000_000_000,
+            ..SimConfig::default()
+        }
+    }
+
+    #[test]
+    fn reproducible() {
+        // Act
+        let first = Simulator::new(config(7)).unwrap().run();
+        let second = Simulator::new(config(7)).unwrap().run();
+        let other = Simulator::new(config(8)).unwrap().run();
+
+        // Assert
+        assert!(!first.trades.is_empty());
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn depth_time_series() {
+        // Act
+        let output = Simulator::new(config(1)).unwrap().run();
+
+        // Assert
+        // Snapshots at 0..=10s for both books
+        assert_eq!(output.depth.len(), 22);
+        assert!(output
+            .depth
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time));
+        for snapshot in &output.depth {
+            assert!(snapshot.bids.len() <= 10 && snapshot.asks.len() <= 10);
+            if let (Some(&(bid, _)), Some(&(ask, _))) =
+                (snapshot.bids.first(), snapshot.asks.first())
+            {
+                assert!(bid < ask, "book is crossed at {}", snapshot.time);
+            }
+        }
+        assert!(output.trades.iter().any(|trade| trade.book == 1));
+        assert!(output
+            .trades
+            .windows(2)
+            .all(|pair| pair[0].time <= pair[1].time));
+    }
+
+    #[test]
+    fn market_makers_respect_max_inventory() {
+        // Setup
+        let mut config = config(3);
+        config.market_makers.max_inventory = 400;
+
+        // Act
+        let output = Simulator::new(config).unwrap().run();
+
+        // Assert
+        let makers = output.inventories.len() - config.market_makers.count..;
+        assert!(output.inventories[makers]
+            .iter()
+            .all(|inventory| inventory.abs() <= 400));
+        assert_eq!(output.inventories.iter().sum::<i64>(), 0);
+    }
+
+    #[test]
+    fn invalid_config() {
+        assert_eq!(
+            Simulator::new(SimConfig {
+                books: 0,
+                ..SimConfig::default()
+            })
+            .err(),
+            Some(SimError::InvalidConfig("at least one book is required"))
+        );
+    }
+
+    #[test]
+    fn snapshots_till_end_of_time() {
+        // Setup
+        let mut config = config(1);
+        config.duration = Time::MAX;
+        config.depth_interval = Time::MAX / 2 + 1;
+        config.noise.count = 0;
+        config.momentum.count = 0;
+        config.market_makers.count = 0;
+
+        // Act
+        let output = Simulator::new(config).unwrap().run();
+
+        // Assert
+        let times: Vec<Time> = output.depth.iter().map(|snapshot| snapshot.time).collect();
+        assert_eq!(times, vec![0, 0, Time::MAX / 2 + 1, Time::MAX / 2 + 1]);
+    }
+}
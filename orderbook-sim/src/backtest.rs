@@ -0,0 +1,912 @@
+//! Backtesting harness
+//!
+//! A [`Backtest`] replays a time-ordered stream of [`TimedEvent`]'s into an [`OrderBook`] and
+//! calls a [`Strategy`] on every event. The strategy submits and cancels its own orders into the
+//! same book through a [`Context`], and is notified of its [`Fill`]'s. Actions and notifications
+//! travel with the delays of a [`LatencyModel`]. Time only advances with the events and the
+//! seeded delays, so a run is fully deterministic.
+//!
+//! The [`FillModel`] decides whether strategy orders rest in the book or are filled by a
+//! [`QueueSim`] estimating their queue position.
+use crate::latency::{Channel, LatencyModel};
+use crate::queue::{CancelModel, L2Event, QueueSim};
+use orderbook::{Order, OrderBook, OrderBookError, Side, Trade};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, BinaryHeap};
+use thiserror::Error;
+
+/// Simulated time in nanoseconds
+pub type Time = u64;
+
+/// First order id assigned to strategy orders, market data orders must use lower ids
+pub const STRATEGY_ID_BASE: u64 = 1 << 63;
+
+/// Number of levels per side in a [`Depth`] snapshot
+pub const DEPTH_LEVELS: usize = 10;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum BacktestError {
+    #[error("Event at {time} is before the previous event at {previous}")]
+    OutOfOrder { time: Time, previous: Time },
+    #[error("Invalid latency model")]
+    InvalidLatency,
+}
+
+/// A market data event
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// An order is matched against the book, any remaining quantity rests in the book
+    Insert { order: Order },
+    /// A resting order is partially executed or cancelled
+    Reduce { id: u64, qty: u64 },
+    /// A resting order is removed
+    Cancel { id: u64 },
+}
+
+/// A [`MarketEvent`] at a point in simulated time
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimedEvent {
+    pub time: Time,
+    #[serde(flatten)]
+    pub event: MarketEvent,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liquidity {
+    /// The strategy order was resting in the book
+    Maker,
+    /// The strategy order was the incoming order
+    Taker,
+}
+
+/// An execution of a strategy order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fill {
+    pub time: Time,
+    pub id: u64,
+    pub side: Side,
+    pub price: u64,
+    pub qty: u64,
+    pub liquidity: Liquidity,
+}
+
+/// How strategy orders are filled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FillModel {
+    /// Strategy orders rest in the book and are matched like any other order
+    #[default]
+    Book,
+    /// Strategy orders never enter the book, a [`QueueSim`] fills them from the market trades
+    /// once the estimated quantity ahead of them has traded
+    ///
+    /// The remainder of an incoming market order counts as traded against the strategy orders
+    /// it would have crossed. Orders crossing the book on arrival take the liquidity
+    /// [`OrderBook::preview`] reports, without removing it from the book.
+    Queue(CancelModel),
+}
+
+/// Snapshot of the top [`DEPTH_LEVELS`] levels of the book, `(price, qty)` best price first
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Depth {
+    pub bids: Vec<(u64, u64)>,
+    pub asks: Vec<(u64, u64)>,
+}
+
+impl Depth {
+    /// Function gets the levels of the given `Side`
+    #[must_use]
+    pub fn levels(&self, side: Side) -> &[(u64, u64)] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    /// Function gets the best price of the given `Side`
+    #[must_use]
+    pub fn best_price(&self, side: Side) -> Option<u64> {
+        self.levels(side).first().map(|&(price, _)| price)
+    }
+}
+
+/// A request of the strategy, sent to the book after the strategy callback returns
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Submit(Order),
+    Cancel(u64),
+}
+
+/// View of the simulation handed to the [`Strategy`] callbacks
+pub struct Context<'a> {
+    time: Time,
+    depth: &'a Depth,
+    actions: &'a mut Vec<Action>,
+    next_id: &'a mut u64,
+}
+
+impl Context<'_> {
+    /// Function gets the current simulated time
+    #[must_use]
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+    /// Function gets the latest [`Depth`] snapshot that reached the strategy
+    ///
+    /// Snapshots are taken after every market event and travel with it, delayed by the market
+    /// data latency. With [`FillModel::Book`] they include the resting strategy orders.
+    #[must_use]
+    pub fn depth(&self) -> &Depth {
+        self.depth
+    }
+
+    /// Function submits a limit order, it is matched once it reaches the book
+    ///
+    /// Returns the id assigned to the order
+    pub fn submit(&mut self, side: Side, price: u64, qty: u64) -> u64 {
+        let id = *self.next_id;
+        *self.next_id += 1;
+        self.actions
+            .push(Action::Submit(Order::new(price, qty, side, id)));
+        id
+    }
+
+    /// Function cancels a resting strategy order once the request reaches the book
+    pub fn cancel(&mut self, id: u64) {
+        self.actions.push(Action::Cancel(id));
+    }
+}
+
+/// Trading logic under test
+pub trait Strategy {
+    /// Called when a market event, already applied to the book, reaches the strategy
+    fn on_event(&mut self, ctx: &mut Context<'_>, event: &MarketEvent);
+
+    /// Called when the execution of a strategy order reaches the strategy
+    fn on_fill(&mut self, _ctx: &mut Context<'_>, _fill: &Fill) {}
+}
+
+/// Outcome of a backtest
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Report {
+    pub fills: Vec<Fill>,
+    /// Net position, positive when long
+    pub inventory: i64,
+    /// Cash flow of all fills
+    pub cash: i128,
+    /// Price the inventory is valued at, the mid price or else the last traded price
+    pub mark: Option<u64>,
+    /// `cash` plus `inventory` valued at `mark`
+    pub pnl: i128,
+    /// Market events rejected by the book, e.g. cancels of orders the strategy already executed
+    pub market_rejects: usize,
+}
+
+/// A message in flight between the strategy and the book
+#[derive(Debug)]
+enum Pending {
+    /// Strategy action arriving at the book
+    Action(Action),
+    /// Market event arriving at the strategy, with the book after the event
+    Event(MarketEvent, Depth),
+    /// Fill arriving at the strategy
+    Fill(Fill),
+}
+
+#[derive(Debug)]
+struct Scheduled {
+    time: Time,
+    /// Schedule sequence, breaks ties between equal `Time`'s
+    seq: u64,
+    pending: Pending,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Earliest first in the max-heap
+        (other.time, other.seq).cmp(&(self.time, self.seq))
+    }
+}
+
+/// Replays market events against a [`Strategy`]
+pub struct Backtest<S> {
+    book: OrderBook,
+    strategy: S,
+    time: Time,
+    next_id: u64,
+    /// Resting strategy orders
+    open: BTreeSet<u64>,
+    fills: Vec<Fill>,
+    inventory: i64,
+    cash: i128,
+    last_price: Option<u64>,
+    market_rejects: usize,
+    queue: BinaryHeap<Scheduled>,
+    seq: u64,
+    order_entry: Channel,
+    market_data: Channel,
+    /// Synthetic strategy orders of [`FillModel::Queue`]
+    queue_sim: Option<QueueSim>,
+    /// Latest snapshot delivered to the strategy
+    depth: Depth,
+}
+
+impl<S: Strategy> Backtest<S> {
+    /// Constructor function, actions reach the book and events reach the strategy without delay
+    pub fn new(strategy: S) -> Self {
+        Self::with_latency(strategy, LatencyModel::default())
+            .expect("default latency model is valid")
+    }
+
+    /// Constructor function with delays between the strategy and the book
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the latency model is not valid
+    pub fn with_latency(strategy: S, latency: LatencyModel) -> Result<Self, BacktestError> {
+        Self::with_fill_model(strategy, latency, FillModel::default())
+    }
+
+    /// Constructor function with delays between the strategy and the book and the given
+    /// [`FillModel`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the latency model is not valid
+    pub fn with_fill_model(
+        strategy: S,
+        latency: LatencyModel,
+        fill_model: FillModel,
+    ) -> Result<Self, BacktestError> {
+        if !latency.is_valid() {
+            return Err(BacktestError::InvalidLatency);
+        }
+        Ok(Self {
+            book: OrderBook::new(),
+            strategy,
+            time: 0,
+            next_id: STRATEGY_ID_BASE,
+            open: BTreeSet::new(),
+            fills: Vec::new(),
+            inventory: 0,
+            cash: 0,
+            last_price: None,
+            market_rejects: 0,
+            queue: BinaryHeap::new(),
+            seq: 0,
+            order_entry: Channel::new(latency.order_entry, latency.seed, 0),
+            market_data: Channel::new(latency.market_data, latency.seed, 1),
+            queue_sim: match fill_model {
+                FillModel::Book => None,
+                FillModel::Queue(model) => Some(QueueSim::new(model)),
+            },
+            depth: Depth::default(),
+        })
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Function gets the synthetic strategy orders, [`None`] unless the fill model is
+    /// [`FillModel::Queue`]
+    pub fn queue_sim(&self) -> Option<&QueueSim> {
+        self.queue_sim.as_ref()
+    }
+
+    /// Function gets the current simulated time
+    pub fn time(&self) -> Time {
+        self.time
+    }
+
+    /// Function applies a single event and runs the strategy callbacks it triggers
+    ///
+    /// Messages in flight that arrive up to the time of the event are delivered first.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the event is older than the previous event
+    pub fn step(&mut self, event: TimedEvent) -> Result<(), BacktestError> {
+        if event.time < self.time {
+            return Err(BacktestError::OutOfOrder {
+                time: event.time,
+                previous: self.time,
+            });
+        }
+        self.advance(event.time);
+        self.time = event.time;
+
+        let resting = match event.event {
+            MarketEvent::Reduce { id, .. } | MarketEvent::Cancel { id } => {
+                self.book.get_order(id).copied()
+            }
+            MarketEvent::Insert { .. } => None,
+        };
+        let res = match event.event {
+            MarketEvent::Insert { order } if order.id >= STRATEGY_ID_BASE => {
+                Err(OrderBookError::DuplicateOrderId(order.id))
+            }
+            MarketEvent::Insert { order } => self.book.match_order(order).map(|trades| {
+                self.record_trades(&trades, false);
+                self.queue_fills(order, &trades);
+            }),
+            MarketEvent::Reduce { id, qty } => self.book.reduce(id, qty),
+            MarketEvent::Cancel { id } => self.book.remove(id),
+        };
+        if res.is_err() {
+            self.market_rejects += 1;
+        }
+        if let Some(order) = resting {
+            self.sync_level(order.side, order.price);
+        }
+        let arrival = self.market_data.arrival(self.time);
+        let depth = self.snapshot();
+        self.schedule(arrival, Pending::Event(event.event, depth));
+        self.advance(self.time);
+        Ok(())
+    }
+
+    /// Function delivers all messages still in flight
+    pub fn finish(&mut self) {
+        self.advance(Time::MAX);
+    }
+
+    /// Function runs all events, delivers the messages still in flight and reports the result
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the events are not ordered by time
+    pub fn run(
+        mut self,
+        events: impl IntoIterator<Item = TimedEvent>,
+    ) -> Result<(Report, S), BacktestError> {
+        for event in events {
+            self.step(event)?;
+        }
+        self.finish();
+        Ok((self.report(), self.strategy))
+    }
+
+    /// Function reports the fills, inventory and PnL so far
+    ///
+    /// Fills count from the moment they execute in the book, even if the strategy has not been
+    /// notified yet.
+    pub fn report(&self) -> Report {
+        let mid = match (
+            self.book.get_best_price(Side::Bid),
+            self.book.get_best_price(Side::Ask),
+        ) {
+            (Some(&bid), Some(&ask)) => Some(bid + (ask - bid) / 2),
+            _ => None,
+        };
+        let mark = mid.or(self.last_price);
+        Report {
+            fills: self.fills.clone(),
+            inventory: self.inventory,
+            cash: self.cash,
+            mark,
+            pnl: self.cash + i128::from(self.inventory) * i128::from(mark.unwrap_or_default()),
+            market_rejects: self.market_rejects,
+        }
+    }
+
+    fn schedule(&mut self, time: Time, pending: Pending) {
+        self.queue.push(Scheduled {
+            time,
+            seq: self.seq,
+            pending,
+        });
+        self.seq += 1;
+    }
+
+    /// Delivers the messages in flight that arrive up to `until`
+    fn advance(&mut self, until: Time) {
+        while self.queue.peek().is_some_and(|item| item.time <= until) {
+            let mut item = self.queue.pop().expect("peeked item");
+            self.time = item.time;
+            if let Pending::Event(_, depth) = &mut item.pending {
+                self.depth = std::mem::take(depth);
+            }
+            let mut actions = Vec::new();
+            let mut ctx = Context {
+                time: self.time,
+                depth: &self.depth,
+                actions: &mut actions,
+                next_id: &mut self.next_id,
+            };
+            match item.pending {
+                Pending::Action(action) => self.execute(action),
+                Pending::Event(event, _) => self.strategy.on_event(&mut ctx, &event),
+                Pending::Fill(fill) => self.strategy.on_fill(&mut ctx, &fill),
+            }
+            for action in actions {
+                let arrival = self.order_entry.arrival(self.time);
+                self.schedule(arrival, Pending::Action(action));
+            }
+        }
+    }
+
+    /// Takes a [`Depth`] snapshot of the book
+    fn snapshot(&self) -> Depth {
+        let levels = |side| self.book.get_levels(side).take(DEPTH_LEVELS).collect();
+        Depth {
+            bids: levels(Side::Bid),
+            asks: levels(Side::Ask),
+        }
+    }
+
+    /// Executes a strategy action, invalid actions are ignored like an exchange reject
+    fn execute(&mut self, action: Action) {
+        if self.queue_sim.is_some() {
+            self.execute_queued(action);
+            return;
+        }
+        match action {
+            Action::Submit(order) => {
+                if let Ok(trades) = self.book.match_order(order) {
+                    self.record_trades(&trades, true);
+                    if self.book.get_order(order.id).is_some() {
+                        self.open.insert(order.id);
+                    }
+                }
+            }
+            Action::Cancel(id) => {
+                if self.open.remove(&id) {
+                    let _ = self.book.remove(id);
+                }
+            }
+        }
+    }
+
+    /// Executes a strategy action of [`FillModel::Queue`], the book is left untouched
+    fn execute_queued(&mut self, action: Action) {
+        let Some(sim) = self.queue_sim.as_mut() else {
+            return;
+        };
+        match action {
+            Action::Submit(order) => {
+                if order.qty == 0 {
+                    return;
+                }
+                let preview = self.book.preview(order.side, order.qty, Some(order.price));
+                if preview.remaining_qty > 0 {
+                    sim.insert(order.id, order.side, order.price, preview.remaining_qty);
+                    self.open.insert(order.id);
+                }
+                for fill in &preview.fills {
+                    self.last_price = Some(fill.price);
+                    self.book_fill(Fill {
+                        time: self.time,
+                        id: order.id,
+                        side: order.side,
+                        price: fill.price,
+                        qty: fill.qty,
+                        liquidity: Liquidity::Taker,
+                    });
+                }
+            }
+            Action::Cancel(id) => {
+                if self.open.remove(&id) {
+                    sim.cancel(id);
+                }
+            }
+        }
+    }
+
+    /// Fills the synthetic strategy orders of [`FillModel::Queue`] against the trades of an
+    /// incoming market order and its remainder, then updates the levels it changed
+    fn queue_fills(&mut self, order: Order, trades: &[Trade]) {
+        let Some(sim) = self.queue_sim.as_mut() else {
+            return;
+        };
+        let traded: u64 = trades.iter().map(|trade| trade.qty).sum();
+        let remaining = order.qty - traded;
+        let mut fills = Vec::new();
+        for trade in trades {
+            fills.extend(sim.apply(
+                self.time,
+                L2Event::Trade {
+                    side: trade.side,
+                    price: trade.price,
+                    qty: trade.qty,
+                },
+            ));
+        }
+        if remaining > 0 {
+            fills.extend(sim.apply(
+                self.time,
+                L2Event::Trade {
+                    side: order.side,
+                    price: order.price,
+                    qty: remaining,
+                },
+            ));
+        }
+        for fill in &fills {
+            if sim.remaining_qty(fill.id).is_none() {
+                self.open.remove(&fill.id);
+            }
+        }
+        for fill in fills {
+            self.book_fill(fill);
+        }
+        for trade in trades {
+            self.sync_level(!trade.side, trade.price);
+        }
+        self.sync_level(order.side, order.price);
+    }
+
+    /// Passes the total quantity of a level of the book on to the [`QueueSim`]
+    fn sync_level(&mut self, side: Side, price: u64) {
+        if let Some(sim) = self.queue_sim.as_mut() {
+            let qty = self.book.get_total_qty(price, side).unwrap_or_default();
+            sim.apply(self.time, L2Event::Level { side, price, qty });
+        }
+    }
+
+    /// Books the strategy side of every trade and sends the fills to the strategy
+    fn record_trades(&mut self, trades: &[Trade], taker: bool) {
+        for trade in trades {
+            self.last_price = Some(trade.price);
+            let time = self.time;
+            let fill = |id, side, liquidity| Fill {
+                time,
+                id,
+                side,
+                price: trade.price,
+                qty: trade.qty,
+                liquidity,
+            };
+            if self.open.contains(&trade.maker_id) {
+                self.book_fill(fill(trade.maker_id, !trade.side, Liquidity::Maker));
+                if self.book.get_order(trade.maker_id).is_none() {
+                    self.open.remove(&trade.maker_id);
+                }
+            }
+            if taker {
+                self.book_fill(fill(trade.taker_id, trade.side, Liquidity::Taker));
+            }
+        }
+    }
+
+    fn book_fill(&mut self, fill: Fill) {
+        let qty = i64::try_from(fill.qty).expect("fill quantity fits in i64");
+        let notional = i128::from(fill.price) * i128::from(fill.qty);
+        match fill.side {
+            Side::Bid => {
+                self.inventory += qty;
+                self.cash -= notional;
+            }
+            Side::Ask => {
+                self.inventory -= qty;
+                self.cash += notional;
+            }
+        }
+        self.fills.push(fill);
+        let arrival = self.market_data.arrival(self.time);
+        self.schedule(arrival, Pending::Fill(fill));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Backtest, BacktestError, Context, Fill, FillModel, Liquidity, MarketEvent, Strategy,
+        TimedEvent, STRATEGY_ID_BASE,
+    };
+    use crate::latency::{Latency, LatencyModel};
+    use crate::queue::CancelModel;
+    use orderbook::{Order, Side};
+
+    /// Joins the best bid and offers the filled quantity one tick inside the best ask
+    #[derive(Default)]
+    struct Scalper {
+        bid: Option<u64>,
+        fills: Vec<Fill>,
+    }
+
+    impl Strategy for Scalper {
+        fn on_event(&mut self, ctx: &mut Context<'_>, _event: &MarketEvent) {
+            if self.bid.is_none() && self.fills.is_empty() {
+                if let Some(bid) = ctx.depth().best_price(Side::Bid) {
+                    self.bid = Some(ctx.submit(Side::Bid, bid, 10));
+                }
+            }
+        }
+
+        fn on_fill(&mut self, ctx: &mut Context<'_>, fill: &Fill) {
+            self.fills.push(*fill);
+            if fill.side == Side::Bid {
+                if let Some(ask) = ctx.depth().best_price(Side::Ask) {
+                    ctx.submit(Side::Ask, ask - 1, fill.qty);
+                }
+            }
+        }
+    }
+
+    fn insert(time: u64, price: u64, qty: u64, side: Side, id: u64) -> TimedEvent {
+        TimedEvent {
+            time,
+            event: MarketEvent::Insert {
+                order: Order::new(price, qty, side, id),
+            },
+        }
+    }
+
+    fn events() -> Vec<TimedEvent> {
+        vec![
+            insert(1, 100, 50, Side::Bid, 1),
+            insert(2, 102, 50, Side::Ask, 2),
+            // Sweeps the market bid and the strategy bid behind it
+            insert(3, 100, 55, Side::Ask, 3),
+            TimedEvent {
+                time: 4,
+                event: MarketEvent::Cancel { id: 1 },
+            },
+            insert(5, 101, 10, Side::Bid, 4),
+        ]
+    }
+
+    #[test]
+    fn fills_pnl_inventory() {
+        // Setup
+        let backtest = Backtest::new(Scalper::default());
+
+        // Act
+        let (report, strategy) = backtest.run(events()).unwrap();
+
+        // Assert
+        assert_eq!(
+            report.fills,
+            vec![
+                Fill {
+                    time: 3,
+                    id: STRATEGY_ID_BASE,
+                    side: Side::Bid,
+                    price: 100,
+                    qty: 5,
+                    liquidity: Liquidity::Maker
+                },
+                Fill {
+                    time: 5,
+                    id: STRATEGY_ID_BASE + 1,
+                    side: Side::Ask,
+                    price: 101,
+                    qty: 5,
+                    liquidity: Liquidity::Maker
+                },
+            ]
+        );
+        assert_eq!(strategy.fills, report.fills);
+        assert_eq!(report.inventory, 0);
+        assert_eq!(report.cash, 5);
+        assert_eq!(report.pnl, 5);
+        assert_eq!(report.market_rejects, 1);
+    }
+
+    /// Lifts the first offer
+    #[derive(Default)]
+    struct Lifter {
+        done: bool,
+    }
+
+    impl Strategy for Lifter {
+        fn on_event(&mut self, ctx: &mut Context<'_>, _event: &MarketEvent) {
+            if let (false, Some(ask)) = (self.done, ctx.depth().best_price(Side::Ask)) {
+                self.done = true;
+                ctx.submit(Side::Bid, ask, 20);
+            }
+        }
+    }
+
+    #[test]
+    fn queue_fill_model() {
+        // Setup
+        let mut backtest = Backtest::with_fill_model(
+            Scalper::default(),
+            LatencyModel::default(),
+            FillModel::Queue(CancelModel::ProRata),
+        )
+        .unwrap();
+        backtest.step(insert(1, 100, 50, Side::Bid, 1)).unwrap();
+
+        // Act
+        backtest.step(insert(2, 100, 30, Side::Ask, 2)).unwrap();
+        let position = backtest
+            .queue_sim()
+            .unwrap()
+            .queue_position(STRATEGY_ID_BASE);
+        backtest.step(insert(3, 100, 25, Side::Ask, 3)).unwrap();
+        backtest.finish();
+
+        // Assert
+        assert_eq!(position, Some(20));
+        assert_eq!(
+            backtest.report().fills,
+            vec![Fill {
+                time: 3,
+                id: STRATEGY_ID_BASE,
+                side: Side::Bid,
+                price: 100,
+                qty: 5,
+                liquidity: Liquidity::Maker
+            }]
+        );
+        assert!(backtest.book().get_order(STRATEGY_ID_BASE).is_none());
+        assert_eq!(backtest.book().get_total_qty(100, Side::Ask), Some(5));
+        assert_eq!(
+            backtest
+                .queue_sim()
+                .unwrap()
+                .remaining_qty(STRATEGY_ID_BASE),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn taker_fill_marked_to_mid() {
+        // Setup
+        let events = vec![
+            insert(1, 102, 50, Side::Ask, 1),
+            insert(2, 100, 50, Side::Bid, 2),
+        ];
+
+        // Act
+        let (report, _) = Backtest::new(Lifter::default()).run(events).unwrap();
+
+        // Assert
+        assert_eq!(report.fills.len(), 1);
+        assert_eq!(report.fills[0].liquidity, Liquidity::Taker);
+        assert_eq!(report.fills[0].time, 1);
+        assert_eq!(report.inventory, 20);
+        assert_eq!(report.cash, -2040);
+        assert_eq!(report.mark, Some(101));
+        assert_eq!(report.pnl, -20);
+    }
+
+    #[test]
+    fn order_entry_latency() {
+        // Setup
+        let latency = LatencyModel {
+            order_entry: Latency::Fixed { delay: 10 },
+            market_data: Latency::Fixed { delay: 3 },
+            seed: 0,
+        };
+        let mut backtest = Backtest::with_latency(Lifter::default(), latency).unwrap();
+        backtest.step(insert(1, 102, 50, Side::Ask, 1)).unwrap();
+
+        // Act
+        backtest
+            .step(TimedEvent {
+                time: 5,
+                event: MarketEvent::Cancel { id: 1 },
+            })
+            .unwrap();
+        backtest.finish();
+
+        // Assert
+        assert_eq!(backtest.time(), 14);
+        assert!(backtest.report().fills.is_empty());
+        assert_eq!(
+            backtest.book().get_best_price(Side::Bid),
+            Some(&102),
+            "bid arrives after the offer is gone"
+        );
+    }
+
+    /// Records the best ask it sees on every event
+    #[derive(Default)]
+    struct Watcher {
+        seen: Vec<(u64, Option<u64>)>,
+    }
+
+    impl Strategy for Watcher {
+        fn on_event(&mut self, ctx: &mut Context<'_>, _event: &MarketEvent) {
+            self.seen
+                .push((ctx.time(), ctx.depth().best_price(Side::Ask)));
+        }
+    }
+
+    #[test]
+    fn market_data_latency_delays_depth() {
+        // Setup
+        let latency = LatencyModel {
+            market_data: Latency::Fixed { delay: 5 },
+            ..LatencyModel::default()
+        };
+        let mut backtest = Backtest::with_latency(Watcher::default(), latency).unwrap();
+        backtest.step(insert(1, 102, 50, Side::Ask, 1)).unwrap();
+        backtest.step(insert(2, 101, 50, Side::Ask, 2)).unwrap();
+
+        // Act
+        backtest.finish();
+
+        // Assert
+        assert_eq!(
+            backtest.strategy().seen,
+            vec![(6, Some(102)), (7, Some(101))]
+        );
+        assert_eq!(backtest.book().get_best_price(Side::Ask), Some(&101));
+    }
+
+    #[test]
+    fn deterministic_latency() {
+        // Setup
+        let latency = LatencyModel {
+            order_entry: Latency::Exponential { mean: 2.0 },
+            market_data: Latency::Uniform { min: 0, max: 3 },
+            seed: 42,
+        };
+        let run = || {
+            Backtest::with_latency(Scalper::default(), latency)
+                .unwrap()
+                .run(events())
+                .unwrap()
+                .0
+        };
+
+        // Assert
+        assert_eq!(run(), run());
+        assert!(matches!(
+            Backtest::with_latency(
+                Scalper::default(),
+                LatencyModel {
+                    seed: 42,
+                    order_entry: Latency::Uniform { min: 3, max: 0 },
+                    ..LatencyModel::default()
+                }
+            ),
+            Err(BacktestError::InvalidLatency)
+        ));
+    }
+
+    #[test]
+    fn deterministic() {
+        // Act
+        let (first, _) = Backtest::new(Scalper::default()).run(events()).unwrap();
+        let (second, _) = Backtest::new(Scalper::default()).run(events()).unwrap();
+
+        // Assert
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn out_of_order() {
+        // Setup
+        let mut backtest = Backtest::new(Scalper::default());
+        backtest.step(insert(5, 100, 50, Side::Bid, 1)).unwrap();
+
+        // Act
+        let res = backtest.step(insert(4, 100, 50, Side::Bid, 2));
+
+        // Assert
+        assert_eq!(
+            res,
+            Err(BacktestError::OutOfOrder {
+                time: 4,
+                previous: 5
+            })
+        );
+        assert_eq!(backtest.time(), 5);
+    }
+}
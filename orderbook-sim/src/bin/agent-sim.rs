@@ -0,0 +1,57 @@
+//! Runs the agent-based simulator with the default populations and prints the trades and depth
+//! snapshots as JSON lines
+//!
+//! Usage: `agent-sim [SEED] [SECONDS] [BOOKS]`
+use orderbook_sim::agents::{SimConfig, Simulator};
+use std::io::{BufWriter, Write};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut config = SimConfig::default();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parse = |idx: usize| args.get(idx).map(|arg| arg.parse::<u64>());
+    match (parse(0), parse(1), parse(2)) {
+        (Some(Err(e)), ..) | (_, Some(Err(e)), _) | (.., Some(Err(e))) => {
+            eprintln!("Usage: agent-sim [SEED] [SECONDS] [BOOKS]: {e}");
+            return ExitCode::FAILURE;
+        }
+        (seed, seconds, books) => {
+            if let Some(Ok(seed)) = seed {
+                config.seed = seed;
+            }
+            if let Some(Ok(seconds)) = seconds {
+                config.duration = seconds.saturating_mul(1_000_000_000);
+            }
+            if let Some(Ok(books)) = books {
+                config.books = usize::try_from(books).unwrap_or(usize::MAX);
+            }
+        }
+    }
+    let output = match Simulator::new(config) {
+        Ok(sim) => sim.run(),
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut out = BufWriter::new(std::io::stdout().lock());
+    let res = output
+        .trades
+        .iter()
+        .map(|trade| serde_json::json!({"type": "trade", "trade": trade}))
+        .chain(
+            output
+                .depth
+                .iter()
+                .map(|depth| serde_json::json!({"type": "depth", "depth": depth})),
+        )
+        .try_for_each(|line| writeln!(out, "{line}"));
+    match res.and_then(|()| out.flush()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,171 @@
+//! Latency models
+//!
+//! Delays between the strategy and the matching engine. Order entry latency delays strategy
+//! actions on their way to the book, market data latency delays market events and fills on their
+//! way to the strategy. Sampled delays come from a seeded RNG and every channel delivers in FIFO
+//! order, so a run with the same seed is reproducible.
+use crate::backtest::Time;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Exp, Normal};
+use serde::{Deserialize, Serialize};
+
+/// Distribution of a one-way delay in nanoseconds
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Latency {
+    /// Always the same delay
+    Fixed { delay: Time },
+    /// Uniformly distributed in `min..=max`
+    Uniform { min: Time, max: Time },
+    /// Normally distributed, negative samples are clamped to zero
+    Normal { mean: f64, std_dev: f64 },
+    /// Exponentially distributed, e.g. queueing delays
+    Exponential { mean: f64 },
+}
+
+impl Default for Latency {
+    fn default() -> Self {
+        Latency::Fixed { delay: 0 }
+    }
+}
+
+impl Latency {
+    /// Function checks if the parameters describe a valid distribution
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Latency::Fixed { .. } => true,
+            Latency::Uniform { min, max } => min <= max,
+            Latency::Normal { mean, std_dev } => {
+                mean.is_finite() && std_dev.is_finite() && std_dev >= 0.0
+            }
+            Latency::Exponential { mean } => mean.is_finite() && mean > 0.0,
+        }
+    }
+
+    /// Function samples a delay
+    ///
+    /// # Panics
+    ///
+    /// Panics if the latency is not valid, see [`Latency::is_valid`]
+    pub fn sample(&self, rng: &mut impl Rng) -> Time {
+        let value = match *self {
+            Latency::Fixed { delay } => return delay,
+            Latency::Uniform { min, max } => return rng.gen_range(min..=max),
+            Latency::Normal { mean, std_dev } => Normal::new(mean, std_dev)
+                .expect("valid normal latency")
+                .sample(rng),
+            Latency::Exponential { mean } => Exp::new(1.0 / mean)
+                .expect("valid exponential latency")
+                .sample(rng),
+        };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let delay = value.max(0.0).round() as Time;
+        delay
+    }
+}
+
+/// Latencies of the simulation path
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyModel {
+    /// Delay from a strategy action to the book
+    pub order_entry: Latency,
+    /// Delay from a market event or fill to the strategy
+    pub market_data: Latency,
+    /// Seed of the RNG sampling the delays
+    pub seed: u64,
+}
+
+impl LatencyModel {
+    /// Function checks if both latencies are valid
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.order_entry.is_valid() && self.market_data.is_valid()
+    }
+}
+
+/// A one-way FIFO link with its own RNG stream
+#[derive(Debug)]
+pub(crate) struct Channel {
+    latency: Latency,
+    rng: ChaCha8Rng,
+    last: Time,
+}
+
+impl Channel {
+    pub(crate) fn new(latency: Latency, seed: u64, stream: u64) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(stream);
+        Self {
+            latency,
+            rng,
+            last: 0,
+        }
+    }
+
+    /// Function gets the arrival time of a message sent at `time`, never before the arrival of
+    /// the previous message
+    pub(crate) fn arrival(&mut self, time: Time) -> Time {
+        let arrival = time
+            .saturating_add(self.latency.sample(&mut self.rng))
+            .max(self.last);
+        self.last = arrival;
+        arrival
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Channel, Latency};
+
+    #[test]
+    fn fifo_arrival() {
+        // Setup
+        let latency = Latency::Uniform { min: 0, max: 1000 };
+        let mut channel = Channel::new(latency, 7, 0);
+
+        // Act
+        let arrivals: Vec<_> = (0..100).map(|time| channel.arrival(time)).collect();
+
+        // Assert
+        assert!(arrivals.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(arrivals
+            .iter()
+            .zip(0..)
+            .all(|(&arrival, time)| arrival >= time));
+        assert!(arrivals
+            .iter()
+            .zip(0..)
+            .all(|(&arrival, time)| arrival <= time + 1000));
+    }
+
+    #[test]
+    fn seeded_samples() {
+        // Setup
+        let latency = Latency::Normal {
+            mean: 500.0,
+            std_dev: 200.0,
+        };
+        let sample = |seed| {
+            let mut channel = Channel::new(latency, seed, 0);
+            (0..10).map(|_| channel.arrival(0)).collect::<Vec<_>>()
+        };
+
+        // Assert
+        assert_eq!(sample(1), sample(1));
+        assert_ne!(sample(1), sample(2));
+    }
+
+    #[test]
+    fn validate() {
+        assert!(Latency::default().is_valid());
+        assert!(!Latency::Uniform { min: 2, max: 1 }.is_valid());
+        assert!(!Latency::Normal {
+            mean: 1.0,
+            std_dev: -1.0
+        }
+        .is_valid());
+        assert!(!Latency::Exponential { mean: 0.0 }.is_valid());
+    }
+}
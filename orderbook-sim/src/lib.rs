@@ -0,0 +1,7 @@
+//! orderbook-sim
+//!
+//! Deterministic simulation on top of an [`orderbook::OrderBook`].
+pub mod agents;
+pub mod backtest;
+pub mod latency;
+pub mod queue;
@@ -0,0 +1,296 @@
+//! Queue position simulation
+//!
+//! With L2 data only the total quantity of every price level is known, not the orders it consists
+//! of. A [`QueueSim`] estimates where synthetic orders sit in the queue of their level: they join
+//! at the back, move up when quantity ahead of them trades or is cancelled, and only fill once the
+//! traded quantity exceeds the quantity ahead, like the drain of a `PriceLevel` in the order book.
+//! Synthetic orders never interact with each other or change the observed levels.
+//!
+//! A [`Backtest`](crate::backtest::Backtest) with [`FillModel::Queue`](crate::backtest::FillModel)
+//! fills its strategy orders this way instead of resting them in the book.
+use crate::backtest::{Fill, Liquidity, Time, STRATEGY_ID_BASE};
+use orderbook::Side;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Which part of a level a quantity decrease that did not trade is assumed to come from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CancelModel {
+    /// Quantity ahead and behind a synthetic order are cancelled in proportion to their size
+    #[default]
+    ProRata,
+    /// Quantity behind a synthetic order is cancelled first
+    Back,
+}
+
+/// An L2 market data event
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum L2Event {
+    /// The total quantity of a level changed, zero removes the level
+    Level { side: Side, price: u64, qty: u64 },
+    /// A trade against the resting quantity of a level, `side` is the aggressor
+    Trade { side: Side, price: u64, qty: u64 },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct SimOrder {
+    side: Side,
+    price: u64,
+    qty: u64,
+    /// Estimated market quantity ahead in the queue
+    ahead: u64,
+}
+
+/// Tracks the estimated queue position of synthetic orders against L2 data
+#[derive(Debug)]
+pub struct QueueSim {
+    model: CancelModel,
+    levels: HashMap<(Side, u64), u64>,
+    orders: BTreeMap<u64, SimOrder>,
+    next_id: u64,
+}
+
+impl QueueSim {
+    /// Constructor function
+    #[must_use]
+    pub fn new(model: CancelModel) -> Self {
+        Self {
+            model,
+            levels: HashMap::new(),
+            orders: BTreeMap::new(),
+            next_id: STRATEGY_ID_BASE,
+        }
+    }
+
+    /// Function gets the observed total quantity of a level
+    #[must_use]
+    pub fn level_qty(&self, side: Side, price: u64) -> u64 {
+        self.levels.get(&(side, price)).copied().unwrap_or_default()
+    }
+
+    /// Function adds a synthetic order at the back of its level
+    ///
+    /// Returns the id assigned to the order
+    pub fn submit(&mut self, side: Side, price: u64, qty: u64) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.insert(id, side, price, qty);
+        id
+    }
+
+    /// Function adds a synthetic order with an id assigned by the caller at the back of its level
+    pub fn insert(&mut self, id: u64, side: Side, price: u64, qty: u64) {
+        let ahead = self.level_qty(side, price);
+        self.orders.insert(
+            id,
+            SimOrder {
+                side,
+                price,
+                qty,
+                ahead,
+            },
+        );
+    }
+
+    /// Function removes a synthetic order
+    ///
+    /// Returns `false` if the order is filled or unknown
+    pub fn cancel(&mut self, id: u64) -> bool {
+        self.orders.remove(&id).is_some()
+    }
+
+    /// Function gets the estimated market quantity ahead of a synthetic order
+    #[must_use]
+    pub fn queue_position(&self, id: u64) -> Option<u64> {
+        self.orders.get(&id).map(|order| order.ahead)
+    }
+
+    /// Function gets the unfilled quantity of a synthetic order
+    #[must_use]
+    pub fn remaining_qty(&self, id: u64) -> Option<u64> {
+        self.orders.get(&id).map(|order| order.qty)
+    }
+
+    /// Function applies an L2 event and updates the queue positions
+    ///
+    /// Returns the fills of synthetic orders in order of their id
+    pub fn apply(&mut self, time: Time, event: L2Event) -> Vec<Fill> {
+        match event {
+            L2Event::Level { side, price, qty } => {
+                self.update_level(side, price, qty);
+                Vec::new()
+            }
+            L2Event::Trade { side, price, qty } => self.trade(time, !side, price, qty),
+        }
+    }
+
+    fn update_level(&mut self, side: Side, price: u64, qty: u64) {
+        let previous = self.level_qty(side, price);
+        if qty == 0 {
+            self.levels.remove(&(side, price));
+        } else {
+            self.levels.insert((side, price), qty);
+        }
+        // Added quantity joins behind every synthetic order
+        let Some(cancelled) = previous.checked_sub(qty).filter(|&qty| qty > 0) else {
+            return;
+        };
+        let model = self.model;
+        for order in self
+            .orders
+            .values_mut()
+            .filter(|order| order.side == side && order.price == price)
+        {
+            let ahead = order.ahead.min(previous);
+            let reduction = match model {
+                CancelModel::ProRata => {
+                    let share = u128::from(cancelled) * u128::from(ahead) / u128::from(previous);
+                    u64::try_from(share).expect("share is at most ahead")
+                }
+                CancelModel::Back => cancelled.saturating_sub(previous - ahead),
+            };
+            order.ahead = (ahead - reduction).min(qty);
+        }
+    }
+
+    /// Executes the traded quantity against the synthetic orders on the resting `side`
+    fn trade(&mut self, time: Time, side: Side, price: u64, qty: u64) -> Vec<Fill> {
+        if let Some(level) = self.levels.get_mut(&(side, price)) {
+            *level = level.saturating_sub(qty);
+            if *level == 0 {
+                self.levels.remove(&(side, price));
+            }
+        }
+
+        let mut fills = Vec::new();
+        for (&id, order) in &mut self.orders {
+            if order.side != side {
+                continue;
+            }
+            let better = match side {
+                Side::Bid => order.price > price,
+                Side::Ask => order.price < price,
+            };
+            let executed = if better {
+                // The aggressor traded through the price of the order
+                qty.min(order.qty)
+            } else if order.price == price {
+                let consumed = qty.min(order.ahead);
+                order.ahead -= consumed;
+                (qty - consumed).min(order.qty)
+            } else {
+                0
+            };
+            if executed > 0 {
+                order.qty -= executed;
+                fills.push(Fill {
+                    time,
+                    id,
+                    side,
+                    price: order.price,
+                    qty: executed,
+                    liquidity: Liquidity::Maker,
+                });
+            }
+        }
+        self.orders.retain(|_, order| order.qty > 0);
+        fills
+    }
+}
+
+impl Default for QueueSim {
+    fn default() -> Self {
+        Self::new(CancelModel::default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CancelModel, L2Event, QueueSim};
+    use orderbook::Side;
+
+    fn level(side: Side, price: u64, qty: u64) -> L2Event {
+        L2Event::Level { side, price, qty }
+    }
+
+    fn trade(side: Side, price: u64, qty: u64) -> L2Event {
+        L2Event::Trade { side, price, qty }
+    }
+
+    #[test]
+    fn fill_after_queue_ahead_trades() {
+        // Setup
+        let mut sim = QueueSim::new(CancelModel::ProRata);
+        sim.apply(0, level(Side::Bid, 100, 50));
+        let id = sim.submit(Side::Bid, 100, 10);
+        sim.apply(1, level(Side::Bid, 100, 80));
+
+        // Act
+        let first = sim.apply(2, trade(Side::Ask, 100, 45));
+        let second = sim.apply(3, trade(Side::Ask, 100, 8));
+
+        // Assert
+        assert!(first.is_empty());
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].id, id);
+        assert_eq!(second[0].qty, 3);
+        assert_eq!(sim.queue_position(id), Some(0));
+        assert_eq!(sim.remaining_qty(id), Some(7));
+        assert_eq!(sim.level_qty(Side::Bid, 100), 27);
+    }
+
+    #[test]
+    fn cancel_pro_rata() {
+        // Setup
+        let mut sim = QueueSim::new(CancelModel::ProRata);
+        sim.apply(0, level(Side::Ask, 100, 60));
+        let id = sim.submit(Side::Ask, 100, 10);
+        sim.apply(1, level(Side::Ask, 100, 100));
+
+        // Act
+        sim.apply(2, level(Side::Ask, 100, 50));
+
+        // Assert
+        assert_eq!(sim.queue_position(id), Some(30));
+    }
+
+    #[test]
+    fn cancel_from_back() {
+        // Setup
+        let mut sim = QueueSim::new(CancelModel::Back);
+        sim.apply(0, level(Side::Ask, 100, 60));
+        let id = sim.submit(Side::Ask, 100, 10);
+        sim.apply(1, level(Side::Ask, 100, 100));
+
+        // Act
+        sim.apply(2, level(Side::Ask, 100, 50));
+
+        // Assert
+        assert_eq!(sim.queue_position(id), Some(50));
+
+        // Act
+        sim.apply(3, level(Side::Ask, 100, 20));
+
+        // Assert
+        assert_eq!(sim.queue_position(id), Some(20));
+    }
+
+    #[test]
+    fn trade_through_price() {
+        // Setup
+        let mut sim = QueueSim::new(CancelModel::default());
+        sim.apply(0, level(Side::Bid, 100, 50));
+        let id = sim.submit(Side::Bid, 101, 10);
+
+        // Act
+        let fills = sim.apply(1, trade(Side::Ask, 100, 30));
+
+        // Assert
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].price, 101);
+        assert_eq!(fills[0].qty, 10);
+        assert_eq!(sim.remaining_qty(id), None);
+        assert!(!sim.cancel(id));
+    }
+}
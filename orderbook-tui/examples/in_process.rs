@@ -0,0 +1,40 @@
+//! Renders the ladder of a book mutated in-process, press any key to insert the next order and
+//! `q` to quit
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use orderbook::{Order, OrderBook, Side};
+use orderbook_tui::Viewer;
+
+fn main() -> std::io::Result<()> {
+    let mut book = OrderBook::default();
+    let mut viewer = Viewer::new();
+    let mut terminal = ratatui::init();
+    let mut id = 0;
+    let res = loop {
+        viewer.update(&book);
+        viewer.set_status(format!("{id} orders inserted, any key inserts, q quits"));
+        if let Err(e) = terminal.draw(|frame| viewer.render(frame)) {
+            break Err(e);
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => {
+                if key.code == KeyCode::Char('q') {
+                    break Ok(());
+                }
+            }
+            Ok(..) => continue,
+            Err(e) => break Err(e),
+        }
+
+        // Alternate sides around a drifting mid price so some orders cross
+        id += 1;
+        let side = if id % 2 == 0 { Side::Bid } else { Side::Ask };
+        let price = 100 + (id * 7) % 11 - 5;
+        let qty = 10 + (id * 13) % 90;
+        let trades = book
+            .match_order(Order::new(price, qty, side, id))
+            .expect("ids are unique");
+        viewer.record_trades(&trades);
+    };
+    ratatui::restore();
+    res
+}
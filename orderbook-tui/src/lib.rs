@@ -0,0 +1,347 @@
+//! orderbook-tui
+//!
+//! A terminal depth ladder for an [`OrderBook`]. A [`Viewer`] takes snapshots of any book through
+//! [`Viewer::update`] and renders the bids and asks with quantity bars, highlighting levels that
+//! changed, next to a tape of recent trades. A [`Player`] feeds a book from a recorded event file.
+//!
+//! Example:
+//! ```
+//! use orderbook::{Order, OrderBook, Side};
+//! use orderbook_tui::Viewer;
+//! use ratatui::backend::TestBackend;
+//! use ratatui::Terminal;
+//!
+//! let mut book = OrderBook::new();
+//! let mut viewer = Viewer::new();
+//! book.insert(Order::new(69, 420, Side::Ask, 1)).unwrap();
+//! viewer.update(&book);
+//!
+//! let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+//! terminal.draw(|frame| viewer.render(frame)).unwrap();
+//! ```
+use orderbook::{Order, OrderBook, Side, Trade};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, Paragraph};
+use ratatui::Frame;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Number of levels shown per side
+pub const LEVELS: usize = 10;
+/// Number of trades kept on the tape
+pub const TAPE_LEN: usize = 100;
+/// Number of updates a changed level stays highlighted
+pub const HIGHLIGHT_UPDATES: u8 = 3;
+const BAR_WIDTH: usize = 30;
+
+/// A single line of a recorded event file, JSON encoded
+///
+/// Example: `{"type":"insert","order":{"price":69,"qty":420,"side":"Ask","id":1}}`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// Matches the order against the book, any remaining quantity rests in the book
+    Insert { order: Order },
+    /// Removes a resting order
+    Cancel { id: u64 },
+}
+
+/// Function parses a recorded event file, one JSON [`Event`] per line, skipping empty lines
+///
+/// # Errors
+///
+/// Returns [`Err`] with the line number if a line is not a valid [`Event`]
+pub fn parse_events(text: &str) -> Result<Vec<Event>, (usize, serde_json::Error)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| serde_json::from_str(line).map_err(|e| (idx + 1, e)))
+        .collect()
+}
+
+/// Renders snapshots of an [`OrderBook`] as a depth ladder and a trade tape
+#[derive(Default)]
+pub struct Viewer {
+    bids: Vec<(u64, u64)>,
+    asks: Vec<(u64, u64)>,
+    /// Remaining updates a level stays highlighted
+    changed: HashMap<(Side, u64), u8>,
+    tape: VecDeque<Trade>,
+    status: String,
+}
+
+impl Viewer {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Function takes a snapshot of the book, highlighting levels whose quantity changed since
+    /// the previous snapshot
+    pub fn update(&mut self, book: &OrderBook) {
+        self.changed.retain(|_, updates| {
+            *updates -= 1;
+            *updates > 0
+        });
+        for side in [Side::Bid, Side::Ask] {
+            let mut depth = book.get_depth(side);
+            depth.truncate(LEVELS);
+            let previous: HashMap<_, _> = self.levels(side).iter().copied().collect();
+            for &(price, qty) in &depth {
+                if previous.get(&price) != Some(&qty) {
+                    self.changed.insert((side, price), HIGHLIGHT_UPDATES);
+                }
+            }
+            match side {
+                Side::Bid => self.bids = depth,
+                Side::Ask => self.asks = depth,
+            }
+        }
+    }
+
+    /// Function adds trades to the tape, newest first
+    pub fn record_trades(&mut self, trades: &[Trade]) {
+        for trade in trades {
+            self.tape.push_front(*trade);
+        }
+        self.tape.truncate(TAPE_LEN);
+    }
+
+    /// Function sets the status line shown below the ladder
+    pub fn set_status(&mut self, status: impl Into<String>) {
+        self.status = status.into();
+    }
+
+    /// Function checks if the level is highlighted as changed
+    #[must_use]
+    pub fn is_changed(&self, side: Side, price: u64) -> bool {
+        self.changed.contains_key(&(side, price))
+    }
+
+    /// Function draws the ladder and the tape over the whole frame
+    pub fn render(&self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [ladder, tape] =
+            Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .areas(main);
+        self.render_ladder(frame, ladder);
+        self.render_tape(frame, tape);
+        frame.render_widget(Paragraph::new(self.status.as_str()), status);
+    }
+
+    fn levels(&self, side: Side) -> &[(u64, u64)] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    fn render_ladder(&self, frame: &mut Frame, area: Rect) {
+        let max_qty = self
+            .bids
+            .iter()
+            .chain(&self.asks)
+            .map(|&(_, qty)| qty)
+            .max()
+            .unwrap_or(1);
+        let line = |side: Side, (price, qty): (u64, u64)| {
+            let (label, color) = match side {
+                Side::Ask => ("ask", Color::Red),
+                Side::Bid => ("bid", Color::Green),
+            };
+            let width = usize::try_from(qty.saturating_mul(BAR_WIDTH as u64) / max_qty)
+                .unwrap_or(BAR_WIDTH)
+                .max(1);
+            let mut style = Style::default().fg(color);
+            if self.is_changed(side, price) {
+                style = style.bg(Color::Yellow).add_modifier(Modifier::BOLD);
+            }
+            Line::from(vec![
+                Span::styled(format!("{label} {price:>10} {qty:>10} "), style),
+                Span::styled("█".repeat(width), Style::default().fg(color)),
+            ])
+        };
+        let mut lines: Vec<Line> = self
+            .asks
+            .iter()
+            .rev()
+            .map(|&level| line(Side::Ask, level))
+            .collect();
+        let spread = match (self.asks.first(), self.bids.first()) {
+            (Some(&(ask, _)), Some(&(bid, _))) => format!("spread {}", ask.saturating_sub(bid)),
+            _ => String::from("spread -"),
+        };
+        lines.push(Line::styled(
+            format!("{:-^27}", format!(" {spread} ")),
+            Style::default().fg(Color::DarkGray),
+        ));
+        lines.extend(self.bids.iter().map(|&level| line(Side::Bid, level)));
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Depth ")),
+            area,
+        );
+    }
+
+    fn render_tape(&self, frame: &mut Frame, area: Rect) {
+        let items: Vec<ListItem> = self
+            .tape
+            .iter()
+            .map(|trade| {
+                let color = match trade.side {
+                    Side::Bid => Color::Green,
+                    Side::Ask => Color::Red,
+                };
+                ListItem::new(format!("{:>8} @ {:<10}", trade.qty, trade.price))
+                    .style(Style::default().fg(color))
+            })
+            .collect();
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title(" Trades ")),
+            area,
+        );
+    }
+}
+
+/// Replays recorded [`Event`]'s into an [`OrderBook`] one at a time
+pub struct Player {
+    book: OrderBook,
+    events: Vec<Event>,
+    position: usize,
+}
+
+impl Player {
+    /// Constructor function
+    #[must_use]
+    pub fn new(events: Vec<Event>) -> Self {
+        Self {
+            book: OrderBook::new(),
+            events,
+            position: 0,
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    /// Function checks if all events have been applied
+    #[must_use]
+    pub fn is_done(&self) -> bool {
+        self.position == self.events.len()
+    }
+
+    /// Function applies the next event and updates the viewer, rejected events are reported in
+    /// the status line
+    ///
+    /// Returns `false` if there are no events left
+    pub fn step(&mut self, viewer: &mut Viewer) -> bool {
+        let Some(&event) = self.events.get(self.position) else {
+            return false;
+        };
+        self.position += 1;
+        let res = match event {
+            Event::Insert { order } => self
+                .book
+                .match_order(order)
+                .map(|trades| viewer.record_trades(&trades)),
+            Event::Cancel { id } => self.book.remove(id),
+        };
+        let progress = format!("event {}/{}", self.position, self.events.len());
+        match res {
+            Ok(()) => viewer.set_status(progress),
+            Err(e) => viewer.set_status(format!("{progress}: {e}")),
+        }
+        viewer.update(&self.book);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_events, Event, Player, Viewer, HIGHLIGHT_UPDATES};
+    use orderbook::{Order, OrderBook, Side};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn screen(viewer: &Viewer) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|frame| viewer.render(frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(usize::from(buffer.area.width))
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn highlight_changed_levels() {
+        // Setup
+        let mut book = OrderBook::new();
+        let mut viewer = Viewer::new();
+        book.insert(Order::new(69, 420, Side::Ask, 1)).unwrap();
+        book.insert(Order::new(68, 420, Side::Bid, 2)).unwrap();
+        viewer.update(&book);
+
+        // Act
+        for _ in 0..HIGHLIGHT_UPDATES {
+            viewer.update(&book);
+        }
+        book.insert(Order::new(68, 20, Side::Bid, 3)).unwrap();
+        viewer.update(&book);
+
+        // Assert
+        assert!(!viewer.is_changed(Side::Ask, 69));
+        assert!(viewer.is_changed(Side::Bid, 68));
+    }
+
+    #[test]
+    fn render_ladder_and_tape() {
+        // Setup
+        let events = parse_events(
+            r#"{"type":"insert","order":{"price":70,"qty":100,"side":"Ask","id":1}}
+               {"type":"insert","order":{"price":69,"qty":200,"side":"Ask","id":2}}
+
+               {"type":"insert","order":{"price":69,"qty":50,"side":"Bid","id":3}}
+               {"type":"insert","order":{"price":67,"qty":300,"side":"Bid","id":4}}
+               {"type":"cancel","id":9}"#,
+        )
+        .unwrap();
+        let mut player = Player::new(events);
+        let mut viewer = Viewer::new();
+
+        // Act
+        while player.step(&mut viewer) {}
+        let screen = screen(&viewer);
+
+        // Assert
+        assert!(player.is_done());
+        assert!(screen.contains("ask         70        100"));
+        assert!(screen.contains("ask         69        150"));
+        assert!(screen.contains("spread 2"));
+        assert!(screen.contains("bid         67        300"));
+        assert!(screen.contains("50 @ 69"));
+        assert!(screen.contains("event 5/5: OrderId not found"));
+        let ask = screen.find("ask         70").unwrap();
+        let bid = screen.find("bid         67").unwrap();
+        assert!(ask < bid);
+    }
+
+    #[test]
+    fn parse_invalid_event() {
+        // Act
+        let res = parse_events("{\"type\":\"cancel\",\"id\":1}\n{\"type\":\"buy\"}");
+
+        // Assert
+        assert_eq!(res.unwrap_err().0, 2);
+        assert_eq!(
+            parse_events("{\"type\":\"cancel\",\"id\":1}").unwrap(),
+            vec![Event::Cancel { id: 1 }]
+        );
+    }
+}
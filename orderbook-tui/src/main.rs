@@ -0,0 +1,75 @@
+//! Usage: `orderbook-tui <EVENTS>`
+//!
+//! Replays a recorded event file, one JSON event per line. Keys: `space` pauses, `n` steps while
+//! paused, `+`/`-` change the speed and `q` quits.
+use crossterm::event::{self, Event as TermEvent, KeyCode, KeyEventKind};
+use orderbook_tui::{parse_events, Player, Viewer};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+
+fn main() -> ExitCode {
+    let Some(path) = std::env::args().nth(1) else {
+        eprintln!("Usage: orderbook-tui <EVENTS>");
+        return ExitCode::FAILURE;
+    };
+    let events = match std::fs::read_to_string(&path).map(|text| parse_events(&text)) {
+        Ok(Ok(events)) => events,
+        Ok(Err((line, e))) => {
+            eprintln!("{path}:{line}: {e}");
+            return ExitCode::FAILURE;
+        }
+        Err(e) => {
+            eprintln!("Failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut terminal = ratatui::init();
+    let res = run(&mut terminal, Player::new(events));
+    ratatui::restore();
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(terminal: &mut ratatui::DefaultTerminal, mut player: Player) -> std::io::Result<()> {
+    let mut viewer = Viewer::new();
+    let mut interval = Duration::from_millis(250);
+    let mut paused = false;
+    let mut next = Instant::now();
+    loop {
+        if !paused && Instant::now() >= next {
+            player.step(&mut viewer);
+            next = Instant::now() + interval;
+        }
+        terminal.draw(|frame| viewer.render(frame))?;
+
+        // While paused `next` is in the past, waiting a full interval avoids redrawing in a loop
+        let timeout = if paused {
+            interval
+        } else {
+            next.saturating_duration_since(Instant::now())
+        };
+        if event::poll(timeout)? {
+            if let TermEvent::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char(' ') => paused = !paused,
+                    KeyCode::Char('n') if paused => {
+                        player.step(&mut viewer);
+                    }
+                    KeyCode::Char('+') => interval = (interval / 2).max(Duration::from_millis(1)),
+                    KeyCode::Char('-') => interval = (interval * 2).min(Duration::from_secs(5)),
+                    _ => (),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+//! orderbook-wasm
+//!
+//! JavaScript bindings of [`orderbook::OrderBook`] for running the book in a browser, build with
+//! `wasm-pack build --target web`.
+//!
+//! Orders, trades and sides cross the boundary as plain JS values through their serde
+//! representation, e.g. `{ price: 101, qty: 10, side: "Ask", id: 1 }`. Integers above
+//! `Number.MAX_SAFE_INTEGER` are passed as `BigInt`'s both ways. Errors are thrown as JS `Error`'s.
+//!
+//! Example:
+//! ```js
+//! import init, { OrderBook } from "./pkg/orderbook_wasm.js";
+//!
+//! await init();
+//! const book = new OrderBook();
+//! book.insert({ price: 101, qty: 10, side: "Ask", id: 1 });
+//! const trades = book.matchOrder({ price: 101, qty: 4, side: "Bid", id: 2 });
+//! console.log(book.depth("Ask")); // [[101, 6]]
+//! ```
+use orderbook::{Order, OrderBook, Side, Trade};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Function converts a value to JS, `u64`'s become numbers or, above `Number.MAX_SAFE_INTEGER`,
+/// `BigInt`'s
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    let serializer =
+        serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    Ok(value.serialize(&serializer)?)
+}
+
+/// Function converts a JS value, numbers and `BigInt`'s are accepted for `u64`'s
+fn from_js<T: serde::de::DeserializeOwned>(value: JsValue) -> Result<T, JsError> {
+    Ok(serde_wasm_bindgen::from_value(value)?)
+}
+
+/// Order book with a log of its trades for replaying them in a visualizer
+#[wasm_bindgen(js_name = OrderBook)]
+#[derive(Default)]
+pub struct WasmOrderBook {
+    book: OrderBook,
+    trades: Vec<Trade>,
+}
+
+#[wasm_bindgen(js_class = OrderBook)]
+impl WasmOrderBook {
+    /// Constructor function
+    #[wasm_bindgen(constructor)]
+    #[must_use]
+    pub fn new() -> WasmOrderBook {
+        WasmOrderBook::default()
+    }
+
+    /// Function inserts an order without matching
+    ///
+    /// # Errors
+    ///
+    /// Throws if the value is not an order or its id is already resting
+    pub fn insert(&mut self, order: JsValue) -> Result<(), JsError> {
+        let order: Order = from_js(order)?;
+        Ok(self.book.insert(order)?)
+    }
+
+    /// Function matches an order against the book, resting the remainder, returns its trades
+    ///
+    /// # Errors
+    ///
+    /// Throws if the value is not an order or its id is already resting
+    #[wasm_bindgen(js_name = matchOrder)]
+    pub fn match_order(&mut self, order: JsValue) -> Result<JsValue, JsError> {
+        let order: Order = from_js(order)?;
+        let trades = self.book.match_order(order)?;
+        self.trades.extend_from_slice(&trades);
+        to_js(&trades)
+    }
+
+    /// Function removes the order with the given id
+    ///
+    /// # Errors
+    ///
+    /// Throws if no order with the id is resting
+    pub fn remove(&mut self, id: JsValue) -> Result<(), JsError> {
+        let id: u64 = from_js(id)?;
+        Ok(self.book.remove(id)?)
+    }
+
+    /// Function gets the `[price, qty]` of every level of the side, best price first
+    ///
+    /// # Errors
+    ///
+    /// Throws if the side is not `"Ask"` or `"Bid"`
+    pub fn depth(&self, side: JsValue) -> Result<JsValue, JsError> {
+        let side: Side = from_js(side)?;
+        to_js(&self.book.get_depth(side))
+    }
+
+    /// Function gets the best price of the side, `undefined` if the side is empty
+    ///
+    /// # Errors
+    ///
+    /// Throws if the side is not `"Ask"` or `"Bid"`
+    #[wasm_bindgen(js_name = bestPrice)]
+    pub fn best_price(&self, side: JsValue) -> Result<JsValue, JsError> {
+        let side: Side = from_js(side)?;
+        to_js(&self.book.get_best_price(side).copied())
+    }
+
+    /// Function gets the orders on the price of the side in FIFO order
+    ///
+    /// # Errors
+    ///
+    /// Throws if the price or side are invalid
+    pub fn orders(&self, price: JsValue, side: JsValue) -> Result<JsValue, JsError> {
+        let price: u64 = from_js(price)?;
+        let side: Side = from_js(side)?;
+        to_js(&self.book.get_orders(price, side).unwrap_or_default())
+    }
+
+    /// Function takes the trades of every match since the last call, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Throws if a trade can not be represented in JS
+    #[wasm_bindgen(js_name = takeTrades)]
+    pub fn take_trades(&mut self) -> Result<JsValue, JsError> {
+        to_js(&std::mem::take(&mut self.trades))
+    }
+}
@@ -0,0 +1,89 @@
+//! Runs in Node with `wasm-pack test --node orderbook-wasm`
+#![cfg(target_arch = "wasm32")]
+use orderbook_wasm::WasmOrderBook;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Trade {
+    maker_id: u64,
+    taker_id: u64,
+    price: u64,
+    qty: u64,
+    side: String,
+}
+
+fn js<T: Serialize>(value: &T) -> JsValue {
+    let serializer =
+        serde_wasm_bindgen::Serializer::new().serialize_large_number_types_as_bigints(true);
+    value.serialize(&serializer).unwrap()
+}
+
+fn order(price: u64, qty: u64, side: &str, id: u64) -> JsValue {
+    js(&serde_json::json!({ "price": price, "qty": qty, "side": side, "id": id }))
+}
+
+#[wasm_bindgen_test]
+fn insert_match_and_depth() {
+    // Setup
+    let mut book = WasmOrderBook::new();
+    book.insert(order(101, 10, "Ask", 1)).unwrap();
+    book.insert(order(102, 20, "Ask", 2)).unwrap();
+
+    // Act
+    let trades = book.match_order(order(102, 15, "Bid", 3)).unwrap();
+    book.remove(js(&2)).unwrap();
+
+    // Assert
+    let trades: Vec<Trade> = serde_wasm_bindgen::from_value(trades).unwrap();
+    assert_eq!(
+        trades
+            .iter()
+            .map(|t| (t.maker_id, t.qty))
+            .collect::<Vec<_>>(),
+        vec![(1, 10), (2, 5)]
+    );
+    let taken: Vec<Trade> = serde_wasm_bindgen::from_value(book.take_trades().unwrap()).unwrap();
+    assert_eq!(taken, trades);
+    let depth: Vec<(u64, u64)> =
+        serde_wasm_bindgen::from_value(book.depth(js(&"Ask")).unwrap()).unwrap();
+    assert!(depth.is_empty());
+    let best: Option<u64> =
+        serde_wasm_bindgen::from_value(book.best_price(js(&"Ask")).unwrap()).unwrap();
+    assert_eq!(best, None);
+}
+
+#[wasm_bindgen_test]
+fn errors_are_thrown() {
+    // Setup
+    let mut book = WasmOrderBook::new();
+    book.insert(order(101, 10, "Ask", 1)).unwrap();
+
+    // Act & Assert
+    assert!(book.insert(order(101, 10, "Ask", 1)).is_err());
+    assert!(book.remove(js(&7)).is_err());
+    assert!(book.depth(js(&"Sideways")).is_err());
+}
+
+#[wasm_bindgen_test]
+fn large_ids_are_bigints() {
+    // Setup
+    let mut book = WasmOrderBook::new();
+    let maker = u64::MAX;
+    let taker = 1 << 60;
+    book.insert(order(101, 10, "Ask", maker)).unwrap();
+
+    // Act
+    let trades = book.match_order(order(101, 4, "Bid", taker)).unwrap();
+
+    // Assert
+    let trades: Vec<Trade> = serde_wasm_bindgen::from_value(trades).unwrap();
+    assert_eq!(
+        trades
+            .iter()
+            .map(|t| (t.maker_id, t.taker_id, t.qty))
+            .collect::<Vec<_>>(),
+        vec![(maker, taker, 4)]
+    );
+}
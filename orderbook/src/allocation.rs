@@ -0,0 +1,326 @@
+//! Allocation policies
+//!
+//! How the quantity of an incoming order is split over the resting orders of a price level. The
+//! policy is chosen per [`OrderBook`](crate::OrderBook) with
+//! [`OrderBook::with_allocation`](crate::OrderBook::with_allocation).
+use crate::Qty;
+use alloc::vec;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Rounding of pro-rata shares to whole quantities
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Rounding {
+    /// Shares are rounded down, the residue is allocated FIFO
+    #[default]
+    Down,
+    /// Shares are rounded half up, an excess over the quantity is taken from the latest orders
+    Nearest,
+}
+
+/// Policy splitting an incoming quantity over the resting orders of a price level
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Allocation {
+    /// Oldest order first
+    #[default]
+    Fifo,
+    /// In proportion to the resting quantities, the residue is allocated FIFO
+    ProRata {
+        rounding: Rounding,
+        /// Shares below this quantity are not allocated and become residue
+        min_qty: Qty,
+    },
+    /// The oldest order first up to `top_order_max`, then `pro_rata_percent` of the rest in
+    /// proportion to the remaining resting quantities, then FIFO for the remainder
+    Hybrid {
+        top_order_max: Qty,
+        pro_rata_percent: u8,
+        rounding: Rounding,
+        min_qty: Qty,
+    },
+}
+
+impl Allocation {
+    /// Function allocates `qty` over resting quantities given in FIFO order
+    ///
+    /// Returns the allocated `Qty` per resting order, summing up to `qty` or the total resting
+    /// quantity if that is smaller
+    #[must_use]
+    pub fn allocate(&self, sizes: &[Qty], qty: Qty) -> Vec<Qty> {
+        let mut allocs = vec![0; sizes.len()];
+        let total: u128 = sizes.iter().copied().map(u128::from).sum();
+        let mut remaining = Qty::try_from(total).map_or(qty, |total| qty.min(total));
+        match *self {
+            Allocation::Fifo => (),
+            Allocation::ProRata { rounding, min_qty } => {
+                remaining -= pro_rata(sizes, &mut allocs, remaining, rounding, min_qty);
+            }
+            Allocation::Hybrid {
+                top_order_max,
+                pro_rata_percent,
+                rounding,
+                min_qty,
+            } => {
+                if let Some(&first) = sizes.first() {
+                    allocs[0] = first.min(top_order_max).min(remaining);
+                    remaining -= allocs[0];
+                }
+                let percent = u128::from(pro_rata_percent.min(100));
+                let portion = Qty::try_from(u128::from(remaining) * percent / 100)
+                    .expect("portion is at most remaining");
+                remaining -= pro_rata(sizes, &mut allocs, portion, rounding, min_qty);
+            }
+        }
+        for (size, alloc) in sizes.iter().zip(&mut allocs) {
+            let fill = (size - *alloc).min(remaining);
+            *alloc += fill;
+            remaining -= fill;
+        }
+        allocs
+    }
+}
+
+/// Allocates `qty` in proportion to the quantities not allocated yet
+///
+/// Returns the allocated `Qty`, at most `qty`
+fn pro_rata(sizes: &[Qty], allocs: &mut [Qty], qty: Qty, rounding: Rounding, min_qty: Qty) -> Qty {
+    let open: u128 = sizes
+        .iter()
+        .zip(allocs.iter())
+        .map(|(size, alloc)| u128::from(size - alloc))
+        .sum();
+    if open == 0 || qty == 0 {
+        return 0;
+    }
+    let mut left = qty;
+    for (size, alloc) in sizes.iter().zip(allocs.iter_mut()) {
+        let order_open = size - *alloc;
+        let exact = u128::from(order_open) * u128::from(qty);
+        let share = match rounding {
+            Rounding::Down => exact / open,
+            Rounding::Nearest => (exact * 2 + open) / (open * 2),
+        };
+        let share = Qty::try_from(share)
+            .expect("share is at most qty")
+            .min(order_open);
+        if share < min_qty {
+            continue;
+        }
+        let share = share.min(left);
+        *alloc += share;
+        left -= share;
+    }
+    qty - left
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Allocation, Rounding};
+    use crate::Qty;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Every level of up to 4 orders with quantities in `1..=5`
+    fn levels() -> Vec<Vec<Qty>> {
+        let mut levels = vec![vec![]];
+        let mut frontier = vec![vec![]];
+        for _ in 0..4 {
+            frontier = frontier
+                .iter()
+                .flat_map(|level: &Vec<Qty>| {
+                    (1..=5).map(move |qty| {
+                        let mut level = level.clone();
+                        level.push(qty);
+                        level
+                    })
+                })
+                .collect();
+            levels.extend(frontier.iter().cloned());
+        }
+        levels
+    }
+
+    fn policies() -> Vec<Allocation> {
+        let mut policies = vec![Allocation::Fifo];
+        for rounding in [Rounding::Down, Rounding::Nearest] {
+            for min_qty in 0..=3 {
+                policies.push(Allocation::ProRata { rounding, min_qty });
+                for top_order_max in [0, 1, 3, Qty::MAX] {
+                    for pro_rata_percent in [0, 40, 100, 200] {
+                        policies.push(Allocation::Hybrid {
+                            top_order_max,
+                            pro_rata_percent,
+                            rounding,
+                            min_qty,
+                        });
+                    }
+                }
+            }
+        }
+        policies
+    }
+
+    /// Checks the allocation of every quantity up to beyond the level total
+    fn exhaustive(check: impl Fn(&Allocation, &[Qty], Qty, &[Qty])) {
+        for policy in policies() {
+            for sizes in levels() {
+                let total: Qty = sizes.iter().sum();
+                for qty in 0..=total + 2 {
+                    let allocs = policy.allocate(&sizes, qty);
+                    check(&policy, &sizes, qty, &allocs);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn conserves_quantity() {
+        exhaustive(|policy, sizes, qty, allocs| {
+            let total: Qty = sizes.iter().sum();
+            assert_eq!(
+                allocs.iter().sum::<Qty>(),
+                qty.min(total),
+                "{policy:?} {sizes:?} {qty}"
+            );
+            assert!(
+                allocs.iter().zip(sizes).all(|(alloc, size)| alloc <= size),
+                "{policy:?} {sizes:?} {qty}: {allocs:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn fifo_residue() {
+        exhaustive(|policy, sizes, qty, allocs| {
+            let Allocation::ProRata {
+                rounding: Rounding::Down,
+                min_qty,
+            } = *policy
+            else {
+                return;
+            };
+            let total: Qty = sizes.iter().sum();
+            let qty = qty.min(total);
+            let mut filled_before = true;
+            for (size, alloc) in sizes.iter().zip(allocs) {
+                let share = size * qty / total;
+                let share = if share < min_qty { 0 } else { share };
+                // Every order gets its rounded down share, only the oldest orders get residue
+                assert!(*alloc >= share, "{policy:?} {sizes:?} {qty}: {allocs:?}");
+                if *alloc > share {
+                    assert!(filled_before, "{policy:?} {sizes:?} {qty}: {allocs:?}");
+                }
+                filled_before &= alloc == size;
+            }
+        });
+    }
+
+    #[test]
+    fn nearest_rounding() {
+        exhaustive(|policy, sizes, qty, allocs| {
+            let Allocation::ProRata {
+                rounding: Rounding::Nearest,
+                min_qty: 0,
+            } = *policy
+            else {
+                return;
+            };
+            let total: Qty = sizes.iter().sum();
+            let qty = qty.min(total);
+            let rounded: Vec<Qty> = sizes
+                .iter()
+                .map(|size| ((2 * size * qty + total) / (2 * total.max(1))).min(*size))
+                .collect();
+            if rounded.iter().sum::<Qty>() <= qty {
+                // A residue goes to the oldest orders
+                assert!(
+                    allocs
+                        .iter()
+                        .zip(&rounded)
+                        .all(|(alloc, share)| alloc >= share),
+                    "{sizes:?} {qty}: {allocs:?}"
+                );
+            } else {
+                // An excess is taken from the latest orders
+                let short = allocs
+                    .iter()
+                    .zip(&rounded)
+                    .position(|(alloc, share)| alloc < share)
+                    .expect("excess is taken back");
+                assert!(
+                    allocs[..short] == rounded[..short],
+                    "{sizes:?} {qty}: {allocs:?}"
+                );
+                assert!(
+                    allocs[short + 1..].iter().all(|&alloc| alloc == 0),
+                    "{sizes:?} {qty}: {allocs:?}"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn hybrid_top_order() {
+        exhaustive(|policy, sizes, qty, allocs| {
+            let Allocation::Hybrid { top_order_max, .. } = *policy else {
+                return;
+            };
+            if let Some(&first) = sizes.first() {
+                assert!(
+                    allocs[0] >= first.min(top_order_max).min(qty),
+                    "{policy:?} {sizes:?} {qty}: {allocs:?}"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn fifo() {
+        // Act & Assert
+        assert_eq!(Allocation::Fifo.allocate(&[3, 4, 5], 8), vec![3, 4, 1]);
+        assert_eq!(Allocation::Fifo.allocate(&[3, 4, 5], 20), vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn pro_rata_examples() {
+        // Setup
+        let down = Allocation::ProRata {
+            rounding: Rounding::Down,
+            min_qty: 0,
+        };
+        let nearest = Allocation::ProRata {
+            rounding: Rounding::Nearest,
+            min_qty: 0,
+        };
+        let min_two = Allocation::ProRata {
+            rounding: Rounding::Down,
+            min_qty: 2,
+        };
+
+        // Act & Assert
+        // Exact shares 1.5, 3.0, 4.5, residue of 1 to the oldest order
+        assert_eq!(down.allocate(&[10, 20, 30], 9), vec![2, 3, 4]);
+        // Rounded 2, 3, 5 is one too many, taken from the latest order
+        assert_eq!(nearest.allocate(&[10, 20, 30], 9), vec![2, 3, 4]);
+        // Exact shares 0.6, 1.2, 4.2, only the last share reaches the minimum
+        assert_eq!(min_two.allocate(&[3, 6, 21], 6), vec![2, 0, 4]);
+    }
+
+    #[test]
+    fn hybrid_example() {
+        // Setup
+        let hybrid = Allocation::Hybrid {
+            top_order_max: 5,
+            pro_rata_percent: 50,
+            rounding: Rounding::Down,
+            min_qty: 0,
+        };
+
+        // Act
+        let allocs = hybrid.allocate(&[10, 20, 40], 25);
+
+        // Assert
+        // Top order 5, pro-rata 10 over open quantities 5, 20, 40 is 0, 3, 6, FIFO 11
+        assert_eq!(allocs, vec![10, 9, 6]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Order book analytics
+//!
+//! Signals computed from the resting quantity of an [`OrderBook`]. None of the functions mutate
+//! the book, they only read its depth.
+use crate::{OrderBook, Price, Qty, Side};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Expected outcome of executing a quantity against the book
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionEstimate {
+    /// Quantity available up to the requested quantity
+    pub filled_qty: Qty,
+    /// Quantity that could not be filled
+    pub remaining_qty: Qty,
+    /// Volume weighted average price of the filled quantity
+    pub avg_price: f64,
+    /// Price of the last level reached
+    pub worst_price: Price,
+    /// Difference between the average price and the best price, positive is a cost
+    pub slippage: f64,
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn to_f64(value: u128) -> f64 {
+    value as f64
+}
+
+fn top(book: &OrderBook, side: Side, levels: usize) -> impl Iterator<Item = (Price, Qty)> + '_ {
+    book.get_levels(side).take(levels)
+}
+
+/// Function computes the imbalance of the resting quantity of the top `levels` of both sides
+///
+/// Returns a value in `-1.0..=1.0`, positive when there is more quantity bid than offered
+/// Returns [`None`] if both sides are empty
+#[must_use]
+pub fn imbalance(book: &OrderBook, levels: usize) -> Option<f64> {
+    let total = |side| -> u128 {
+        top(book, side, levels)
+            .map(|(_, qty)| u128::from(qty))
+            .sum()
+    };
+    let (bid, ask) = (total(Side::Bid), total(Side::Ask));
+    if bid + ask == 0 {
+        return None;
+    }
+    Some((to_f64(bid) - to_f64(ask)) / to_f64(bid + ask))
+}
+
+/// Function computes the microprice, the best prices weighted by the quantity on the opposite
+/// side
+///
+/// Returns [`None`] if either side is empty
+#[must_use]
+pub fn microprice(book: &OrderBook) -> Option<f64> {
+    let best = |side| top(book, side, 1).next();
+    let ((bid, bid_qty), (ask, ask_qty)) = (best(Side::Bid)?, best(Side::Ask)?);
+    let weighted = u128::from(bid) * u128::from(ask_qty) + u128::from(ask) * u128::from(bid_qty);
+    Some(to_f64(weighted) / to_f64(u128::from(bid_qty) + u128::from(ask_qty)))
+}
+
+/// Function computes the mid of the volume weighted average prices of the top `levels` of both
+/// sides
+///
+/// Returns [`None`] if either side is empty
+#[must_use]
+pub fn depth_weighted_mid(book: &OrderBook, levels: usize) -> Option<f64> {
+    let vwap = |side| {
+        let (qty, notional) =
+            top(book, side, levels).fold((0u128, 0u128), |(total, notional), (price, qty)| {
+                (
+                    total + u128::from(qty),
+                    notional + u128::from(price) * u128::from(qty),
+                )
+            });
+        (qty > 0).then(|| to_f64(notional) / to_f64(qty))
+    };
+    Some((vwap(Side::Bid)? + vwap(Side::Ask)?) / 2.0)
+}
+
+/// Function gets the cumulative resting quantity of a side at every price, best price first
+#[must_use]
+pub fn cumulative_depth(book: &OrderBook, side: Side) -> Vec<(Price, Qty)> {
+    let mut total: Qty = 0;
+    book.get_levels(side)
+        .map(|(price, qty)| {
+            total += qty;
+            (price, total)
+        })
+        .collect()
+}
+
+/// Function estimates the execution of an incoming order on `side` for `qty`, without a limit
+/// price, against the opposite side of the book, from the [`OrderBook::preview`] of the order
+///
+/// Returns [`None`] if the opposite side is empty or `qty` is 0
+#[must_use]
+pub fn execution_estimate(book: &OrderBook, side: Side, qty: Qty) -> Option<ExecutionEstimate> {
+    let &best = book.get_best_price(!side)?;
+    if qty == 0 {
+        return None;
+    }
+    let preview = book.preview(side, qty, None);
+    let (avg_price, worst_price) = (preview.vwap?, preview.worst_price?);
+    let slippage = match side {
+        Side::Bid => avg_price - to_f64(u128::from(best)),
+        Side::Ask => to_f64(u128::from(best)) - avg_price,
+    };
+    Some(ExecutionEstimate {
+        filled_qty: preview.filled_qty,
+        remaining_qty: preview.remaining_qty,
+        avg_price,
+        worst_price,
+        slippage,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        cumulative_depth, depth_weighted_mid, execution_estimate, imbalance, microprice,
+        ExecutionEstimate,
+    };
+    use crate::{Order, OrderBook, Side};
+
+    fn book() -> OrderBook {
+        let mut book = OrderBook::new();
+        let orders = [
+            (101, 10, Side::Ask),
+            (102, 20, Side::Ask),
+            (104, 30, Side::Ask),
+            (99, 30, Side::Bid),
+            (98, 10, Side::Bid),
+            (97, 60, Side::Bid),
+        ];
+        for (id, (price, qty, side)) in (1..).zip(orders) {
+            book.insert(Order::new(price, qty, side, id)).unwrap();
+        }
+        book
+    }
+
+    #[test]
+    fn imbalance_top_levels() {
+        // Setup
+        let book = book();
+
+        // Act & Assert
+        assert_eq!(imbalance(&book, 1), Some(0.5));
+        assert_eq!(imbalance(&book, 2), Some(10.0 / 70.0));
+        assert_eq!(imbalance(&OrderBook::new(), 5), None);
+    }
+
+    #[test]
+    fn microprice_and_weighted_mid() {
+        // Setup
+        let book = book();
+
+        // Act & Assert
+        // (99 * 10 + 101 * 30) / 40
+        assert_eq!(microprice(&book), Some(100.5));
+        // Bids (99 * 30 + 98 * 10) / 40, asks (101 * 10 + 102 * 20) / 30
+        let expected = (3950.0 / 40.0 + 3050.0 / 30.0) / 2.0;
+        assert_eq!(depth_weighted_mid(&book, 2), Some(expected));
+        let mut one_sided = OrderBook::new();
+        one_sided.insert(Order::new(99, 1, Side::Bid, 1)).unwrap();
+        assert_eq!(microprice(&one_sided), None);
+        assert_eq!(depth_weighted_mid(&one_sided, 2), None);
+    }
+
+    #[test]
+    fn cumulative() {
+        // Act
+        let asks = cumulative_depth(&book(), Side::Ask);
+        let bids = cumulative_depth(&book(), Side::Bid);
+
+        // Assert
+        assert_eq!(asks, vec![(101, 10), (102, 30), (104, 60)]);
+        assert_eq!(bids, vec![(99, 30), (98, 40), (97, 100)]);
+    }
+
+    #[test]
+    fn estimate_execution() {
+        // Setup
+        let book = book();
+
+        // Act
+        let buy = execution_estimate(&book, Side::Bid, 40).unwrap();
+        let sell = execution_estimate(&book, Side::Ask, 150).unwrap();
+
+        // Assert
+        assert_eq!(
+            buy,
+            ExecutionEstimate {
+                filled_qty: 40,
+                remaining_qty: 0,
+                avg_price: (1010.0 + 2040.0 + 1040.0) / 40.0,
+                worst_price: 104,
+                slippage: (1010.0 + 2040.0 + 1040.0) / 40.0 - 101.0,
+            }
+        );
+        assert_eq!(sell.filled_qty, 100);
+        assert_eq!(sell.remaining_qty, 50);
+        assert_eq!(sell.worst_price, 97);
+        assert_eq!(sell.slippage, 99.0 - 9770.0 / 100.0);
+        assert_eq!(execution_estimate(&book, Side::Bid, 0), None);
+        // The book is untouched
+        assert_eq!(book.get_total_qty(101, Side::Ask), Some(10));
+        assert_eq!(book.get_depth(Side::Bid).len(), 3);
+    }
+}
@@ -0,0 +1,88 @@
+//! Replays a NASDAQ ITCH 5.0 `BinaryFILE` and prints the top of book of every stock
+//!
+//! Usage: `itch-replay <FILE> [TIMESTAMP...]`
+//!
+//! Timestamps are nanoseconds since midnight, without timestamps the top of book is printed once
+//! the whole file has been replayed.
+use orderbook::itch::{Parser, Replay, TimeStamp};
+use orderbook::Side;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("Usage: itch-replay <FILE> [TIMESTAMP...]");
+        return ExitCode::FAILURE;
+    };
+    let mut timestamps = Vec::new();
+    for arg in args {
+        match arg.parse::<TimeStamp>() {
+            Ok(ts) => timestamps.push(ts),
+            Err(e) => {
+                eprintln!("Invalid timestamp {arg:?}: {e}");
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+    timestamps.sort_unstable();
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Failed to read {path}: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut replay = Replay::new();
+    let mut pending = timestamps.iter().peekable();
+    for msg in Parser::new(&bytes) {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(e) => {
+                eprintln!("{e}");
+                return ExitCode::FAILURE;
+            }
+        };
+        if let Some(header) = msg.header() {
+            while let Some(&&ts) = pending.peek() {
+                if header.timestamp <= ts {
+                    break;
+                }
+                print_top_of_book(&replay, ts);
+                pending.next();
+            }
+        }
+        if let Err(e) = replay.apply(&msg) {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    if timestamps.is_empty() {
+        print_top_of_book(&replay, replay.timestamp());
+    }
+    for &ts in pending {
+        print_top_of_book(&replay, ts);
+    }
+    ExitCode::SUCCESS
+}
+
+fn print_top_of_book(replay: &Replay, ts: TimeStamp) {
+    for stock in replay.stocks() {
+        let book = stock.book();
+        let level = |side| {
+            book.get_best_price(side).map_or_else(
+                || String::from("-"),
+                |&price| {
+                    let qty = book.get_total_qty(price, side).unwrap_or_default();
+                    format!("{qty} @ {}.{:04}", price / 10_000, price % 10_000)
+                },
+            )
+        };
+        println!(
+            "{ts} {:<8} bid {:>20} | ask {:<20}",
+            stock.symbol(),
+            level(Side::Bid),
+            level(Side::Ask)
+        );
+    }
+}
@@ -1,10 +1,15 @@
-use crate::{Order, OrderId, Price, PriceLevel, Qty};
+use crate::allocation::Allocation;
+use crate::hash_map::Entry;
+use crate::ladder::{self, Ladder};
+use crate::HashMap;
+use crate::{BookConfig, Order, OrderId, Price, PriceLevel, Qty};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Not;
+use core::slice;
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
-use std::ops::Not;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 /// Ask or Bid
 pub enum Side {
     /// Ordered in ascending order
@@ -24,43 +29,137 @@ impl Not for Side {
     }
 }
 
+/// Storage of the price levels of both sides of an [`OrderBook`](crate::OrderBook)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Backend {
+    /// A `HashMap` of price levels and a sorted list of prices, any `Price` is accepted
+    #[default]
+    HashMap,
+    /// A dense array with a price level per tick from `min_price` to `max_price`, orders at other
+    /// prices are rejected with [`OrderBookError::InvalidPrice`](crate::OrderBookError)
+    Ladder {
+        min_price: Price,
+        max_price: Price,
+        tick: Price,
+    },
+}
+
+/// Price levels of a `BookSide`
+#[derive(Clone, Debug)]
+enum Levels {
+    Map {
+        price_levels: HashMap<Price, PriceLevel>,
+        /// Prices of `price_levels`, best price first
+        prices: Vec<Price>,
+        /// Emptied `PriceLevel`'s kept for reuse by new prices
+        free_levels: Vec<PriceLevel>,
+    },
+    Ladder(Ladder),
+}
+
+/// Iterator over the prices of a `BookSide`, best price first
+enum Prices<'a> {
+    Map(slice::Iter<'a, Price>),
+    Ladder(ladder::Prices<'a>),
+}
+
+impl Iterator for Prices<'_> {
+    type Item = Price;
+
+    fn next(&mut self) -> Option<Price> {
+        match self {
+            Prices::Map(prices) => prices.next().copied(),
+            Prices::Ladder(prices) => prices.next(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub(super) struct BookSide {
-    price_levels: HashMap<Price, PriceLevel>,
+    levels: Levels,
     map: HashMap<OrderId, Order>,
     side: Side,
-    prices: Vec<Price>,
+    allocation: Allocation,
 }
 
 impl BookSide {
-    /// Constructor function
-    pub(super) fn new(side: Side) -> Self {
+    /// Constructor function, the [`Allocation`] of the [`BookConfig`] splits incoming orders within
+    /// every `PriceLevel`, the orders and `PriceLevel`'s of its [`Capacity`] are pre-allocated and
+    /// its [`Backend`] stores the `PriceLevel`'s
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tick of a [`Backend::Ladder`] is 0 or its maximum price is below its minimum
+    /// price
+    pub(super) fn new(side: Side, config: BookConfig) -> Self {
+        let BookConfig {
+            allocation,
+            capacity,
+            backend,
+        } = config;
+        let levels = match backend {
+            Backend::HashMap => Levels::Map {
+                price_levels: HashMap::with_capacity(capacity.levels),
+                prices: Vec::with_capacity(capacity.levels),
+                free_levels: (0..capacity.levels)
+                    .map(|_| PriceLevel::with_capacity(allocation, capacity.orders_per_level))
+                    .collect(),
+            },
+            Backend::Ladder {
+                min_price,
+                max_price,
+                tick,
+            } => Levels::Ladder(Ladder::new(
+                side,
+                (min_price, max_price, tick),
+                allocation,
+                capacity.orders_per_level,
+            )),
+        };
         Self {
-            price_levels: HashMap::new(),
-            map: HashMap::new(),
+            levels,
+            map: HashMap::with_capacity(capacity.orders),
             side,
-            prices: Vec::new(),
+            allocation,
+        }
+    }
+
+    /// Function checks if orders at the given `Price` can be stored
+    pub(super) fn accepts(&self, price: Price) -> bool {
+        match &self.levels {
+            Levels::Map { .. } => true,
+            Levels::Ladder(ladder) => ladder.accepts(price),
         }
     }
 
     /// Function insert new order into the `BookSide`
     pub(super) fn insert(&mut self, order: &Order) {
-        let id = order.id;
-        match self.price_levels.entry(order.price) {
-            Entry::Vacant(new_price_lvl) => {
-                let mut price_lvl = PriceLevel::new();
-                price_lvl.insert(order);
-                new_price_lvl.insert(price_lvl);
-                self.prices.push(order.price);
-                match self.side {
-                    Side::Bid => self.prices.sort_by(Ord::cmp),
-                    Side::Ask => self.prices.sort_by(|a, b| b.cmp(a)),
+        match &mut self.levels {
+            Levels::Map {
+                price_levels,
+                prices,
+                free_levels,
+            } => match price_levels.entry(order.price) {
+                Entry::Vacant(new_price_lvl) => {
+                    let mut price_lvl = free_levels
+                        .pop()
+                        .unwrap_or_else(|| PriceLevel::new(self.allocation));
+                    price_lvl.insert(order);
+                    new_price_lvl.insert(price_lvl);
+                    let idx = match self.side {
+                        Side::Ask => prices.partition_point(|&price| price < order.price),
+                        Side::Bid => prices.partition_point(|&price| price > order.price),
+                    };
+                    prices.insert(idx, order.price);
                 }
-            }
-            Entry::Occupied(mut price_lvl) => {
-                price_lvl.get_mut().insert(order);
-            }
+                Entry::Occupied(mut price_lvl) => {
+                    price_lvl.get_mut().insert(order);
+                }
+            },
+            Levels::Ladder(ladder) => ladder.insert(order),
         }
-        self.map.insert(id, *order);
+        self.map.insert(order.id, *order);
     }
 
     /// Function removes order with given `OrderId`
@@ -70,10 +169,66 @@ impl BookSide {
     /// Returns [`Err`] if the order with given `OrderId` is not present
     pub(super) fn remove(&mut self, id: OrderId) {
         if let Some(order) = self.map.remove(&id) {
-            if let Some(price_level) = self.price_levels.get_mut(&order.price) {
+            if let Some(price_level) = self.level_mut(order.price) {
                 price_level.remove(id);
-                if price_level.get_total_qty() == 0 {
-                    self.prices.retain(|&p| p != order.price);
+                if price_level.is_empty() {
+                    self.remove_level(order.price);
+                }
+            }
+        }
+    }
+
+    fn level(&self, price: Price) -> Option<&PriceLevel> {
+        match &self.levels {
+            Levels::Map { price_levels, .. } => price_levels.get(&price),
+            Levels::Ladder(ladder) => ladder.get(price),
+        }
+    }
+
+    fn level_mut(&mut self, price: Price) -> Option<&mut PriceLevel> {
+        match &mut self.levels {
+            Levels::Map { price_levels, .. } => price_levels.get_mut(&price),
+            Levels::Ladder(ladder) => ladder.get_mut(price),
+        }
+    }
+
+    fn prices(&self) -> Prices<'_> {
+        match &self.levels {
+            Levels::Map { prices, .. } => Prices::Map(prices.iter()),
+            Levels::Ladder(ladder) => Prices::Ladder(ladder.prices()),
+        }
+    }
+
+    /// Function removes the `PriceLevel` of the given `Price`, keeping it for reuse
+    fn remove_level(&mut self, price: Price) {
+        match &mut self.levels {
+            Levels::Map {
+                price_levels,
+                prices,
+                free_levels,
+            } => {
+                if let Some(mut price_level) = price_levels.remove(&price) {
+                    price_level.clear();
+                    free_levels.push(price_level);
+                }
+                prices.retain(|&p| p != price);
+            }
+            Levels::Ladder(ladder) => ladder.remove_level(price),
+        }
+    }
+
+    /// Function reduces the `Qty` of the order with given `OrderId`
+    ///
+    /// The order keeps its time priority, it is removed once its `Qty` reaches zero
+    pub(super) fn reduce(&mut self, id: OrderId, qty: Qty) {
+        if let Some(order) = self.map.get_mut(&id) {
+            if qty >= order.qty {
+                self.remove(id);
+            } else {
+                order.qty -= qty;
+                let price = order.price;
+                if let Some(price_level) = self.level_mut(price) {
+                    price_level.reduce(id, qty);
                 }
             }
         }
@@ -83,48 +238,151 @@ impl BookSide {
     ///
     /// Returns [`None`] if there are no orders on given side
     pub(super) fn get_best_price(&self) -> Option<&Price> {
-        self.prices.first()
+        match &self.levels {
+            Levels::Map { prices, .. } => prices.first(),
+            Levels::Ladder(ladder) => ladder.best(),
+        }
     }
 
     /// Function gets the total quantity at the given `Price` and `Side` combination
     pub(super) fn get_total_qty(&self, price: Price) -> Option<Qty> {
-        self.price_levels.get(&price).map(PriceLevel::get_total_qty)
+        self.level(price).map(PriceLevel::get_total_qty)
+    }
+
+    /// Function gets the `Price` and total `Qty` of every level, best price first
+    pub(super) fn get_depth(&self) -> Vec<(Price, Qty)> {
+        self.levels().collect()
+    }
+
+    /// Function iterates the `Price` and total `Qty` of the levels, best price first
+    pub(super) fn levels(&self) -> impl Iterator<Item = (Price, Qty)> + '_ {
+        self.prices()
+            .filter_map(|price| Some((price, self.get_total_qty(price)?)))
+    }
+
+    /// Function gets the orders at the given `Price` in FIFO order
+    pub(super) fn get_orders(&self, price: Price) -> Option<Vec<Order>> {
+        self.level(price).map(PriceLevel::get_orders)
+    }
+
+    /// Function collects the orders an incoming order for `qty` would execute against, best price
+    /// first and FIFO within a price, without draining them
+    ///
+    /// Only prices for which `crosses` returns `true` are visited
+    pub(super) fn peek_orders_till_qty(
+        &self,
+        qty: Qty,
+        crosses: impl Fn(Price) -> bool,
+    ) -> (Vec<Order>, Qty) {
+        let mut collected_qty = 0;
+        let mut orders = vec![];
+        for price in self.prices() {
+            if collected_qty == qty || !crosses(price) {
+                break;
+            }
+            let Some(price_level) = self.level(price) else {
+                break;
+            };
+            let (level_orders, level_qty) = price_level.peek_orders_till_qty(qty - collected_qty);
+            collected_qty += level_qty;
+            orders.extend(level_orders);
+        }
+        (orders, collected_qty)
     }
 
     /// Function drains orders on the given `Price` and `Side` combination up to the given `Qty`
     ///
+    /// Partially drained orders stay in the `BookSide` with their remaining `Qty`
+    ///
     /// Returns [`Some`] with map and total collected `Qty`
     /// Returns [`None`] if there are no map on the given `Side` and `Price` combination
+    #[cfg(test)]
     pub(super) fn get_orders_till_qty(
         &mut self,
         price: Price,
         qty: Qty,
     ) -> Option<(Vec<Order>, Qty)> {
-        match self
-            .price_levels
-            .get_mut(&price)
-            .map(|price_level| price_level.get_orders_till_qty(qty))
-        {
-            Some((orders, total_qty)) => {
-                orders.iter().for_each(|order| {
-                    self.map.remove(&order.id);
-                });
-                Some((orders, total_qty))
+        let mut orders = vec![];
+        let total_qty = self.get_orders_till_qty_into(price, qty, &mut orders)?;
+        Some((orders, total_qty))
+    }
+
+    /// Function drains orders on the given `Price` up to the given `Qty`, appending them to
+    /// `orders`
+    ///
+    /// Returns [`Some`] with the total collected `Qty`
+    /// Returns [`None`] if there are no orders on the given `Price`
+    pub(super) fn get_orders_till_qty_into(
+        &mut self,
+        price: Price,
+        qty: Qty,
+        orders: &mut Vec<Order>,
+    ) -> Option<Qty> {
+        let start = orders.len();
+        let price_level = self.level_mut(price)?;
+        let total_qty = price_level.get_orders_till_qty_into(qty, orders);
+        if price_level.is_empty() {
+            self.remove_level(price);
+        }
+        for order in &orders[start..] {
+            if let Entry::Occupied(mut entry) = self.map.entry(order.id) {
+                if entry.get().qty > order.qty {
+                    entry.get_mut().qty -= order.qty;
+                } else {
+                    entry.remove();
+                }
+            }
+        }
+        Some(total_qty)
+    }
+
+    /// Function asserts that the prices are exactly the non-empty levels best price first, that
+    /// the levels hold the orders of the `BookSide` and that those are the orders of the given
+    /// `Side` in `orders`
+    #[cfg(test)]
+    pub(super) fn assert_invariants(&self, orders: &HashMap<OrderId, Order>) {
+        let prices: Vec<Price> = self.prices().collect();
+        assert!(
+            prices.windows(2).all(|pair| match self.side {
+                Side::Ask => pair[0] < pair[1],
+                Side::Bid => pair[0] > pair[1],
+            }),
+            "prices out of order {prices:?}"
+        );
+        assert_eq!(self.get_best_price(), prices.first());
+        if let Levels::Map { price_levels, .. } = &self.levels {
+            assert_eq!(price_levels.len(), prices.len(), "levels without a price");
+        }
+        let mut level_orders = HashMap::new();
+        for &price in &prices {
+            let price_level = self.level(price).expect("level of a listed price");
+            price_level.assert_invariants();
+            let resting = price_level.get_orders();
+            assert!(!resting.is_empty(), "empty level at {price}");
+            for order in resting {
+                assert_eq!((order.price, order.side), (price, self.side));
+                level_orders.insert(order.id, order);
             }
-            None => None,
         }
+        assert_eq!(level_orders, self.map, "levels and orders of the side");
+        let side_orders: HashMap<OrderId, Order> = orders
+            .iter()
+            .filter(|(_, order)| order.side == self.side)
+            .map(|(&id, &order)| (id, order))
+            .collect();
+        assert_eq!(self.map, side_orders, "orders of the book and the side");
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::{BookSide, Order, OrderId, Side};
+    use crate::{BookConfig, BookSide, Order, OrderId, Side};
 
     #[test]
     fn insert() {
         // Setup
         let side = Side::Ask;
-        let mut bs = BookSide::new(side);
+        let mut bs = BookSide::new(side, BookConfig::default());
         let price = 69;
         let qty = 420;
         let id: OrderId = 1;
@@ -140,10 +398,10 @@ mod test {
 
         // Assert
         assert!(bs.map.contains_key(&id));
-        assert!(bs.price_levels.contains_key(&price));
+        assert!(bs.get_total_qty(price).is_some());
         assert_eq!(bs.get_total_qty(price), Some(qty));
-        assert_eq!(bs.prices.len(), 1);
-        let best_price = bs.prices.first().unwrap();
+        assert_eq!(bs.get_depth().len(), 1);
+        let best_price = bs.get_best_price().unwrap();
         assert_eq!(*best_price, price);
     }
 
@@ -151,7 +409,7 @@ mod test {
     fn remove() {
         // Setup
         let side = Side::Ask;
-        let mut bs = BookSide::new(side);
+        let mut bs = BookSide::new(side, BookConfig::default());
         let price = 69;
         let qty = 420;
         let id: OrderId = 1;
@@ -167,14 +425,67 @@ mod test {
 
         // Act
         assert!(!bs.map.contains_key(&id));
-        assert!(bs.prices.is_empty());
+        assert!(bs.get_total_qty(price).is_none());
+        assert!(bs.get_best_price().is_none());
+    }
+
+    #[test]
+    fn remove_keeps_level_of_zero_qty_orders() {
+        // Setup
+        let side = Side::Ask;
+        let mut bs = BookSide::new(side, BookConfig::default());
+        let price = 69;
+        bs.insert(&Order::new(price, 0, side, 1));
+        bs.insert(&Order::new(price, 10, side, 2));
+
+        // Act
+        bs.remove(2);
+        let (orders, qty) = bs.get_orders_till_qty(price, 5).unwrap();
+
+        // Assert
+        assert_eq!(orders, vec![Order::new(price, 0, side, 1)]);
+        assert_eq!(qty, 0);
+        assert!(bs.map.is_empty());
+        assert!(bs.get_best_price().is_none());
+    }
+
+    #[test]
+    fn reduce() {
+        // Setup
+        let side = Side::Bid;
+        let mut bs = BookSide::new(side, BookConfig::default());
+        let price = 69;
+        let qty = 420;
+        let id: OrderId = 1;
+        let order = Order {
+            price,
+            qty,
+            side,
+            id,
+        };
+        bs.insert(&order);
+
+        // Act
+        bs.reduce(id, 20);
+
+        // Assert
+        assert_eq!(bs.map.get(&id).unwrap().qty, qty - 20);
+        assert_eq!(bs.get_total_qty(price), Some(qty - 20));
+
+        // Act
+        bs.reduce(id, qty);
+
+        // Assert
+        assert!(!bs.map.contains_key(&id));
+        assert_eq!(bs.get_total_qty(price), None);
+        assert!(bs.get_best_price().is_none());
     }
 
     #[test]
     fn get_best_price_ask() {
         // Setup
         let side = Side::Ask;
-        let mut bs = BookSide::new(side);
+        let mut bs = BookSide::new(side, BookConfig::default());
         // First order
         let price = 69;
         let qty = 420;
@@ -202,14 +513,14 @@ mod test {
         let best_price = bs.get_best_price();
 
         // Assert
-        assert_eq!(best_price, Some(&o2.price));
+        assert_eq!(best_price, Some(&o1.price));
     }
 
     #[test]
     fn get_best_price_bid() {
         // Setup
         let side = Side::Bid;
-        let mut bs = BookSide::new(side);
+        let mut bs = BookSide::new(side, BookConfig::default());
         // First order
         let price = 69;
         let qty = 420;
@@ -237,14 +548,14 @@ mod test {
         let best_price = bs.get_best_price();
 
         // Assert
-        assert_eq!(best_price, Some(&o1.price));
+        assert_eq!(best_price, Some(&o2.price));
     }
 
     #[test]
     fn get_total_qty() {
         // Setup
         let side = Side::Bid;
-        let mut bs = BookSide::new(side);
+        let mut bs = BookSide::new(side, BookConfig::default());
         // First order
         let price = 69;
         let qty = 420;
@@ -279,7 +590,7 @@ mod test {
     fn get_till_qty() {
         // Setup
         let side = Side::Bid;
-        let mut bs = BookSide::new(side);
+        let mut bs = BookSide::new(side, BookConfig::default());
         // First order
         let price = 69;
         let qty = 420;
@@ -320,5 +631,58 @@ mod test {
         let item = items.get(1).unwrap();
         assert_eq!(item.qty, qty);
         assert!(!bs.map.contains_key(&id_2));
+        assert!(bs.get_best_price().is_none());
+        assert!(bs.get_total_qty(price).is_none());
+    }
+
+    #[test]
+    fn get_till_qty_partial() {
+        // Setup
+        let side = Side::Bid;
+        let mut bs = BookSide::new(side, BookConfig::default());
+        let price = 69;
+        let qty = 420;
+        let id: OrderId = 1;
+        let order = Order {
+            price,
+            qty,
+            side,
+            id,
+        };
+        bs.insert(&order);
+
+        // Act
+        let res = bs.get_orders_till_qty(price, 20);
+
+        // Assert
+        let (items, total_qty) = res.unwrap();
+        assert_eq!(total_qty, 20);
+        assert_eq!(items.first().unwrap().side, side);
+        assert_eq!(bs.map.get(&id).unwrap().qty, qty - 20);
+        assert_eq!(bs.get_depth(), vec![(price, qty - 20)]);
+    }
+
+    #[test]
+    fn get_depth() {
+        // Setup
+        let side = Side::Ask;
+        let mut bs = BookSide::new(side, BookConfig::default());
+        let qty = 420;
+        for (id, price) in [(1, 70), (2, 69), (3, 70)] {
+            bs.insert(&Order {
+                price,
+                qty,
+                side,
+                id,
+            });
+        }
+
+        // Act
+        let depth = bs.get_depth();
+
+        // Assert
+        assert_eq!(depth, vec![(69, qty), (70, qty * 2)]);
+        let ids: Vec<OrderId> = bs.get_orders(70).unwrap().iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![1, 3]);
     }
 }
@@ -0,0 +1,350 @@
+//! Single writer, multi reader order book
+//!
+//! A [`ConcurrentOrderBook`] owns the [`OrderBook`] and is the only writer, so matching never
+//! waits for readers. After every mutation it publishes the top levels of both sides through a
+//! seqlock. Any number of [`BookReader`]'s on other threads copy a consistent [`Snapshot`]
+//! without taking a lock, retrying only when they overlap with a publish.
+//!
+//! All published values are atomics, a torn copy is detected by the sequence number changing
+//! while it was read.
+use crate::{Order, OrderBook, OrderBookError, OrderId, Price, Qty, Side, Trade};
+use alloc::{boxed::Box, vec::Vec};
+use serde::{Deserialize, Serialize};
+
+#[cfg(not(loom))]
+use alloc::sync::Arc;
+#[cfg(not(loom))]
+use core::{
+    hint,
+    sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering},
+};
+#[cfg(loom)]
+use loom::{
+    hint,
+    sync::{
+        atomic::{fence, AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Consistent copy of the published top of book
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// Number of publishes since construction, increasing with every mutation
+    pub version: u64,
+    /// Best bids first
+    pub bids: Vec<(Price, Qty)>,
+    /// Best asks first
+    pub asks: Vec<(Price, Qty)>,
+}
+
+impl Snapshot {
+    /// Function gets the published depth of a side
+    #[must_use]
+    pub fn depth(&self, side: Side) -> &[(Price, Qty)] {
+        match side {
+            Side::Bid => &self.bids,
+            Side::Ask => &self.asks,
+        }
+    }
+
+    /// Function gets the best price of a side
+    ///
+    /// Returns [`None`] if the side is empty
+    #[must_use]
+    pub fn best_price(&self, side: Side) -> Option<Price> {
+        self.depth(side).first().map(|&(price, _)| price)
+    }
+}
+
+/// Published levels of one side
+#[derive(Debug)]
+struct Levels {
+    len: AtomicUsize,
+    prices: Box<[AtomicU64]>,
+    qtys: Box<[AtomicU64]>,
+}
+
+impl Levels {
+    fn new(capacity: usize) -> Self {
+        Self {
+            len: AtomicUsize::new(0),
+            prices: (0..capacity).map(|_| AtomicU64::new(0)).collect(),
+            qtys: (0..capacity).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Stores the first levels of `depth` that fit, without allocating
+    fn store(&self, depth: impl Iterator<Item = (Price, Qty)>) {
+        let mut len = 0;
+        for ((price, qty), (level_price, level_qty)) in
+            self.prices.iter().zip(self.qtys.iter()).zip(depth)
+        {
+            price.store(level_price, Ordering::Relaxed);
+            qty.store(level_qty, Ordering::Relaxed);
+            len += 1;
+        }
+        self.len.store(len, Ordering::Relaxed);
+    }
+
+    fn load(&self, depth: &mut Vec<(Price, Qty)>) {
+        depth.clear();
+        let len = self.len.load(Ordering::Relaxed).min(self.prices.len());
+        depth.extend(
+            self.prices[..len]
+                .iter()
+                .zip(&self.qtys[..len])
+                .map(|(price, qty)| (price.load(Ordering::Relaxed), qty.load(Ordering::Relaxed))),
+        );
+    }
+}
+
+/// State shared between the writer and its readers
+#[derive(Debug)]
+struct Published {
+    /// Odd while a publish is in progress
+    seq: AtomicU64,
+    bids: Levels,
+    asks: Levels,
+}
+
+impl Published {
+    fn new(levels: usize) -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            bids: Levels::new(levels),
+            asks: Levels::new(levels),
+        }
+    }
+
+    fn store(&self, book: &OrderBook) {
+        self.bids.store(book.get_levels(Side::Bid));
+        self.asks.store(book.get_levels(Side::Ask));
+    }
+
+    /// Must only be called by the single writer
+    fn publish(&self, book: &OrderBook) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq + 1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        self.store(book);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    fn try_read(&self, snapshot: &mut Snapshot) -> bool {
+        let before = self.seq.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            return false;
+        }
+        self.bids.load(&mut snapshot.bids);
+        self.asks.load(&mut snapshot.asks);
+        fence(Ordering::Acquire);
+        snapshot.version = before / 2;
+        self.seq.load(Ordering::Relaxed) == before
+    }
+}
+
+/// Order book with a single writer publishing to lock-free readers
+#[derive(Debug)]
+pub struct ConcurrentOrderBook {
+    book: OrderBook,
+    published: Arc<Published>,
+}
+
+impl ConcurrentOrderBook {
+    /// Constructor function, publishing up to `levels` price levels per side
+    #[must_use]
+    pub fn new(levels: usize) -> Self {
+        Self::with_book(OrderBook::new(), levels)
+    }
+
+    /// Constructor function taking over an existing book
+    #[must_use]
+    pub fn with_book(book: OrderBook, levels: usize) -> Self {
+        let published = Arc::new(Published::new(levels));
+        published.store(&book);
+        Self { book, published }
+    }
+
+    /// Function creates a reader of the published top of book, it can be sent to other threads
+    #[must_use]
+    pub fn reader(&self) -> BookReader {
+        BookReader {
+            published: Arc::clone(&self.published),
+        }
+    }
+
+    /// Function gets the order book, the writer reads it directly
+    #[must_use]
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    /// Function applies several mutations and publishes once, readers see all or none of them
+    pub fn update<R>(&mut self, f: impl FnOnce(&mut OrderBook) -> R) -> R {
+        let result = f(&mut self.book);
+        self.published.publish(&self.book);
+        result
+    }
+
+    /// Function inserts an order and publishes, see [`OrderBook::insert`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is already in the book
+    pub fn insert(&mut self, order: Order) -> Result<(), OrderBookError> {
+        self.update(|book| book.insert(order))
+    }
+
+    /// Function removes an order and publishes, see [`OrderBook::remove`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is unknown
+    pub fn remove(&mut self, id: OrderId) -> Result<(), OrderBookError> {
+        self.update(|book| book.remove(id))
+    }
+
+    /// Function reduces an order and publishes, see [`OrderBook::reduce`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is unknown
+    pub fn reduce(&mut self, id: OrderId, qty: Qty) -> Result<(), OrderBookError> {
+        self.update(|book| book.reduce(id, qty))
+    }
+
+    /// Function matches an incoming order and publishes, see [`OrderBook::match_order`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is already in the book
+    pub fn match_order(&mut self, order: Order) -> Result<Vec<Trade>, OrderBookError> {
+        self.update(|book| book.match_order(order))
+    }
+}
+
+/// Lock-free reader of a [`ConcurrentOrderBook`]
+#[derive(Clone, Debug)]
+pub struct BookReader {
+    published: Arc<Published>,
+}
+
+impl BookReader {
+    /// Function copies the published top of book, retrying while a publish is in progress
+    #[must_use]
+    pub fn snapshot(&self) -> Snapshot {
+        let mut snapshot = Snapshot::default();
+        self.snapshot_into(&mut snapshot);
+        snapshot
+    }
+
+    /// Function copies the published top of book into an existing snapshot, reusing its buffers
+    pub fn snapshot_into(&self, snapshot: &mut Snapshot) {
+        while !self.published.try_read(snapshot) {
+            hint::spin_loop();
+        }
+    }
+
+    /// Function copies the published top of book with a single attempt
+    ///
+    /// Returns [`None`] if the copy overlapped with a publish
+    #[must_use]
+    pub fn try_snapshot(&self) -> Option<Snapshot> {
+        let mut snapshot = Snapshot::default();
+        self.published.try_read(&mut snapshot).then_some(snapshot)
+    }
+
+    /// Function gets the published best price of a side
+    ///
+    /// Returns [`None`] if the side is empty
+    #[must_use]
+    pub fn best_price(&self, side: Side) -> Option<Price> {
+        self.snapshot().best_price(side)
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod test {
+    use super::ConcurrentOrderBook;
+    use crate::{Order, Side};
+
+    #[test]
+    fn publishes_top_levels() {
+        // Setup
+        let mut book = ConcurrentOrderBook::new(2);
+        let reader = book.reader();
+
+        // Act
+        for (id, price) in (1..).zip([97, 98, 99]) {
+            book.insert(Order::new(price, 10, Side::Bid, id)).unwrap();
+        }
+        book.insert(Order::new(101, 5, Side::Ask, 4)).unwrap();
+        let before = reader.snapshot();
+        book.match_order(Order::new(99, 15, Side::Ask, 5)).unwrap();
+        let after = reader.snapshot();
+
+        // Assert
+        assert_eq!(before.version, 4);
+        assert_eq!(before.bids, vec![(99, 10), (98, 10)]);
+        assert_eq!(before.asks, vec![(101, 5)]);
+        assert_eq!(after.version, 5);
+        assert_eq!(after.bids, vec![(98, 10), (97, 10)]);
+        assert_eq!(after.asks, vec![(99, 5), (101, 5)]);
+        assert_eq!(reader.best_price(Side::Ask), Some(99));
+    }
+
+    #[test]
+    fn update_publishes_once() {
+        // Setup
+        let mut book = ConcurrentOrderBook::new(5);
+        let reader = book.reader();
+
+        // Act
+        book.update(|book| {
+            book.insert(Order::new(100, 1, Side::Bid, 1)).unwrap();
+            book.insert(Order::new(101, 1, Side::Ask, 2)).unwrap();
+        });
+
+        // Assert
+        let snapshot = reader.try_snapshot().unwrap();
+        assert_eq!(snapshot.version, 1);
+        assert_eq!(snapshot.best_price(Side::Bid), Some(100));
+        assert_eq!(snapshot.best_price(Side::Ask), Some(101));
+    }
+}
+
+#[cfg(all(test, loom))]
+mod loom_test {
+    use super::{ConcurrentOrderBook, Snapshot};
+    use crate::{Order, Side};
+
+    /// The book is empty at version 0 and has a bid at 1 and an ask at 11 at version 1
+    fn consistent(snapshot: &Snapshot) {
+        match snapshot.version {
+            0 => assert!(snapshot.bids.is_empty() && snapshot.asks.is_empty()),
+            _ => {
+                assert_eq!(snapshot.bids, vec![(1, 1)]);
+                assert_eq!(snapshot.asks, vec![(11, 1)]);
+            }
+        }
+    }
+
+    #[test]
+    fn no_torn_reads() {
+        loom::model(|| {
+            let mut book = ConcurrentOrderBook::new(1);
+            let reader = book.reader();
+            let thread = loom::thread::spawn(move || {
+                if let Some(snapshot) = reader.try_snapshot() {
+                    consistent(&snapshot);
+                }
+            });
+            book.update(|book| {
+                book.insert(Order::new(1, 1, Side::Bid, 1)).unwrap();
+                book.insert(Order::new(11, 1, Side::Ask, 2)).unwrap();
+            });
+            thread.join().unwrap();
+        });
+    }
+}
@@ -1,12 +1,17 @@
 #![allow(clippy::module_name_repetitions)]
-use crate::OrderId;
+use crate::{OrderId, Price};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
+#[non_exhaustive]
 pub enum OrderBookError {
     #[error("OrderId not found")]
     UnknownId(OrderId),
     #[error("Duplicate OrderId {0}")]
     DuplicateOrderId(OrderId),
+    #[error("Qty of OrderId {0} is zero")]
+    InvalidQty(OrderId),
+    #[error("Price {price} of OrderId {id} is not on the price ladder")]
+    InvalidPrice { id: OrderId, price: Price },
 }
@@ -0,0 +1,750 @@
+//! Binary market data feed
+//!
+//! Fixed-layout, big-endian messages modelled on ITCH 5.0, framed in MoldUDP64 packets. A
+//! [`Publisher`] owns an [`OrderBook`] and encodes every mutation, a [`Consumer`] decodes the
+//! packets and rebuilds the same [`OrderBook`].
+//!
+//! Example:
+//! ```
+//! use orderbook::feed::{Consumer, Publisher};
+//! use orderbook::{Order, Side};
+//!
+//! let mut publisher = Publisher::new(*b"SESSION001", 1);
+//! publisher.insert(Order::new(69, 420, Side::Ask, 1)).unwrap();
+//! publisher.match_order(Order::new(69, 20, Side::Bid, 2)).unwrap();
+//!
+//! let mut consumer = Consumer::new();
+//! for packet in publisher.flush() {
+//!     consumer.apply(&packet).unwrap();
+//! }
+//! assert_eq!(consumer.book(1).unwrap().get_total_qty(69, Side::Ask), Some(400));
+//! ```
+use crate::HashMap;
+use crate::{Order, OrderBook, OrderBookError, OrderId, Price, Qty, Side, Trade};
+use alloc::vec::Vec;
+use thiserror::Error;
+
+/// Identifies the instrument of a message within a session
+pub type Locate = u16;
+/// Sequence number of a message within a session
+pub type SequenceNumber = u64;
+
+/// Length of the MoldUDP64 packet header
+pub const PACKET_HEADER_LEN: usize = 20;
+/// Maximum length of an encoded packet, fits in a single Ethernet frame
+pub const MAX_PACKET_LEN: usize = 1400;
+const MESSAGE_HEADER_LEN: usize = 11;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FeedError {
+    #[error("Truncated packet")]
+    Truncated,
+    #[error("Invalid length {len} for message type {kind:?}")]
+    InvalidLength { kind: char, len: usize },
+    #[error("Unknown message type {0:?}")]
+    UnknownMessageType(char),
+    #[error("Invalid side {0}")]
+    InvalidSide(u8),
+    #[error("Sequence gap, expected {expected} received {received}")]
+    SequenceGap {
+        expected: SequenceNumber,
+        received: SequenceNumber,
+    },
+    #[error(transparent)]
+    OrderBook(#[from] OrderBookError),
+}
+
+/// Body of a feed [`Message`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    /// `A`, a new order rests in the book
+    Add(Order),
+    /// `E`, a resting order is (partially) executed
+    Execute {
+        id: OrderId,
+        qty: Qty,
+        match_number: u64,
+    },
+    /// `X`, a resting order is partially cancelled
+    Cancel { id: OrderId, qty: Qty },
+    /// `D`, a resting order is removed
+    Delete { id: OrderId },
+    /// `U`, a resting order is replaced by a new order on the same side
+    Replace {
+        id: OrderId,
+        new_id: OrderId,
+        price: Price,
+        qty: Qty,
+    },
+    /// `P`, informational trade between an incoming and a resting order
+    Trade { trade: Trade, match_number: u64 },
+}
+
+/// A single feed message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Message {
+    pub locate: Locate,
+    /// Set by the publisher, see [`Publisher::set_timestamp`]
+    pub timestamp: u64,
+    pub kind: MessageKind,
+}
+
+impl Message {
+    /// Function appends the encoded message, without length prefix, to `buf`
+    pub fn encode(&self, buf: &mut Vec<u8>) {
+        let kind = match self.kind {
+            MessageKind::Add(..) => b'A',
+            MessageKind::Execute { .. } => b'E',
+            MessageKind::Cancel { .. } => b'X',
+            MessageKind::Delete { .. } => b'D',
+            MessageKind::Replace { .. } => b'U',
+            MessageKind::Trade { .. } => b'P',
+        };
+        buf.push(kind);
+        buf.extend_from_slice(&self.locate.to_be_bytes());
+        buf.extend_from_slice(&self.timestamp.to_be_bytes());
+        let mut put = |value: u64| buf.extend_from_slice(&value.to_be_bytes());
+        match self.kind {
+            MessageKind::Add(order) => {
+                put(order.id);
+                put(order.price);
+                put(order.qty);
+                buf.push(encode_side(order.side));
+            }
+            MessageKind::Execute {
+                id,
+                qty,
+                match_number,
+            } => {
+                put(id);
+                put(qty);
+                put(match_number);
+            }
+            MessageKind::Cancel { id, qty } => {
+                put(id);
+                put(qty);
+            }
+            MessageKind::Delete { id } => put(id),
+            MessageKind::Replace {
+                id,
+                new_id,
+                price,
+                qty,
+            } => {
+                put(id);
+                put(new_id);
+                put(price);
+                put(qty);
+            }
+            MessageKind::Trade {
+                trade,
+                match_number,
+            } => {
+                put(match_number);
+                put(trade.maker_id);
+                put(trade.taker_id);
+                put(trade.price);
+                put(trade.qty);
+                buf.push(encode_side(trade.side));
+            }
+        }
+    }
+
+    /// Function decodes a single message, without length prefix
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] on an unknown message type or a length not matching the message type
+    pub fn decode(buf: &[u8]) -> Result<Self, FeedError> {
+        let Some(&kind) = buf.first() else {
+            return Err(FeedError::Truncated);
+        };
+        let expected = MESSAGE_HEADER_LEN
+            + match kind {
+                b'A' => 25,
+                b'E' => 24,
+                b'X' => 16,
+                b'D' => 8,
+                b'U' => 32,
+                b'P' => 41,
+                _ => return Err(FeedError::UnknownMessageType(kind as char)),
+            };
+        if buf.len() != expected {
+            return Err(FeedError::InvalidLength {
+                kind: kind as char,
+                len: buf.len(),
+            });
+        }
+        let locate = u16::from_be_bytes([buf[1], buf[2]]);
+        let body = &buf[MESSAGE_HEADER_LEN..];
+        let get = |idx: usize| be_u64(&body[idx * 8..]);
+        let kind = match kind {
+            b'A' => MessageKind::Add(Order {
+                id: get(0),
+                price: get(1),
+                qty: get(2),
+                side: decode_side(body[24])?,
+            }),
+            b'E' => MessageKind::Execute {
+                id: get(0),
+                qty: get(1),
+                match_number: get(2),
+            },
+            b'X' => MessageKind::Cancel {
+                id: get(0),
+                qty: get(1),
+            },
+            b'D' => MessageKind::Delete { id: get(0) },
+            b'U' => MessageKind::Replace {
+                id: get(0),
+                new_id: get(1),
+                price: get(2),
+                qty: get(3),
+            },
+            _ => MessageKind::Trade {
+                match_number: get(0),
+                trade: Trade {
+                    maker_id: get(1),
+                    taker_id: get(2),
+                    price: get(3),
+                    qty: get(4),
+                    side: decode_side(body[40])?,
+                },
+            },
+        };
+        Ok(Self {
+            locate,
+            timestamp: be_u64(&buf[3..]),
+            kind,
+        })
+    }
+}
+
+/// A decoded MoldUDP64 packet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Packet<'a> {
+    pub session: &'a [u8; 10],
+    /// Sequence number of the first message in the packet
+    pub sequence: SequenceNumber,
+    pub count: u16,
+    payload: &'a [u8],
+}
+
+impl<'a> Packet<'a> {
+    /// Function parses the MoldUDP64 header of a packet
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the packet is shorter than its header
+    pub fn parse(buf: &'a [u8]) -> Result<Self, FeedError> {
+        if buf.len() < PACKET_HEADER_LEN {
+            return Err(FeedError::Truncated);
+        }
+        Ok(Self {
+            session: buf[..10].try_into().expect("length checked"),
+            sequence: be_u64(&buf[10..]),
+            count: u16::from_be_bytes([buf[18], buf[19]]),
+            payload: &buf[PACKET_HEADER_LEN..],
+        })
+    }
+
+    /// Function decodes the messages of the packet in sequence order
+    pub fn messages(&self) -> impl Iterator<Item = Result<Message, FeedError>> + 'a {
+        let mut rest = self.payload;
+        (0..self.count).map_while(move |_| {
+            if rest.len() < 2 {
+                rest = &[];
+                return Some(Err(FeedError::Truncated));
+            }
+            let len = usize::from(u16::from_be_bytes([rest[0], rest[1]]));
+            let Some(msg) = rest.get(2..2 + len) else {
+                rest = &[];
+                return Some(Err(FeedError::Truncated));
+            };
+            rest = &rest[2 + len..];
+            Some(Message::decode(msg))
+        })
+    }
+}
+
+/// Owns an [`OrderBook`] and publishes every mutation as a feed [`Message`]
+pub struct Publisher {
+    book: OrderBook,
+    session: [u8; 10],
+    locate: Locate,
+    timestamp: u64,
+    next_sequence: SequenceNumber,
+    match_number: u64,
+    pending: Vec<Message>,
+}
+
+impl Publisher {
+    /// Constructor function
+    #[must_use]
+    pub fn new(session: [u8; 10], locate: Locate) -> Self {
+        Self {
+            book: OrderBook::new(),
+            session,
+            locate,
+            timestamp: 0,
+            next_sequence: 1,
+            match_number: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+
+    /// Function sets the timestamp of subsequent messages
+    pub fn set_timestamp(&mut self, timestamp: u64) {
+        self.timestamp = timestamp;
+    }
+
+    /// Function inserts a new [`Order`], see [`OrderBook::insert`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the given `id` is already in the orderbook
+    pub fn insert(&mut self, order: Order) -> Result<(), OrderBookError> {
+        self.book.insert(order)?;
+        self.push(MessageKind::Add(order));
+        Ok(())
+    }
+
+    /// Function removes an [`Order`], see [`OrderBook::remove`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the order with the given `OrderId` is not present
+    pub fn remove(&mut self, id: OrderId) -> Result<(), OrderBookError> {
+        self.book.remove(id)?;
+        self.push(MessageKind::Delete { id });
+        Ok(())
+    }
+
+    /// Function reduces the `Qty` of an [`Order`], see [`OrderBook::reduce`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the order with the given `OrderId` is not present
+    pub fn reduce(&mut self, id: OrderId, qty: Qty) -> Result<(), OrderBookError> {
+        let resting = self
+            .book
+            .get_order(id)
+            .ok_or(OrderBookError::UnknownId(id))?
+            .qty;
+        self.book.reduce(id, qty)?;
+        self.push(MessageKind::Cancel {
+            id,
+            qty: qty.min(resting),
+        });
+        Ok(())
+    }
+
+    /// Function replaces an [`Order`] with a new order on the same `Side`, the new order loses
+    /// the time priority of the original order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the original order is not present, `new_id` is already in the orderbook
+    /// or the new order has a zero `Qty` or a `Price` the book does not accept, in which case the
+    /// book is left unchanged
+    pub fn replace(
+        &mut self,
+        id: OrderId,
+        new_id: OrderId,
+        price: Price,
+        qty: Qty,
+    ) -> Result<(), OrderBookError> {
+        let side = self
+            .book
+            .get_order(id)
+            .ok_or(OrderBookError::UnknownId(id))?
+            .side;
+        if id != new_id && self.book.get_order(new_id).is_some() {
+            return Err(OrderBookError::DuplicateOrderId(new_id));
+        }
+        let order = Order::new(price, qty, side, new_id);
+        OrderBook::check_qty(&order)?;
+        self.book.check_price(&order)?;
+        self.book.remove(id)?;
+        self.book.insert(order)?;
+        self.push(MessageKind::Replace {
+            id,
+            new_id,
+            price,
+            qty,
+        });
+        Ok(())
+    }
+
+    /// Function matches an incoming [`Order`], see [`OrderBook::match_order`]
+    ///
+    /// Publishes an execution and a trade per [`Trade`], followed by an add if the order rests
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the given `id` is already in the orderbook
+    pub fn match_order(&mut self, order: Order) -> Result<Vec<Trade>, OrderBookError> {
+        let trades = self.book.match_order(order)?;
+        for trade in &trades {
+            self.match_number += 1;
+            let match_number = self.match_number;
+            self.push(MessageKind::Execute {
+                id: trade.maker_id,
+                qty: trade.qty,
+                match_number,
+            });
+            self.push(MessageKind::Trade {
+                trade: *trade,
+                match_number,
+            });
+        }
+        if let Some(rest) = self.book.get_order(order.id) {
+            self.push(MessageKind::Add(*rest));
+        }
+        Ok(trades)
+    }
+
+    /// Function encodes all pending messages into MoldUDP64 packets of at most
+    /// [`MAX_PACKET_LEN`] bytes
+    pub fn flush(&mut self) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        let mut packet = Vec::with_capacity(MAX_PACKET_LEN);
+        let mut count: u16 = 0;
+        let mut msg_buf = Vec::new();
+        for msg in core::mem::take(&mut self.pending) {
+            msg_buf.clear();
+            msg.encode(&mut msg_buf);
+            if count > 0 && packet.len() + 2 + msg_buf.len() > MAX_PACKET_LEN {
+                packets.push(self.finish(packet, count));
+                packet = Vec::with_capacity(MAX_PACKET_LEN);
+                count = 0;
+            }
+            if count == 0 {
+                packet.resize(PACKET_HEADER_LEN, 0);
+            }
+            let len = u16::try_from(msg_buf.len()).expect("messages are shorter than u16::MAX");
+            packet.extend_from_slice(&len.to_be_bytes());
+            packet.extend_from_slice(&msg_buf);
+            count += 1;
+        }
+        if count > 0 {
+            packets.push(self.finish(packet, count));
+        }
+        packets
+    }
+
+    /// Writes the MoldUDP64 header and advances the sequence number
+    fn finish(&mut self, mut packet: Vec<u8>, count: u16) -> Vec<u8> {
+        packet[..10].copy_from_slice(&self.session);
+        packet[10..18].copy_from_slice(&self.next_sequence.to_be_bytes());
+        packet[18..20].copy_from_slice(&count.to_be_bytes());
+        self.next_sequence += SequenceNumber::from(count);
+        packet
+    }
+
+    fn push(&mut self, kind: MessageKind) {
+        self.pending.push(Message {
+            locate: self.locate,
+            timestamp: self.timestamp,
+            kind,
+        });
+    }
+}
+
+/// Rebuilds an [`OrderBook`] per [`Locate`] from feed packets
+pub struct Consumer {
+    books: HashMap<Locate, OrderBook>,
+    next_sequence: SequenceNumber,
+}
+
+impl Consumer {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            books: HashMap::new(),
+            next_sequence: 1,
+        }
+    }
+
+    /// Function applies the messages of a packet to the books
+    ///
+    /// Messages that were already applied, e.g. from a retransmission, are skipped
+    ///
+    /// Returns the newly applied messages
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] on a malformed packet, a sequence gap or a message that does not apply to
+    /// the book
+    pub fn apply(&mut self, buf: &[u8]) -> Result<Vec<Message>, FeedError> {
+        let packet = Packet::parse(buf)?;
+        if packet.sequence > self.next_sequence {
+            return Err(FeedError::SequenceGap {
+                expected: self.next_sequence,
+                received: packet.sequence,
+            });
+        }
+        let mut applied = Vec::new();
+        for (sequence, msg) in (packet.sequence..).zip(packet.messages()) {
+            let msg = msg?;
+            if sequence < self.next_sequence {
+                continue;
+            }
+            let book = self.books.entry(msg.locate).or_default();
+            match msg.kind {
+                MessageKind::Add(order) => book.insert(order)?,
+                MessageKind::Execute { id, qty, .. } | MessageKind::Cancel { id, qty } => {
+                    book.reduce(id, qty)?;
+                }
+                MessageKind::Delete { id } => book.remove(id)?,
+                MessageKind::Replace {
+                    id,
+                    new_id,
+                    price,
+                    qty,
+                } => {
+                    let side = book
+                        .get_order(id)
+                        .ok_or(OrderBookError::UnknownId(id))?
+                        .side;
+                    book.remove(id)?;
+                    book.insert(Order::new(price, qty, side, new_id))?;
+                }
+                MessageKind::Trade { .. } => (),
+            }
+            self.next_sequence = sequence + 1;
+            applied.push(msg);
+        }
+        Ok(applied)
+    }
+
+    /// Function gets the sequence number of the next expected message
+    #[must_use]
+    pub fn next_sequence(&self) -> SequenceNumber {
+        self.next_sequence
+    }
+
+    /// Function gets the book for the given [`Locate`]
+    pub fn book(&self, locate: Locate) -> Option<&OrderBook> {
+        self.books.get(&locate)
+    }
+}
+
+impl Default for Consumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn encode_side(side: Side) -> u8 {
+    match side {
+        Side::Bid => b'B',
+        Side::Ask => b'S',
+    }
+}
+
+fn decode_side(side: u8) -> Result<Side, FeedError> {
+    match side {
+        b'B' => Ok(Side::Bid),
+        b'S' => Ok(Side::Ask),
+        side => Err(FeedError::InvalidSide(side)),
+    }
+}
+
+fn be_u64(buf: &[u8]) -> u64 {
+    u64::from_be_bytes(buf[..8].try_into().expect("length checked"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Consumer, FeedError, Message, MessageKind, Packet, Publisher, MAX_PACKET_LEN};
+    use crate::{Order, OrderBook, OrderBookError, Side, Trade};
+
+    const LOCATE: u16 = 3;
+
+    /// Asserts both books hold the same orders in the same FIFO order
+    fn assert_same_book(a: &OrderBook, b: &OrderBook) {
+        for side in [Side::Ask, Side::Bid] {
+            let depth = a.get_depth(side);
+            assert_eq!(depth, b.get_depth(side));
+            for (price, _) in depth {
+                assert_eq!(a.get_orders(price, side), b.get_orders(price, side));
+            }
+        }
+    }
+
+    #[test]
+    fn encode_decode() {
+        // Setup
+        let trade = Trade {
+            maker_id: 1,
+            taker_id: 2,
+            price: 69,
+            qty: 420,
+            side: Side::Bid,
+        };
+        let kinds = [
+            MessageKind::Add(Order::new(69, 420, Side::Ask, 1)),
+            MessageKind::Execute {
+                id: 1,
+                qty: 20,
+                match_number: 7,
+            },
+            MessageKind::Cancel { id: 1, qty: 20 },
+            MessageKind::Delete { id: 1 },
+            MessageKind::Replace {
+                id: 1,
+                new_id: 2,
+                price: 70,
+                qty: 10,
+            },
+            MessageKind::Trade {
+                trade,
+                match_number: 7,
+            },
+        ];
+
+        for kind in kinds {
+            let msg = Message {
+                locate: LOCATE,
+                timestamp: 34_200_000_000_000,
+                kind,
+            };
+            let mut buf = vec![];
+
+            // Act
+            msg.encode(&mut buf);
+
+            // Assert
+            assert_eq!(Message::decode(&buf), Ok(msg));
+        }
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(
+            Message::decode(b"Z"),
+            Err(FeedError::UnknownMessageType('Z'))
+        );
+        assert_eq!(
+            Message::decode(b"D12"),
+            Err(FeedError::InvalidLength { kind: 'D', len: 3 })
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        // Setup
+        let mut publisher = Publisher::new(*b"SESSION001", LOCATE);
+        let mut consumer = Consumer::new();
+        publisher.set_timestamp(1);
+        for id in 1..=50 {
+            let side = if id % 2 == 0 { Side::Bid } else { Side::Ask };
+            let price = if side == Side::Bid {
+                60 + id % 7
+            } else {
+                70 + id % 5
+            };
+            publisher
+                .insert(Order::new(price, 100 + id, side, id))
+                .unwrap();
+        }
+        publisher.set_timestamp(2);
+        publisher.reduce(3, 40).unwrap();
+        publisher.remove(4).unwrap();
+        publisher.replace(5, 105, 71, 10).unwrap();
+        let trades = publisher
+            .match_order(Order::new(72, 1_000, Side::Bid, 200))
+            .unwrap();
+        assert!(!trades.is_empty());
+        publisher
+            .match_order(Order::new(50, 2_000, Side::Ask, 201))
+            .unwrap();
+
+        // Act
+        let packets = publisher.flush();
+        let mut applied = vec![];
+        for packet in &packets {
+            applied.extend(consumer.apply(packet).unwrap());
+        }
+
+        // Assert
+        assert!(packets.len() > 1);
+        assert!(packets.iter().all(|packet| packet.len() <= MAX_PACKET_LEN));
+        let book = consumer.book(LOCATE).unwrap();
+        assert_same_book(publisher.book(), book);
+        let published: Vec<Trade> = applied
+            .iter()
+            .filter_map(|msg| match msg.kind {
+                MessageKind::Trade { trade, .. } => Some(trade),
+                _ => None,
+            })
+            .filter(|trade| trade.taker_id == 200)
+            .collect();
+        assert_eq!(published, trades);
+        assert_eq!(consumer.next_sequence(), applied.len() as u64 + 1);
+    }
+
+    #[test]
+    fn rejected_replace_keeps_books_in_sync() {
+        // Setup
+        let mut publisher = Publisher::new(*b"SESSION001", LOCATE);
+        let mut consumer = Consumer::new();
+        publisher.insert(Order::new(69, 420, Side::Ask, 1)).unwrap();
+        publisher.insert(Order::new(60, 10, Side::Bid, 2)).unwrap();
+
+        // Act
+        let zero = publisher.replace(1, 3, 70, 0);
+        let duplicate = publisher.replace(1, 2, 70, 10);
+        publisher.remove(2).unwrap();
+        for packet in &publisher.flush() {
+            consumer.apply(packet).unwrap();
+        }
+
+        // Assert
+        assert_eq!(zero, Err(OrderBookError::InvalidQty(3)));
+        assert_eq!(duplicate, Err(OrderBookError::DuplicateOrderId(2)));
+        assert_eq!(
+            publisher.book().get_order(1),
+            Some(&Order::new(69, 420, Side::Ask, 1))
+        );
+        assert_same_book(publisher.book(), consumer.book(LOCATE).unwrap());
+    }
+
+    #[test]
+    fn sequence_gap_and_retransmission() {
+        // Setup
+        let mut publisher = Publisher::new(*b"SESSION001", LOCATE);
+        let mut consumer = Consumer::new();
+        publisher.insert(Order::new(69, 420, Side::Ask, 1)).unwrap();
+        let first = publisher.flush().remove(0);
+        publisher.insert(Order::new(69, 420, Side::Ask, 2)).unwrap();
+        let second = publisher.flush().remove(0);
+        publisher.insert(Order::new(69, 420, Side::Ask, 3)).unwrap();
+        let third = publisher.flush().remove(0);
+
+        // Act
+        let gap = consumer.apply(&second);
+        consumer.apply(&first).unwrap();
+        consumer.apply(&second).unwrap();
+        let duplicate = consumer.apply(&second).unwrap();
+        consumer.apply(&third).unwrap();
+
+        // Assert
+        assert_eq!(
+            gap,
+            Err(FeedError::SequenceGap {
+                expected: 1,
+                received: 2
+            })
+        );
+        assert!(duplicate.is_empty());
+        assert_eq!(Packet::parse(&third).unwrap().sequence, 3);
+        assert_same_book(publisher.book(), consumer.book(LOCATE).unwrap());
+    }
+}
@@ -0,0 +1,648 @@
+//! NASDAQ TotalView-ITCH 5.0
+//!
+//! Zero-copy parser for the order messages of an ITCH 5.0 `BinaryFILE` and a [`Replay`] that
+//! drives one [`OrderBook`] per stock from them.
+//!
+//! Example:
+//! ```
+//! use orderbook::itch::{Parser, Replay};
+//! use orderbook::Side;
+//!
+//! let bytes: Vec<u8> = std::fs::read("20190130.NASDAQ_ITCH50").unwrap_or_default();
+//! let mut replay = Replay::new();
+//! for msg in Parser::new(&bytes) {
+//!     replay.apply(&msg.unwrap()).unwrap();
+//! }
+//! for stock in replay.stocks() {
+//!     println!("{} {:?}", stock.symbol(), stock.book().get_best_price(Side::Bid));
+//! }
+//! ```
+use crate::HashMap;
+use crate::{Order, OrderBook, OrderBookError, OrderId, Price, Qty, Side};
+use alloc::vec::Vec;
+use thiserror::Error;
+
+/// Nanoseconds since midnight
+pub type TimeStamp = u64;
+/// Locate code identifying a stock for the day
+pub type StockLocate = u16;
+
+const HEADER_LEN: usize = 11;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ItchError {
+    #[error("Truncated message at offset {0}")]
+    Truncated(usize),
+    #[error("Invalid length {len} for message type {kind:?}")]
+    InvalidLength { kind: char, len: usize },
+    #[error("Invalid side {0:?}")]
+    InvalidSide(char),
+    #[error(transparent)]
+    OrderBook(#[from] OrderBookError),
+}
+
+/// Fields shared by every ITCH message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Header {
+    pub stock_locate: StockLocate,
+    pub tracking_number: u16,
+    pub timestamp: TimeStamp,
+}
+
+/// Add Order (`A`), or Add Order with MPID attribution (`F`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddOrder<'a> {
+    pub header: Header,
+    pub order_ref: OrderId,
+    pub side: Side,
+    pub shares: Qty,
+    /// Space padded stock symbol
+    pub stock: &'a [u8; 8],
+    /// Price with 4 implied decimals
+    pub price: Price,
+    /// Only present on `F` messages
+    pub attribution: Option<&'a [u8; 4]>,
+}
+
+/// Order Executed (`E`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderExecuted {
+    pub header: Header,
+    pub order_ref: OrderId,
+    pub executed_shares: Qty,
+    pub match_number: u64,
+}
+
+/// Order Executed With Price (`C`), an execution at a price other than the order price
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderExecutedWithPrice {
+    pub header: Header,
+    pub order_ref: OrderId,
+    pub executed_shares: Qty,
+    pub match_number: u64,
+    /// Whether the execution should be included in volume and price statistics
+    pub printable: bool,
+    /// Price with 4 implied decimals
+    pub execution_price: Price,
+}
+
+/// Order Cancel (`X`), a partial cancellation
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderCancel {
+    pub header: Header,
+    pub order_ref: OrderId,
+    pub cancelled_shares: Qty,
+}
+
+/// Order Delete (`D`)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderDelete {
+    pub header: Header,
+    pub order_ref: OrderId,
+}
+
+/// Order Replace (`U`), the new order keeps the side of the original order
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrderReplace {
+    pub header: Header,
+    pub original_order_ref: OrderId,
+    pub new_order_ref: OrderId,
+    pub shares: Qty,
+    pub price: Price,
+}
+
+/// A single ITCH message borrowed from the underlying buffer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Message<'a> {
+    AddOrder(AddOrder<'a>),
+    OrderExecuted(OrderExecuted),
+    OrderExecutedWithPrice(OrderExecutedWithPrice),
+    OrderCancel(OrderCancel),
+    OrderDelete(OrderDelete),
+    OrderReplace(OrderReplace),
+    /// Any message type that does not affect the order book, with its raw payload
+    Other(&'a [u8]),
+}
+
+impl<'a> Message<'a> {
+    /// Function parses a single message, without the `BinaryFILE` length prefix
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the payload is too short for its message type or holds an invalid side
+    pub fn parse(buf: &'a [u8]) -> Result<Self, ItchError> {
+        let Some(&kind) = buf.first() else {
+            return Err(ItchError::Truncated(0));
+        };
+        let expected = match kind {
+            b'A' => 36,
+            b'F' => 40,
+            b'E' => 31,
+            b'C' => 36,
+            b'X' => 23,
+            b'D' => 19,
+            b'U' => 35,
+            _ => return Ok(Message::Other(buf)),
+        };
+        if buf.len() != expected {
+            return Err(ItchError::InvalidLength {
+                kind: kind as char,
+                len: buf.len(),
+            });
+        }
+        let header = Header {
+            stock_locate: be_u16(&buf[1..]),
+            tracking_number: be_u16(&buf[3..]),
+            timestamp: be_u48(&buf[5..]),
+        };
+        let body = &buf[HEADER_LEN..];
+        let msg = match kind {
+            b'A' | b'F' => Message::AddOrder(AddOrder {
+                header,
+                order_ref: be_u64(body),
+                side: match body[8] {
+                    b'B' => Side::Bid,
+                    b'S' => Side::Ask,
+                    side => return Err(ItchError::InvalidSide(side as char)),
+                },
+                shares: be_u32(&body[9..]).into(),
+                stock: fixed(&body[13..]),
+                price: be_u32(&body[21..]).into(),
+                attribution: (kind == b'F').then(|| fixed(&body[25..])),
+            }),
+            b'E' => Message::OrderExecuted(OrderExecuted {
+                header,
+                order_ref: be_u64(body),
+                executed_shares: be_u32(&body[8..]).into(),
+                match_number: be_u64(&body[12..]),
+            }),
+            b'C' => Message::OrderExecutedWithPrice(OrderExecutedWithPrice {
+                header,
+                order_ref: be_u64(body),
+                executed_shares: be_u32(&body[8..]).into(),
+                match_number: be_u64(&body[12..]),
+                printable: body[20] == b'Y',
+                execution_price: be_u32(&body[21..]).into(),
+            }),
+            b'X' => Message::OrderCancel(OrderCancel {
+                header,
+                order_ref: be_u64(body),
+                cancelled_shares: be_u32(&body[8..]).into(),
+            }),
+            b'D' => Message::OrderDelete(OrderDelete {
+                header,
+                order_ref: be_u64(body),
+            }),
+            _ => Message::OrderReplace(OrderReplace {
+                header,
+                original_order_ref: be_u64(body),
+                new_order_ref: be_u64(&body[8..]),
+                shares: be_u32(&body[16..]).into(),
+                price: be_u32(&body[20..]).into(),
+            }),
+        };
+        Ok(msg)
+    }
+
+    /// Function gets the [`Header`] of the message
+    ///
+    /// Returns [`None`] for [`Message::Other`] messages shorter than a header
+    pub fn header(&self) -> Option<Header> {
+        match self {
+            Message::AddOrder(msg) => Some(msg.header),
+            Message::OrderExecuted(msg) => Some(msg.header),
+            Message::OrderExecutedWithPrice(msg) => Some(msg.header),
+            Message::OrderCancel(msg) => Some(msg.header),
+            Message::OrderDelete(msg) => Some(msg.header),
+            Message::OrderReplace(msg) => Some(msg.header),
+            Message::Other(buf) if buf.len() >= HEADER_LEN => Some(Header {
+                stock_locate: be_u16(&buf[1..]),
+                tracking_number: be_u16(&buf[3..]),
+                timestamp: be_u48(&buf[5..]),
+            }),
+            Message::Other(..) => None,
+        }
+    }
+}
+
+/// Iterator over the messages of an ITCH 5.0 `BinaryFILE`, where every message is prefixed with
+/// its length as a big-endian `u16`
+pub struct Parser<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// Constructor function
+    #[must_use]
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, offset: 0 }
+    }
+
+    /// Function gets the offset of the next message in the buffer
+    #[must_use]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'a> Iterator for Parser<'a> {
+    type Item = Result<Message<'a>, ItchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = &self.buf[self.offset..];
+        if rest.is_empty() {
+            return None;
+        }
+        let len = match rest.get(..2) {
+            Some(len) => usize::from(be_u16(len)),
+            None => return Some(self.fail()),
+        };
+        match rest.get(2..2 + len) {
+            Some(payload) => {
+                self.offset += 2 + len;
+                Some(Message::parse(payload))
+            }
+            None => Some(self.fail()),
+        }
+    }
+}
+
+impl Parser<'_> {
+    /// Reports the truncated message and stops the iterator
+    fn fail<T>(&mut self) -> Result<T, ItchError> {
+        let offset = self.offset;
+        self.offset = self.buf.len();
+        Err(ItchError::Truncated(offset))
+    }
+}
+
+/// The [`OrderBook`] of a single stock
+pub struct StockBook {
+    symbol: [u8; 8],
+    book: OrderBook,
+}
+
+impl StockBook {
+    /// Function gets the stock symbol without padding
+    pub fn symbol(&self) -> &str {
+        core::str::from_utf8(&self.symbol)
+            .unwrap_or_default()
+            .trim_end()
+    }
+
+    pub fn book(&self) -> &OrderBook {
+        &self.book
+    }
+}
+
+/// Rebuilds an [`OrderBook`] per stock from a stream of ITCH [`Message`]'s
+#[derive(Default)]
+pub struct Replay {
+    stocks: HashMap<StockLocate, StockBook>,
+    timestamp: TimeStamp,
+}
+
+impl Replay {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Function applies a [`Message`] to the book of its stock
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the message references an unknown order or adds a duplicate order
+    pub fn apply(&mut self, msg: &Message) -> Result<(), ItchError> {
+        if let Some(header) = msg.header() {
+            self.timestamp = header.timestamp;
+        }
+        match msg {
+            Message::AddOrder(add) => {
+                let stock = self
+                    .stocks
+                    .entry(add.header.stock_locate)
+                    .or_insert_with(|| StockBook {
+                        symbol: *add.stock,
+                        book: OrderBook::new(),
+                    });
+                let order = Order::new(add.price, add.shares, add.side, add.order_ref);
+                stock.book.insert(order)?;
+            }
+            Message::OrderExecuted(exec) => {
+                self.book_mut(exec.header, exec.order_ref)?
+                    .reduce(exec.order_ref, exec.executed_shares)?;
+            }
+            Message::OrderExecutedWithPrice(exec) => {
+                self.book_mut(exec.header, exec.order_ref)?
+                    .reduce(exec.order_ref, exec.executed_shares)?;
+            }
+            Message::OrderCancel(cancel) => {
+                self.book_mut(cancel.header, cancel.order_ref)?
+                    .reduce(cancel.order_ref, cancel.cancelled_shares)?;
+            }
+            Message::OrderDelete(delete) => {
+                self.book_mut(delete.header, delete.order_ref)?
+                    .remove(delete.order_ref)?;
+            }
+            Message::OrderReplace(replace) => {
+                let id = replace.original_order_ref;
+                let book = self.book_mut(replace.header, id)?;
+                let side = book
+                    .get_order(id)
+                    .ok_or(OrderBookError::UnknownId(id))?
+                    .side;
+                book.remove(id)?;
+                let order = Order::new(replace.price, replace.shares, side, replace.new_order_ref);
+                book.insert(order)?;
+            }
+            Message::Other(..) => (),
+        }
+        Ok(())
+    }
+
+    /// Function gets the timestamp of the last applied message
+    #[must_use]
+    pub fn timestamp(&self) -> TimeStamp {
+        self.timestamp
+    }
+
+    /// Function gets the book of the stock with the given locate code
+    pub fn stock(&self, locate: StockLocate) -> Option<&StockBook> {
+        self.stocks.get(&locate)
+    }
+
+    /// Function gets all books, sorted by symbol
+    pub fn stocks(&self) -> Vec<&StockBook> {
+        let mut stocks: Vec<_> = self.stocks.values().collect();
+        stocks.sort_by_key(|stock| stock.symbol);
+        stocks
+    }
+
+    fn book_mut(&mut self, header: Header, id: OrderId) -> Result<&mut OrderBook, ItchError> {
+        self.stocks
+            .get_mut(&header.stock_locate)
+            .map(|stock| &mut stock.book)
+            .ok_or(ItchError::OrderBook(OrderBookError::UnknownId(id)))
+    }
+}
+
+fn fixed<const N: usize>(buf: &[u8]) -> &[u8; N] {
+    buf[..N]
+        .try_into()
+        .expect("length checked by Message::parse")
+}
+
+fn be_u16(buf: &[u8]) -> u16 {
+    u16::from_be_bytes(*fixed(buf))
+}
+
+fn be_u32(buf: &[u8]) -> u32 {
+    u32::from_be_bytes(*fixed(buf))
+}
+
+fn be_u48(buf: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    bytes[2..].copy_from_slice(&buf[..6]);
+    u64::from_be_bytes(bytes)
+}
+
+fn be_u64(buf: &[u8]) -> u64 {
+    u64::from_be_bytes(*fixed(buf))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ItchError, Message, Parser, Replay};
+    use crate::{OrderBookError, Side};
+
+    const LOCATE: u16 = 7;
+
+    /// Writes a length prefixed message with the common header
+    fn frame(file: &mut Vec<u8>, kind: u8, ts: u64, body: &[u8]) {
+        let len = u16::try_from(super::HEADER_LEN + body.len()).unwrap();
+        file.extend_from_slice(&len.to_be_bytes());
+        file.push(kind);
+        file.extend_from_slice(&LOCATE.to_be_bytes());
+        file.extend_from_slice(&0u16.to_be_bytes());
+        file.extend_from_slice(&ts.to_be_bytes()[2..]);
+        file.extend_from_slice(body);
+    }
+
+    fn add(file: &mut Vec<u8>, ts: u64, id: u64, side: u8, shares: u32, price: u32) {
+        let mut body = id.to_be_bytes().to_vec();
+        body.push(side);
+        body.extend_from_slice(&shares.to_be_bytes());
+        body.extend_from_slice(b"AAPL    ");
+        body.extend_from_slice(&price.to_be_bytes());
+        frame(file, b'A', ts, &body);
+    }
+
+    fn executed(file: &mut Vec<u8>, ts: u64, id: u64, shares: u32) {
+        let mut body = id.to_be_bytes().to_vec();
+        body.extend_from_slice(&shares.to_be_bytes());
+        body.extend_from_slice(&1u64.to_be_bytes());
+        frame(file, b'E', ts, &body);
+    }
+
+    fn executed_with_price(file: &mut Vec<u8>, ts: u64, id: u64, shares: u32, price: u32) {
+        let mut body = id.to_be_bytes().to_vec();
+        body.extend_from_slice(&shares.to_be_bytes());
+        body.extend_from_slice(&2u64.to_be_bytes());
+        body.push(b'Y');
+        body.extend_from_slice(&price.to_be_bytes());
+        frame(file, b'C', ts, &body);
+    }
+
+    fn cancel(file: &mut Vec<u8>, ts: u64, id: u64, shares: u32) {
+        let mut body = id.to_be_bytes().to_vec();
+        body.extend_from_slice(&shares.to_be_bytes());
+        frame(file, b'X', ts, &body);
+    }
+
+    fn delete(file: &mut Vec<u8>, ts: u64, id: u64) {
+        frame(file, b'D', ts, &id.to_be_bytes());
+    }
+
+    fn replace(file: &mut Vec<u8>, ts: u64, id: u64, new_id: u64, shares: u32, price: u32) {
+        let mut body = id.to_be_bytes().to_vec();
+        body.extend_from_slice(&new_id.to_be_bytes());
+        body.extend_from_slice(&shares.to_be_bytes());
+        body.extend_from_slice(&price.to_be_bytes());
+        frame(file, b'U', ts, &body);
+    }
+
+    fn replay(file: &[u8]) -> Result<Replay, ItchError> {
+        let mut replay = Replay::new();
+        for msg in Parser::new(file) {
+            replay.apply(&msg?)?;
+        }
+        Ok(replay)
+    }
+
+    #[test]
+    fn parse_add_order() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 34_200_000_000_000, 1, b'B', 100, 1_500_000);
+
+        // Act
+        let msgs: Vec<_> = Parser::new(&file).collect();
+
+        // Assert
+        assert_eq!(msgs.len(), 1);
+        let Ok(Message::AddOrder(add)) = msgs[0] else {
+            panic!("expected AddOrder, got {:?}", msgs[0]);
+        };
+        assert_eq!(add.header.stock_locate, LOCATE);
+        assert_eq!(add.header.timestamp, 34_200_000_000_000);
+        assert_eq!(add.order_ref, 1);
+        assert_eq!(add.side, Side::Bid);
+        assert_eq!(add.shares, 100);
+        assert_eq!(add.stock, b"AAPL    ");
+        assert_eq!(add.price, 1_500_000);
+        assert_eq!(add.attribution, None);
+    }
+
+    #[test]
+    fn parse_truncated() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 1, 1, b'B', 100, 1_500_000);
+        let len = file.len();
+        add(&mut file, 2, 2, b'B', 100, 1_500_000);
+        file.truncate(file.len() - 3);
+
+        // Act
+        let msgs: Vec<_> = Parser::new(&file).collect();
+
+        // Assert
+        assert_eq!(msgs.len(), 2);
+        assert!(msgs[0].is_ok());
+        assert_eq!(msgs[1], Err(ItchError::Truncated(len)));
+    }
+
+    #[test]
+    fn parse_other() {
+        // Setup
+        let mut file = vec![];
+        frame(&mut file, b'S', 1, b"O");
+
+        // Act
+        let msg = Parser::new(&file).next().unwrap();
+
+        // Assert
+        assert!(matches!(msg, Ok(Message::Other(buf)) if buf[0] == b'S'));
+    }
+
+    #[test]
+    fn replay_add_execute_cancel() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 1, 1, b'B', 100, 1_500_000);
+        add(&mut file, 2, 2, b'B', 300, 1_500_000);
+        add(&mut file, 3, 3, b'S', 200, 1_510_000);
+        executed(&mut file, 4, 1, 40);
+        cancel(&mut file, 5, 2, 100);
+
+        // Act
+        let replay = replay(&file).unwrap();
+
+        // Assert
+        assert_eq!(replay.timestamp(), 5);
+        let stock = replay.stock(LOCATE).unwrap();
+        assert_eq!(stock.symbol(), "AAPL");
+        let book = stock.book();
+        assert_eq!(book.get_best_price(Side::Bid), Some(&1_500_000));
+        assert_eq!(book.get_total_qty(1_500_000, Side::Bid), Some(260));
+        assert_eq!(book.get_best_price(Side::Ask), Some(&1_510_000));
+        assert_eq!(book.get_order(1).unwrap().qty, 60);
+    }
+
+    #[test]
+    fn replay_executed_with_price() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 1, 1, b'S', 100, 1_510_000);
+        executed_with_price(&mut file, 2, 1, 30, 1_509_900);
+
+        // Act
+        let msg = Parser::new(&file).nth(1).unwrap();
+        let replay = replay(&file).unwrap();
+
+        // Assert
+        let Ok(Message::OrderExecutedWithPrice(exec)) = msg else {
+            panic!("expected OrderExecutedWithPrice, got {msg:?}");
+        };
+        assert_eq!(exec.order_ref, 1);
+        assert_eq!(exec.executed_shares, 30);
+        assert_eq!(exec.match_number, 2);
+        assert!(exec.printable);
+        assert_eq!(exec.execution_price, 1_509_900);
+        let book = replay.stock(LOCATE).unwrap().book();
+        assert_eq!(book.get_order(1).unwrap().qty, 70);
+        assert_eq!(book.get_total_qty(1_510_000, Side::Ask), Some(70));
+    }
+
+    #[test]
+    fn replay_delete_replace() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 1, 1, b'S', 100, 1_510_000);
+        add(&mut file, 2, 2, b'S', 100, 1_520_000);
+        delete(&mut file, 3, 1);
+        replace(&mut file, 4, 2, 3, 50, 1_505_000);
+
+        // Act
+        let replay = replay(&file).unwrap();
+
+        // Assert
+        let book = replay.stock(LOCATE).unwrap().book();
+        assert!(book.get_order(1).is_none());
+        assert!(book.get_order(2).is_none());
+        let order = book.get_order(3).unwrap();
+        assert_eq!(order.side, Side::Ask);
+        assert_eq!(order.qty, 50);
+        assert_eq!(book.get_best_price(Side::Ask), Some(&1_505_000));
+        assert_eq!(book.get_total_qty(1_520_000, Side::Ask), None);
+    }
+
+    #[test]
+    fn replay_unknown_order() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 1, 1, b'S', 100, 1_510_000);
+        delete(&mut file, 2, 2);
+
+        // Act
+        let res = replay(&file);
+
+        // Assert
+        assert_eq!(
+            res.err(),
+            Some(ItchError::OrderBook(OrderBookError::UnknownId(2)))
+        );
+    }
+
+    #[test]
+    fn replay_file() {
+        // Setup
+        let mut file = vec![];
+        add(&mut file, 1, 1, b'B', 100, 1_500_000);
+        executed(&mut file, 2, 1, 100);
+        let path = std::env::temp_dir().join(format!("itch-{}.bin", std::process::id()));
+        std::fs::write(&path, &file).unwrap();
+
+        // Act
+        let bytes = std::fs::read(&path).unwrap();
+        let res = replay(&bytes);
+        std::fs::remove_file(&path).unwrap();
+
+        // Assert
+        let book = res.unwrap();
+        let book = book.stock(LOCATE).unwrap().book();
+        assert_eq!(book.get_best_price(Side::Bid), None);
+    }
+}
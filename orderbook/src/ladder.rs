@@ -0,0 +1,242 @@
+use crate::allocation::Allocation;
+use crate::{Order, Price, PriceLevel, Side};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Dense price ladder of one side, a `PriceLevel` per tick between the minimum and maximum price
+///
+/// A bitmap marks the non-empty levels, the best price is kept up to date on every change so
+/// reading it is O(1), finding the next best price after a level empties scans the bitmap a word
+/// at a time.
+#[derive(Clone, Debug)]
+pub(super) struct Ladder {
+    side: Side,
+    min_price: Price,
+    tick: Price,
+    levels: Vec<PriceLevel>,
+    bitmap: Vec<u64>,
+    best: Option<Price>,
+}
+
+impl Ladder {
+    /// Constructor function, every level holds `orders_per_level` orders without reallocating
+    ///
+    /// # Panics
+    ///
+    /// Panics if `tick` is 0, `max_price` is below `min_price` or the number of ticks does not fit
+    /// in a `usize`, and aborts if the levels of a wide range can not be allocated
+    pub(super) fn new(
+        side: Side,
+        (min_price, max_price, tick): (Price, Price, Price),
+        allocation: Allocation,
+        orders_per_level: usize,
+    ) -> Self {
+        assert!(tick > 0, "ladder tick must be positive");
+        assert!(min_price <= max_price, "ladder max_price below min_price");
+        let len = ((max_price - min_price) / tick)
+            .checked_add(1)
+            .and_then(|len| usize::try_from(len).ok())
+            .expect("ladder too large");
+        Self {
+            side,
+            min_price,
+            tick,
+            levels: (0..len)
+                .map(|_| PriceLevel::with_capacity(allocation, orders_per_level))
+                .collect(),
+            bitmap: vec![0; len.div_ceil(64)],
+            best: None,
+        }
+    }
+
+    /// Function gets the index of a `Price`
+    ///
+    /// Returns [`None`] if the price is outside of the ladder or not on a tick
+    fn index(&self, price: Price) -> Option<usize> {
+        let offset = price.checked_sub(self.min_price)?;
+        if offset % self.tick != 0 {
+            return None;
+        }
+        usize::try_from(offset / self.tick)
+            .ok()
+            .filter(|&idx| idx < self.levels.len())
+    }
+
+    fn price(&self, idx: usize) -> Price {
+        self.min_price + idx as Price * self.tick
+    }
+
+    fn is_set(&self, idx: usize) -> bool {
+        self.bitmap[idx / 64] & (1 << (idx % 64)) != 0
+    }
+
+    /// Function gets the first non-empty index at or after `from`
+    fn next_set(&self, from: usize) -> Option<usize> {
+        let mut word = from / 64;
+        let mut bits = *self.bitmap.get(word)? & (u64::MAX << (from % 64));
+        loop {
+            if bits != 0 {
+                return Some(word * 64 + bits.trailing_zeros() as usize);
+            }
+            word += 1;
+            bits = *self.bitmap.get(word)?;
+        }
+    }
+
+    /// Function gets the last non-empty index at or before `from`
+    fn prev_set(&self, from: usize) -> Option<usize> {
+        let mut word = from / 64;
+        let mut bits = self.bitmap[word] & (u64::MAX >> (63 - from % 64));
+        loop {
+            if bits != 0 {
+                return Some(word * 64 + 63 - bits.leading_zeros() as usize);
+            }
+            word = word.checked_sub(1)?;
+            bits = self.bitmap[word];
+        }
+    }
+
+    /// Function gets the next index in priority order after `idx`
+    fn next_worse(&self, idx: usize) -> Option<usize> {
+        match self.side {
+            Side::Ask => self.next_set(idx + 1),
+            Side::Bid => self.prev_set(idx.checked_sub(1)?),
+        }
+    }
+
+    /// Function checks if the `Price` is on the ladder
+    pub(super) fn accepts(&self, price: Price) -> bool {
+        self.index(price).is_some()
+    }
+
+    /// Function gets the non-empty `PriceLevel` of the given `Price`
+    pub(super) fn get(&self, price: Price) -> Option<&PriceLevel> {
+        let idx = self.index(price)?;
+        self.is_set(idx).then(|| &self.levels[idx])
+    }
+
+    /// Function gets the non-empty `PriceLevel` of the given `Price` mutably
+    pub(super) fn get_mut(&mut self, price: Price) -> Option<&mut PriceLevel> {
+        let idx = self.index(price)?;
+        if self.is_set(idx) {
+            Some(&mut self.levels[idx])
+        } else {
+            None
+        }
+    }
+
+    /// Function inserts an order into the level of its price
+    ///
+    /// # Panics
+    ///
+    /// Panics if the price of the order is not on the ladder
+    pub(super) fn insert(&mut self, order: &Order) {
+        let idx = self.index(order.price).expect("price on the ladder");
+        self.levels[idx].insert(order);
+        self.bitmap[idx / 64] |= 1 << (idx % 64);
+        let better = self.best.is_none_or(|best| match self.side {
+            Side::Ask => order.price < best,
+            Side::Bid => order.price > best,
+        });
+        if better {
+            self.best = Some(order.price);
+        }
+    }
+
+    /// Function empties the level of the given `Price`, keeping its capacity
+    pub(super) fn remove_level(&mut self, price: Price) {
+        let Some(idx) = self.index(price) else {
+            return;
+        };
+        self.levels[idx].clear();
+        self.bitmap[idx / 64] &= !(1 << (idx % 64));
+        if self.best == Some(price) {
+            self.best = self.next_worse(idx).map(|idx| self.price(idx));
+        }
+    }
+
+    pub(super) fn best(&self) -> Option<&Price> {
+        self.best.as_ref()
+    }
+
+    /// Function gets the prices of the non-empty levels, best price first
+    pub(super) fn prices(&self) -> Prices<'_> {
+        Prices {
+            ladder: self,
+            next: self.best.and_then(|best| self.index(best)),
+        }
+    }
+}
+
+/// Iterator over the non-empty prices of a [`Ladder`], best price first
+pub(super) struct Prices<'a> {
+    ladder: &'a Ladder,
+    next: Option<usize>,
+}
+
+impl Iterator for Prices<'_> {
+    type Item = Price;
+
+    fn next(&mut self) -> Option<Price> {
+        let idx = self.next?;
+        self.next = self.ladder.next_worse(idx);
+        Some(self.ladder.price(idx))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Ladder;
+    use crate::allocation::Allocation;
+    use crate::{Order, Side};
+
+    fn ladder(side: Side) -> Ladder {
+        Ladder::new(side, (100, 1100, 5), Allocation::Fifo, 0)
+    }
+
+    #[test]
+    fn accepts_ticks_in_range() {
+        // Setup
+        let ladder = ladder(Side::Ask);
+
+        // Act & Assert
+        assert!(ladder.accepts(100));
+        assert!(ladder.accepts(1100));
+        assert!(ladder.accepts(505));
+        assert!(!ladder.accepts(95));
+        assert!(!ladder.accepts(1105));
+        assert!(!ladder.accepts(502));
+    }
+
+    #[test]
+    #[should_panic(expected = "ladder too large")]
+    fn full_price_range_too_large() {
+        let _ = Ladder::new(Side::Ask, (0, u64::MAX, 1), Allocation::Fifo, 0);
+    }
+
+    #[test]
+    fn best_price_across_words() {
+        // Setup
+        let mut asks = ladder(Side::Ask);
+        let mut bids = ladder(Side::Bid);
+        for (id, price) in (1..).zip([1100, 100, 505, 700]) {
+            asks.insert(&Order::new(price, 1, Side::Ask, id));
+            bids.insert(&Order::new(price, 1, Side::Bid, id));
+        }
+
+        // Act & Assert
+        assert_eq!(asks.prices().collect::<Vec<_>>(), vec![100, 505, 700, 1100]);
+        assert_eq!(bids.prices().collect::<Vec<_>>(), vec![1100, 700, 505, 100]);
+        asks.remove_level(100);
+        bids.remove_level(1100);
+        assert_eq!(asks.best(), Some(&505));
+        assert_eq!(bids.best(), Some(&700));
+        asks.remove_level(505);
+        asks.remove_level(700);
+        asks.remove_level(1100);
+        assert_eq!(asks.best(), None);
+        assert_eq!(asks.prices().next(), None);
+        assert!(asks.get(700).is_none());
+        assert_eq!(bids.get(505).map(|level| level.get_total_qty()), Some(1));
+    }
+}
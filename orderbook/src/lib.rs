@@ -1,6 +1,7 @@
 //! orderbook
 //!
-//! A limit order book that sorts order First In First Out (FIFO)
+//! A limit order book that sorts order First In First Out (FIFO), with optional pro-rata and
+//! hybrid allocation
 //!
 //! Example:
 //! ```
@@ -20,17 +21,45 @@
 //! }
 //!
 //! ```
+//!
+//! The `std` feature is enabled by default, without it the crate is `no_std` and only depends on
+//! `alloc`. Time priority within a price level follows insertion order, timestamps are supplied
+//! by the caller where needed so no clock is read. The [`concurrent`] book is only available on
+//! targets with 64-bit atomics.
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
+
+pub mod allocation;
+pub mod analytics;
 mod book_side;
+#[cfg(target_has_atomic = "64")]
+pub mod concurrent;
 mod error;
+pub mod feed;
+pub mod itch;
+mod ladder;
+#[cfg(test)]
+mod model;
 mod order_book;
 mod price_level;
+pub mod quote;
+#[cfg(feature = "tokio")]
+pub mod service;
+pub mod tape;
 
 use book_side::BookSide;
-pub use book_side::Side;
+pub use book_side::{Backend, Side};
 pub use error::OrderBookError;
-pub use order_book::{Order, OrderBook};
+pub use order_book::{BookConfig, Capacity, Order, OrderBook, Preview, Trade};
 use price_level::PriceLevel;
 
+// Ids are chosen by clients, so with `std` the maps keep the randomly keyed SipHash of the
+// standard library and only fall back to hashbrown's default hasher without it
+#[cfg(not(feature = "std"))]
+use hashbrown::{hash_map, HashMap};
+#[cfg(feature = "std")]
+use std::collections::{hash_map, HashMap};
+
 type OrderId = u64;
 type Price = u64;
 type Qty = u64;
@@ -0,0 +1,289 @@
+//! Reference model of the [`OrderBook`], random sequences of operations are applied to both and
+//! their outcomes compared, with the invariants of the book asserted after every step
+use crate::{Backend, Order, OrderBook, OrderBookError, OrderId, Price, Qty, Side, Trade};
+use proptest::prelude::*;
+use std::cmp::Ordering;
+
+/// Resting orders in arrival order, the FIFO priority within a price
+#[derive(Default)]
+struct Model {
+    orders: Vec<Order>,
+}
+
+impl Model {
+    fn position(&self, id: OrderId) -> Option<usize> {
+        self.orders.iter().position(|order| order.id == id)
+    }
+
+    fn insert(&mut self, order: Order) -> Result<(), OrderBookError> {
+        if order.qty == 0 {
+            return Err(OrderBookError::InvalidQty(order.id));
+        }
+        if self.position(order.id).is_some() {
+            return Err(OrderBookError::DuplicateOrderId(order.id));
+        }
+        self.orders.push(order);
+        Ok(())
+    }
+
+    fn remove(&mut self, id: OrderId) -> Result<(), OrderBookError> {
+        let idx = self.position(id).ok_or(OrderBookError::UnknownId(id))?;
+        self.orders.remove(idx);
+        Ok(())
+    }
+
+    fn reduce(&mut self, id: OrderId, qty: Qty) -> Result<(), OrderBookError> {
+        let idx = self.position(id).ok_or(OrderBookError::UnknownId(id))?;
+        if qty < self.orders[idx].qty {
+            self.orders[idx].qty -= qty;
+        } else {
+            self.orders.remove(idx);
+        }
+        Ok(())
+    }
+
+    fn best_price(&self, side: Side) -> Option<Price> {
+        let prices = self
+            .orders
+            .iter()
+            .filter(|order| order.side == side)
+            .map(|order| order.price);
+        match side {
+            Side::Ask => prices.min(),
+            Side::Bid => prices.max(),
+        }
+    }
+
+    fn level(&self, price: Price, side: Side) -> Vec<Order> {
+        self.orders
+            .iter()
+            .filter(|order| (order.price, order.side) == (price, side))
+            .copied()
+            .collect()
+    }
+
+    fn depth(&self, side: Side) -> Vec<(Price, Qty)> {
+        let mut depth: Vec<(Price, Qty)> = vec![];
+        for order in self.orders.iter().filter(|order| order.side == side) {
+            match depth.iter_mut().find(|(price, _)| *price == order.price) {
+                Some((_, qty)) => *qty += order.qty,
+                None => depth.push((order.price, order.qty)),
+            }
+        }
+        depth.sort_unstable_by_key(|&(price, _)| price);
+        if side == Side::Bid {
+            depth.reverse();
+        }
+        depth
+    }
+
+    /// Drains FIFO, the order reaching `qty` is split and keeps its place
+    fn drain(&mut self, price: Price, side: Side, qty: Qty) -> Option<(Vec<Order>, Qty)> {
+        if self.level(price, side).is_empty() {
+            return None;
+        }
+        if qty == 0 {
+            return Some((vec![], 0));
+        }
+        let mut drained = vec![];
+        let mut collected = 0;
+        let mut idx = 0;
+        while idx < self.orders.len() {
+            let order = self.orders[idx];
+            if (order.price, order.side) != (price, side) {
+                idx += 1;
+                continue;
+            }
+            match (collected + order.qty).cmp(&qty) {
+                Ordering::Less => {
+                    collected += order.qty;
+                    drained.push(order);
+                    self.orders.remove(idx);
+                }
+                Ordering::Equal => {
+                    collected += order.qty;
+                    drained.push(order);
+                    self.orders.remove(idx);
+                    break;
+                }
+                Ordering::Greater => {
+                    let split = qty - collected;
+                    collected = qty;
+                    drained.push(Order {
+                        qty: split,
+                        ..order
+                    });
+                    self.orders[idx].qty -= split;
+                    break;
+                }
+            }
+        }
+        Some((drained, collected))
+    }
+
+    fn match_order(&mut self, order: Order) -> Result<Vec<Trade>, OrderBookError> {
+        if self.position(order.id).is_some() {
+            return Err(OrderBookError::DuplicateOrderId(order.id));
+        }
+        if order.qty == 0 {
+            return Err(OrderBookError::InvalidQty(order.id));
+        }
+        let mut trades = vec![];
+        let mut remaining = order.qty;
+        while remaining > 0 {
+            let Some(price) = self.best_price(!order.side) else {
+                break;
+            };
+            let crosses = match order.side {
+                Side::Ask => price >= order.price,
+                Side::Bid => price <= order.price,
+            };
+            if !crosses {
+                break;
+            }
+            let (makers, qty) = self.drain(price, !order.side, remaining).unwrap();
+            remaining -= qty;
+            trades.extend(makers.iter().map(|maker| Trade {
+                maker_id: maker.id,
+                taker_id: order.id,
+                price,
+                qty: maker.qty,
+                side: order.side,
+            }));
+        }
+        if remaining > 0 {
+            self.insert(Order {
+                qty: remaining,
+                ..order
+            })?;
+        }
+        Ok(trades)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(Order),
+    Match(Order),
+    Remove(OrderId),
+    Reduce(OrderId, Qty),
+    Drain(Price, Side, Qty),
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Done(Result<(), OrderBookError>),
+    Trades(Result<Vec<Trade>, OrderBookError>),
+    Drained(Option<(Vec<Order>, Qty)>),
+}
+
+const IDS: OrderId = 40;
+const MIN_PRICE: Price = 95;
+const MAX_PRICE: Price = 105;
+
+fn side() -> impl Strategy<Value = Side> + Clone {
+    prop_oneof![Just(Side::Ask), Just(Side::Bid)]
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let order = (MIN_PRICE..=MAX_PRICE, 0..20u64, side(), 1..IDS)
+        .prop_map(|(price, qty, side, id)| Order::new(price, qty, side, id));
+    prop_oneof![
+        3 => order.clone().prop_map(Op::Insert),
+        3 => order.prop_map(Op::Match),
+        2 => (1..IDS).prop_map(Op::Remove),
+        1 => (1..IDS, 0..20u64).prop_map(|(id, qty)| Op::Reduce(id, qty)),
+        2 => (MIN_PRICE..=MAX_PRICE, side(), 0..40u64)
+            .prop_map(|(price, side, qty)| Op::Drain(price, side, qty)),
+    ]
+}
+
+fn backend() -> impl Strategy<Value = Backend> {
+    prop_oneof![
+        Just(Backend::HashMap),
+        Just(Backend::Ladder {
+            min_price: MIN_PRICE,
+            max_price: MAX_PRICE,
+            tick: 1,
+        }),
+    ]
+}
+
+/// Applies an operation to the book, inserting only orders that do not cross like the
+/// matching would
+fn apply(book: &mut OrderBook, op: &Op) -> Outcome {
+    match *op {
+        Op::Insert(order) => {
+            let crosses = book
+                .get_best_price(!order.side)
+                .is_some_and(|&best| match order.side {
+                    Side::Ask => best >= order.price,
+                    Side::Bid => best <= order.price,
+                });
+            if crosses {
+                Outcome::Trades(book.match_order(order))
+            } else {
+                Outcome::Done(book.insert(order))
+            }
+        }
+        Op::Match(order) => Outcome::Trades(book.match_order(order)),
+        Op::Remove(id) => Outcome::Done(book.remove(id)),
+        Op::Reduce(id, qty) => Outcome::Done(book.reduce(id, qty)),
+        Op::Drain(price, side, qty) => Outcome::Drained(book.get_orders_till_qty(price, side, qty)),
+    }
+}
+
+fn apply_model(model: &mut Model, op: &Op) -> Outcome {
+    match *op {
+        Op::Insert(order) => {
+            let crosses = model
+                .best_price(!order.side)
+                .is_some_and(|best| match order.side {
+                    Side::Ask => best >= order.price,
+                    Side::Bid => best <= order.price,
+                });
+            if crosses {
+                Outcome::Trades(model.match_order(order))
+            } else {
+                Outcome::Done(model.insert(order))
+            }
+        }
+        Op::Match(order) => Outcome::Trades(model.match_order(order)),
+        Op::Remove(id) => Outcome::Done(model.remove(id)),
+        Op::Reduce(id, qty) => Outcome::Done(model.reduce(id, qty)),
+        Op::Drain(price, side, qty) => Outcome::Drained(model.drain(price, side, qty)),
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn book_follows_model(backend in backend(), ops in prop::collection::vec(op(), 1..100)) {
+        let mut book = OrderBook::with_backend(backend);
+        let mut model = Model::default();
+        for op in &ops {
+            let outcome = apply(&mut book, op);
+            if let Outcome::Drained(Some((orders, _))) = &outcome {
+                prop_assert!(orders.iter().all(|order| order.qty > 0), "{:?}", op);
+            }
+            prop_assert_eq!(outcome, apply_model(&mut model, op), "{:?}", op);
+            book.assert_invariants();
+            for side in [Side::Ask, Side::Bid] {
+                prop_assert_eq!(book.get_depth(side), model.depth(side));
+                prop_assert_eq!(book.get_best_price(side).copied(), model.best_price(side));
+                for price in MIN_PRICE..=MAX_PRICE {
+                    let level = model.level(price, side);
+                    prop_assert_eq!(
+                        book.get_orders(price, side),
+                        (!level.is_empty()).then_some(level)
+                    );
+                }
+            }
+            for id in 1..IDS {
+                let order = model.position(id).map(|idx| model.orders[idx]);
+                prop_assert_eq!(book.get_order(id).copied(), order);
+            }
+        }
+    }
+}
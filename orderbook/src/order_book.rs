@@ -1,7 +1,11 @@
-use crate::{BookSide, OrderBookError, OrderId, Price, Qty, Side};
+use crate::allocation::Allocation;
+use crate::hash_map::Entry;
+use crate::quote::{self, OwnerId, QuoteError, QuoteLevel, QuoteSet};
+use crate::HashMap;
+use crate::{Backend, BookSide, OrderBookError, OrderId, Price, Qty, Side};
+use alloc::vec;
+use alloc::vec::Vec;
 use serde::{Deserialize, Serialize};
-use std::collections::hash_map::Entry;
-use std::collections::HashMap;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Order {
@@ -28,20 +32,138 @@ impl Order {
     }
 }
 
+/// An execution between a resting order and an incoming order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Trade {
+    /// OrderId of the resting order
+    pub maker_id: OrderId,
+    /// OrderId of the incoming order
+    pub taker_id: OrderId,
+    /// Price of the resting order
+    pub price: Price,
+    /// Executed quantity
+    pub qty: Qty,
+    /// Side of the incoming order
+    pub side: Side,
+}
+
+/// Would-be outcome of matching an incoming order, see [`OrderBook::preview`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Preview {
+    /// Resting orders with the `Qty` that would execute, in execution order
+    pub fills: Vec<Order>,
+    /// Total `Qty` that would execute
+    pub filled_qty: Qty,
+    /// `Qty` that would rest in the book, or be left over without a limit price
+    pub remaining_qty: Qty,
+    /// Volume weighted average price of the fills, [`None`] without fills
+    pub vwap: Option<f64>,
+    /// Price of the last fill, [`None`] without fills
+    pub worst_price: Option<Price>,
+}
+
+/// Capacity hints of an [`OrderBook`], see [`OrderBook::with_capacity`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capacity {
+    /// Resting orders in the whole book
+    pub orders: usize,
+    /// Price levels per side
+    pub levels: usize,
+    /// Resting orders per price level
+    pub orders_per_level: usize,
+}
+
+/// Construction options of an [`OrderBook`], see [`OrderBook::with_config`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookConfig {
+    pub allocation: Allocation,
+    pub capacity: Capacity,
+    pub backend: Backend,
+}
+
+#[derive(Clone, Debug)]
 pub struct OrderBook {
     asks: BookSide,
     bids: BookSide,
     orders: HashMap<OrderId, Order>,
+    quotes: HashMap<OwnerId, QuoteSet>,
+    /// Owner of every order of a current quote
+    quote_orders: HashMap<OrderId, OwnerId>,
+    /// Resting orders drained by the current match, reused across matches
+    makers: Vec<Order>,
 }
 
 impl OrderBook {
     /// Constructor function
     #[must_use]
     pub fn new() -> OrderBook {
+        Self::with_allocation(Allocation::Fifo)
+    }
+
+    /// Constructor function with the [`Allocation`] splitting incoming orders over the resting
+    /// orders of a price level
+    #[must_use]
+    pub fn with_allocation(allocation: Allocation) -> OrderBook {
+        Self::with_config(BookConfig {
+            allocation,
+            ..BookConfig::default()
+        })
+    }
+
+    /// Constructor function pre-allocating orders and price levels
+    ///
+    /// As long as the book stays within the [`Capacity`], inserting, removing and FIFO matching
+    /// with [`OrderBook::match_order_into`] do not allocate
+    #[must_use]
+    pub fn with_capacity(capacity: Capacity) -> OrderBook {
+        Self::with_config(BookConfig {
+            capacity,
+            ..BookConfig::default()
+        })
+    }
+
+    /// Constructor function combining [`OrderBook::with_allocation`] and
+    /// [`OrderBook::with_capacity`]
+    #[must_use]
+    pub fn with_allocation_and_capacity(allocation: Allocation, capacity: Capacity) -> OrderBook {
+        Self::with_config(BookConfig {
+            allocation,
+            capacity,
+            ..BookConfig::default()
+        })
+    }
+
+    /// Constructor function with the [`Backend`] storing the price levels
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tick of a [`Backend::Ladder`] is 0, its maximum price is below its minimum
+    /// price or its number of ticks does not fit in a `usize`. A ladder allocates a price level
+    /// per tick on both sides, so a wide range of small ticks may abort on allocation failure
+    #[must_use]
+    pub fn with_backend(backend: Backend) -> OrderBook {
+        Self::with_config(BookConfig {
+            backend,
+            ..BookConfig::default()
+        })
+    }
+
+    /// Constructor function combining all [`BookConfig`] options
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tick of a [`Backend::Ladder`] is 0, its maximum price is below its minimum
+    /// price or its number of ticks does not fit in a `usize`. A ladder allocates a price level
+    /// per tick on both sides, so a wide range of small ticks may abort on allocation failure
+    #[must_use]
+    pub fn with_config(config: BookConfig) -> OrderBook {
         Self {
-            asks: BookSide::new(Side::Ask),
-            bids: BookSide::new(Side::Bid),
-            orders: HashMap::new(),
+            asks: BookSide::new(Side::Ask, config),
+            bids: BookSide::new(Side::Bid, config),
+            orders: HashMap::with_capacity(config.capacity.orders),
+            quotes: HashMap::new(),
+            quote_orders: HashMap::new(),
+            makers: Vec::with_capacity(config.capacity.orders_per_level),
         }
     }
 
@@ -49,9 +171,12 @@ impl OrderBook {
     ///
     /// # Errors
     ///
-    /// Returns [`Err`] if the given `id` is already in the orderbook
+    /// Returns [`Err`] if the given `id` is already in the orderbook, the `Qty` is zero, or the
+    /// `Price` is not on the ladder of a [`Backend::Ladder`]
     pub fn insert(&mut self, order: Order) -> Result<(), OrderBookError> {
         let id = order.id;
+        Self::check_qty(&order)?;
+        self.check_price(&order)?;
         match self.orders.entry(id) {
             Entry::Vacant(entry) => {
                 match order.side {
@@ -76,12 +201,333 @@ impl OrderBook {
                     Side::Ask => self.asks.remove(id),
                     Side::Bid => self.bids.remove(id),
                 };
+                if !self.quote_orders.is_empty() {
+                    self.quote_orders.remove(&id);
+                }
                 Ok(())
             }
             None => Err(OrderBookError::UnknownId(id)),
         }
     }
 
+    /// Function reduces the `Qty` of the [`Order`] with the given `OrderId`
+    ///
+    /// The order keeps its time priority, it is removed once its `Qty` reaches zero
+    ///
+    /// # Errors
+    /// Returns [`Err`] if the order with the given `OrderId` is not present
+    pub fn reduce(&mut self, id: OrderId, qty: Qty) -> Result<(), OrderBookError> {
+        match self.orders.get_mut(&id) {
+            Some(order) if qty < order.qty => {
+                order.qty -= qty;
+                match order.side {
+                    Side::Ask => self.asks.reduce(id, qty),
+                    Side::Bid => self.bids.reduce(id, qty),
+                };
+                Ok(())
+            }
+            Some(_) => self.remove(id),
+            None => Err(OrderBookError::UnknownId(id)),
+        }
+    }
+
+    /// Function matches an incoming [`Order`] against the opposite `Side` of the [`OrderBook`]
+    ///
+    /// Resting orders are executed best price first and by the [`Allocation`] within a price, FIFO
+    /// by default, any remaining `Qty` of the incoming order is inserted into the [`OrderBook`]
+    ///
+    /// Returns the [`Trade`]'s in execution order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the given `id` is already in the orderbook, the `Qty` is zero, or the
+    /// `Price` is not on the ladder of a [`Backend::Ladder`]
+    pub fn match_order(&mut self, order: Order) -> Result<Vec<Trade>, OrderBookError> {
+        let mut trades = Vec::new();
+        self.match_order_into(order, &mut trades)?;
+        Ok(trades)
+    }
+
+    /// Function matches an incoming [`Order`] like [`OrderBook::match_order`], appending the
+    /// [`Trade`]'s to `trades`
+    ///
+    /// Reusing `trades` across calls keeps matching free of allocations
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the given `id` is already in the orderbook, the `Qty` is zero, or the
+    /// `Price` is not on the ladder of a [`Backend::Ladder`]
+    pub fn match_order_into(
+        &mut self,
+        order: Order,
+        trades: &mut Vec<Trade>,
+    ) -> Result<(), OrderBookError> {
+        if self.orders.contains_key(&order.id) {
+            return Err(OrderBookError::DuplicateOrderId(order.id));
+        }
+        Self::check_qty(&order)?;
+        self.check_price(&order)?;
+        let start = trades.len();
+        let mut makers = core::mem::take(&mut self.makers);
+        let mut remaining = order.qty;
+        while remaining > 0 {
+            let Some(&price) = self.get_best_price(!order.side) else {
+                break;
+            };
+            let crosses = match order.side {
+                Side::Ask => price >= order.price,
+                Side::Bid => price <= order.price,
+            };
+            if !crosses {
+                break;
+            }
+            makers.clear();
+            let Some(qty) = self.drain_into(price, !order.side, remaining, &mut makers) else {
+                break;
+            };
+            if qty == 0 {
+                break;
+            }
+            remaining -= qty;
+            trades.extend(makers.iter().map(|maker| Trade {
+                maker_id: maker.id,
+                taker_id: order.id,
+                price,
+                qty: maker.qty,
+                side: order.side,
+            }));
+        }
+        makers.clear();
+        self.makers = makers;
+        if remaining > 0 {
+            self.insert(Order {
+                qty: remaining,
+                ..order
+            })?;
+        }
+        if !self.quote_orders.is_empty() {
+            self.attribute_quote_fills(&trades[start..]);
+        }
+        Ok(())
+    }
+
+    /// Function replaces the quote of the owner with the given levels in a single call
+    ///
+    /// A level reusing the `OrderId` of a resting order of the previous quote at the same `Price`
+    /// and `Side`, without growing its `Qty`, is replaced in place and keeps its time priority.
+    /// All other orders of the previous quote are removed, then the remaining levels are matched
+    /// like an incoming order with [`OrderBook::match_order`]. Either the whole quote is applied
+    /// or, on error, nothing changes.
+    ///
+    /// Returns the [`Trade`]'s of levels crossing resting orders of other owners
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if a level reuses an `OrderId` that is not part of the previous quote of
+    /// the owner, or if the bids cross the asks of the quote
+    pub fn quote(
+        &mut self,
+        owner: OwnerId,
+        quote_id: u64,
+        levels: Vec<QuoteLevel>,
+    ) -> Result<Vec<Trade>, QuoteError> {
+        // Everything that can fail is checked before the book changes, so the quote is applied
+        // either completely or not at all
+        quote::check_crossed(&levels)?;
+        for (idx, level) in levels.iter().enumerate() {
+            let reused = self.orders.contains_key(&level.id)
+                && self.quote_orders.get(&level.id) != Some(&owner);
+            if reused || levels[..idx].iter().any(|other| other.id == level.id) {
+                return Err(OrderBookError::DuplicateOrderId(level.id).into());
+            }
+            if level.qty > 0 {
+                self.check_price(&Order::new(level.price, level.qty, level.side, level.id))?;
+            }
+        }
+
+        let in_place: Vec<bool> = levels
+            .iter()
+            .map(|level| self.quoted_in_place(owner, level))
+            .collect();
+        if let Some(previous) = self.quotes.remove(&owner) {
+            for level in &previous.levels {
+                let kept = levels
+                    .iter()
+                    .zip(&in_place)
+                    .any(|(new, &kept)| kept && new.id == level.id);
+                if !kept && self.quote_orders.contains_key(&level.id) {
+                    let _ = self.remove(level.id);
+                }
+            }
+        }
+        let mut placed = Vec::new();
+        for (level, kept) in levels.iter().zip(in_place) {
+            if kept {
+                let resting = self.orders[&level.id].qty;
+                if level.qty < resting {
+                    // Kept orders are resting and not removed above
+                    self.reduce(level.id, resting - level.qty)
+                        .expect("validated above");
+                }
+            } else if level.qty > 0 {
+                self.quote_orders.insert(level.id, owner);
+                placed.push(Order::new(level.price, level.qty, level.side, level.id));
+            }
+        }
+        self.quotes.insert(
+            owner,
+            QuoteSet {
+                quote_id,
+                levels: levels.clone(),
+                fills: Vec::new(),
+            },
+        );
+        let mut trades = Vec::new();
+        for order in placed {
+            // Placed orders have a positive `Qty`, an accepted `Price` and an `OrderId` that is
+            // unique in the quote and no longer resting, they do not cross each other
+            trades.extend(self.match_order(order).expect("validated above"));
+        }
+        Ok(trades)
+    }
+
+    /// Function checks if the level replaces a resting order of the quote of the owner in place
+    fn quoted_in_place(&self, owner: OwnerId, level: &QuoteLevel) -> bool {
+        level.qty > 0
+            && self.quote_orders.get(&level.id) == Some(&owner)
+            && self.orders.get(&level.id).is_some_and(|order| {
+                (order.side, order.price) == (level.side, level.price) && level.qty <= order.qty
+            })
+    }
+
+    /// Function removes all resting orders of the quote of the owner
+    ///
+    /// Returns [`Some`] with the removed [`QuoteSet`] and its fills
+    ///
+    /// Returns [`None`] if the owner has no quote
+    pub fn cancel_quote(&mut self, owner: OwnerId) -> Option<QuoteSet> {
+        let quote = self.quotes.remove(&owner)?;
+        for level in &quote.levels {
+            if self.quote_orders.remove(&level.id).is_some() {
+                let _ = self.remove(level.id);
+            }
+        }
+        Some(quote)
+    }
+
+    /// Function removes the quotes of all owners
+    ///
+    /// Returns the removed [`QuoteSet`]'s ordered by owner
+    pub fn cancel_all_quotes(&mut self) -> Vec<(OwnerId, QuoteSet)> {
+        let mut owners: Vec<OwnerId> = self.quotes.keys().copied().collect();
+        owners.sort_unstable();
+        owners
+            .into_iter()
+            .filter_map(|owner| Some((owner, self.cancel_quote(owner)?)))
+            .collect()
+    }
+
+    /// Function gets the current quote of the owner
+    ///
+    /// Returns [`None`] if the owner has no quote
+    pub fn get_quote(&self, owner: OwnerId) -> Option<&QuoteSet> {
+        self.quotes.get(&owner)
+    }
+
+    pub(crate) fn check_qty(order: &Order) -> Result<(), OrderBookError> {
+        if order.qty == 0 {
+            Err(OrderBookError::InvalidQty(order.id))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub(crate) fn check_price(&self, order: &Order) -> Result<(), OrderBookError> {
+        let side = match order.side {
+            Side::Ask => &self.asks,
+            Side::Bid => &self.bids,
+        };
+        if side.accepts(order.price) {
+            Ok(())
+        } else {
+            Err(OrderBookError::InvalidPrice {
+                id: order.id,
+                price: order.price,
+            })
+        }
+    }
+
+    /// Credits the trades to the quotes of their orders, then forgets the quote orders that left
+    /// the [`OrderBook`] so a later order reusing the `OrderId` is not taken for a quote order
+    fn attribute_quote_fills(&mut self, trades: &[Trade]) {
+        for trade in trades {
+            for id in [trade.maker_id, trade.taker_id] {
+                if let Some(quote) = self
+                    .quote_orders
+                    .get(&id)
+                    .and_then(|owner| self.quotes.get_mut(owner))
+                {
+                    quote.fills.push(*trade);
+                }
+            }
+        }
+        for trade in trades {
+            for id in [trade.maker_id, trade.taker_id] {
+                if !self.orders.contains_key(&id) {
+                    self.quote_orders.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// Function previews matching an incoming order on the given `Side` for the given `Qty`
+    /// against the opposite `Side`, up to the `limit` price if given
+    ///
+    /// Walks the levels from the touch exactly like [`OrderBook::match_order`] but leaves the
+    /// [`OrderBook`] untouched
+    pub fn preview(&self, side: Side, qty: Qty, limit: Option<Price>) -> Preview {
+        let crosses = |price: Price| match (side, limit) {
+            (_, None) => true,
+            (Side::Ask, Some(limit)) => price >= limit,
+            (Side::Bid, Some(limit)) => price <= limit,
+        };
+        let (fills, filled_qty) = match side {
+            Side::Ask => self.bids.peek_orders_till_qty(qty, crosses),
+            Side::Bid => self.asks.peek_orders_till_qty(qty, crosses),
+        };
+        let notional: u128 = fills
+            .iter()
+            .map(|fill| u128::from(fill.price) * u128::from(fill.qty))
+            .sum();
+        #[allow(clippy::cast_precision_loss)]
+        let vwap = (filled_qty > 0).then(|| notional as f64 / filled_qty as f64);
+        Preview {
+            worst_price: fills.last().map(|fill| fill.price),
+            fills,
+            filled_qty,
+            remaining_qty: qty - filled_qty,
+            vwap,
+        }
+    }
+
+    /// Function asserts that both sides are consistent with the orders of the [`OrderBook`] and
+    /// that the book is not crossed
+    #[cfg(test)]
+    pub(crate) fn assert_invariants(&self) {
+        self.asks.assert_invariants(&self.orders);
+        self.bids.assert_invariants(&self.orders);
+        if let (Some(bid), Some(ask)) = (self.bids.get_best_price(), self.asks.get_best_price()) {
+            assert!(bid < ask, "crossed book, best bid {bid} >= best ask {ask}");
+        }
+    }
+
+    /// Function gets the resting [`Order`] with the given `OrderId`
+    ///
+    /// Returns [`None`] if the order is not present
+    pub fn get_order(&self, id: OrderId) -> Option<&Order> {
+        self.orders.get(&id)
+    }
+
     /// Function gets the best price for the given `Side`
     ///
     /// Returns [`Some`] `Price` on success
@@ -106,8 +552,38 @@ impl OrderBook {
         }
     }
 
+    /// Function gets the `Price` and total `Qty` of every level on the given `Side`, best price
+    /// first
+    pub fn get_depth(&self, side: Side) -> Vec<(Price, Qty)> {
+        match side {
+            Side::Ask => self.asks.get_depth(),
+            Side::Bid => self.bids.get_depth(),
+        }
+    }
+
+    /// Function iterates the `Price` and total `Qty` of the levels on the given `Side`, best price
+    /// first, without allocating
+    pub fn get_levels(&self, side: Side) -> impl Iterator<Item = (Price, Qty)> + '_ {
+        match side {
+            Side::Ask => self.asks.levels(),
+            Side::Bid => self.bids.levels(),
+        }
+    }
+
+    /// Function gets the orders on the given `Price` and `Side` combination in FIFO order
+    ///
+    /// Returns [`None`] if there are no orders on the given `Side` and `Price` combination
+    pub fn get_orders(&self, price: Price, side: Side) -> Option<Vec<Order>> {
+        match side {
+            Side::Ask => self.asks.get_orders(price),
+            Side::Bid => self.bids.get_orders(price),
+        }
+    }
+
     /// Function drains orders on the given `Price` and `Side` combination up to the given `Qty`
     ///
+    /// Partially drained orders stay in the [`OrderBook`] with their remaining `Qty`
+    ///
     /// Returns [`Some`] [`Vec`] of [`Order`] and total collected `Qty`
     ///
     /// Returns [`None`] if there are no orders on the given `Side` and `Price` combination
@@ -117,10 +593,60 @@ impl OrderBook {
         side: Side,
         qty: Qty,
     ) -> Option<(Vec<Order>, Qty)> {
-        match side {
-            Side::Ask => self.asks.get_orders_till_qty(price, qty),
-            Side::Bid => self.bids.get_orders_till_qty(price, qty),
+        let mut orders = vec![];
+        let total_qty = self.get_orders_till_qty_into(price, side, qty, &mut orders)?;
+        Some((orders, total_qty))
+    }
+
+    /// Function drains orders on the given `Price` and `Side` combination up to the given `Qty`
+    /// like [`OrderBook::get_orders_till_qty`], appending them to `orders`
+    ///
+    /// Returns [`Some`] total collected `Qty`
+    ///
+    /// Returns [`None`] if there are no orders on the given `Side` and `Price` combination
+    pub fn get_orders_till_qty_into(
+        &mut self,
+        price: Price,
+        side: Side,
+        qty: Qty,
+        orders: &mut Vec<Order>,
+    ) -> Option<Qty> {
+        let start = orders.len();
+        let total_qty = self.drain_into(price, side, qty, orders)?;
+        if !self.quote_orders.is_empty() {
+            for order in &orders[start..] {
+                if !self.orders.contains_key(&order.id) {
+                    self.quote_orders.remove(&order.id);
+                }
+            }
+        }
+        Some(total_qty)
+    }
+
+    /// Drains orders like [`OrderBook::get_orders_till_qty_into`], leaving the quote orders to
+    /// the caller
+    fn drain_into(
+        &mut self,
+        price: Price,
+        side: Side,
+        qty: Qty,
+        orders: &mut Vec<Order>,
+    ) -> Option<Qty> {
+        let start = orders.len();
+        let total_qty = match side {
+            Side::Ask => self.asks.get_orders_till_qty_into(price, qty, orders),
+            Side::Bid => self.bids.get_orders_till_qty_into(price, qty, orders),
+        }?;
+        for order in &orders[start..] {
+            if let Entry::Occupied(mut entry) = self.orders.entry(order.id) {
+                if entry.get().qty > order.qty {
+                    entry.get_mut().qty -= order.qty;
+                } else {
+                    entry.remove();
+                }
+            }
         }
+        Some(total_qty)
     }
 }
 
@@ -132,7 +658,201 @@ impl Default for OrderBook {
 
 #[cfg(test)]
 mod test {
-    use crate::{Order, OrderBook, OrderBookError, OrderId, Side};
+    use crate::quote::{QuoteError, QuoteLevel};
+    use crate::{Order, OrderBook, OrderBookError, OrderId, Side, Trade};
+
+    #[test]
+    fn quote_replace() {
+        // Setup
+        let mut ob = OrderBook::default();
+        let owner = 7;
+        ob.quote(
+            owner,
+            1,
+            vec![
+                QuoteLevel::new(1, Side::Bid, 99, 10),
+                QuoteLevel::new(2, Side::Bid, 98, 20),
+                QuoteLevel::new(3, Side::Ask, 101, 10),
+            ],
+        )
+        .unwrap();
+
+        // Act
+        let res = ob.quote(
+            owner,
+            2,
+            vec![
+                QuoteLevel::new(1, Side::Bid, 100, 5),
+                QuoteLevel::new(4, Side::Ask, 102, 15),
+                QuoteLevel::new(5, Side::Ask, 103, 0),
+            ],
+        );
+
+        // Assert
+        assert_eq!(res, Ok(vec![]));
+        assert_eq!(ob.get_depth(Side::Bid), vec![(100, 5)]);
+        assert_eq!(ob.get_depth(Side::Ask), vec![(102, 15)]);
+        assert_eq!(ob.get_quote(owner).unwrap().quote_id, 2);
+    }
+
+    #[test]
+    fn quote_replaces_in_place() {
+        // Setup
+        let mut ob = OrderBook::default();
+        let owner = 7;
+        ob.quote(owner, 1, vec![QuoteLevel::new(1, Side::Bid, 99, 10)])
+            .unwrap();
+        ob.insert(Order::new(99, 5, Side::Bid, 9)).unwrap();
+        let ids = |ob: &OrderBook| -> Vec<(OrderId, u64)> {
+            ob.get_orders(99, Side::Bid)
+                .unwrap()
+                .iter()
+                .map(|order| (order.id, order.qty))
+                .collect()
+        };
+
+        // Act
+        ob.quote(owner, 2, vec![QuoteLevel::new(1, Side::Bid, 99, 6)])
+            .unwrap();
+        let reduced = ids(&ob);
+        ob.quote(owner, 3, vec![QuoteLevel::new(1, Side::Bid, 99, 8)])
+            .unwrap();
+        let grown = ids(&ob);
+
+        // Assert
+        assert_eq!(reduced, vec![(1, 6), (9, 5)]);
+        assert_eq!(grown, vec![(9, 5), (1, 8)]);
+        assert_eq!(ob.get_quote(owner).unwrap().quote_id, 3);
+    }
+
+    #[test]
+    fn quote_order_id_reused_after_fill() {
+        // Setup
+        let mut ob = OrderBook::default();
+        ob.quote(1, 1, vec![QuoteLevel::new(1, Side::Ask, 101, 10)])
+            .unwrap();
+        ob.match_order(Order::new(101, 10, Side::Bid, 2)).unwrap();
+
+        // Act
+        ob.insert(Order::new(102, 5, Side::Ask, 1)).unwrap();
+        ob.match_order(Order::new(102, 2, Side::Bid, 3)).unwrap();
+        let quote = ob.cancel_quote(1).unwrap();
+
+        // Assert
+        assert_eq!(quote.filled_qty(), 10);
+        assert_eq!(ob.get_order(1), Some(&Order::new(102, 3, Side::Ask, 1)));
+    }
+
+    #[test]
+    fn quote_rejected_atomically() {
+        // Setup
+        let mut ob = OrderBook::default();
+        ob.insert(Order::new(50, 1, Side::Bid, 9)).unwrap();
+        ob.quote(1, 1, vec![QuoteLevel::new(1, Side::Bid, 99, 10)])
+            .unwrap();
+
+        // Act
+        let duplicate = ob.quote(1, 2, vec![QuoteLevel::new(9, Side::Bid, 98, 10)]);
+        let other_owner = ob.quote(2, 1, vec![QuoteLevel::new(1, Side::Bid, 98, 10)]);
+        let crossed = ob.quote(
+            1,
+            3,
+            vec![
+                QuoteLevel::new(2, Side::Bid, 101, 10),
+                QuoteLevel::new(3, Side::Ask, 100, 10),
+            ],
+        );
+
+        // Assert
+        assert_eq!(
+            duplicate,
+            Err(QuoteError::OrderBook(OrderBookError::DuplicateOrderId(9)))
+        );
+        assert_eq!(
+            other_owner,
+            Err(QuoteError::OrderBook(OrderBookError::DuplicateOrderId(1)))
+        );
+        assert_eq!(crossed, Err(QuoteError::Crossed { bid: 101, ask: 100 }));
+        assert_eq!(ob.get_depth(Side::Bid), vec![(99, 10), (50, 1)]);
+        assert_eq!(ob.get_quote(1).unwrap().quote_id, 1);
+    }
+
+    #[test]
+    fn quote_fills_and_cancel_all() {
+        // Setup
+        let mut ob = OrderBook::default();
+        ob.quote(1, 10, vec![QuoteLevel::new(1, Side::Ask, 101, 10)])
+            .unwrap();
+        ob.quote(2, 20, vec![QuoteLevel::new(2, Side::Ask, 101, 10)])
+            .unwrap();
+
+        // Act
+        ob.match_order(Order::new(101, 15, Side::Bid, 3)).unwrap();
+        let trades = ob
+            .quote(3, 30, vec![QuoteLevel::new(4, Side::Bid, 101, 2)])
+            .unwrap();
+        let cancelled = ob.cancel_all_quotes();
+
+        // Assert
+        assert_eq!(trades.len(), 1);
+        let filled: Vec<_> = cancelled
+            .iter()
+            .map(|(owner, quote)| (*owner, quote.quote_id, quote.filled_qty()))
+            .collect();
+        assert_eq!(filled, vec![(1, 10, 10), (2, 20, 7), (3, 30, 2)]);
+        assert!(ob.get_depth(Side::Ask).is_empty());
+        assert_eq!(ob.get_depth(Side::Bid), vec![]);
+        assert!(ob.get_quote(1).is_none());
+    }
+
+    #[test]
+    fn get_levels() {
+        // Setup
+        let mut ob = OrderBook::new();
+        for (id, price) in [(1, 71), (2, 69), (3, 70), (4, 69)] {
+            assert!(ob.insert(Order::new(price, 10, Side::Ask, id)).is_ok());
+        }
+
+        // Act
+        let levels: Vec<_> = ob.get_levels(Side::Ask).take(2).collect();
+
+        // Assert
+        assert_eq!(levels, vec![(69, 20), (70, 10)]);
+        assert_eq!(ob.get_levels(Side::Bid).next(), None);
+    }
+
+    #[test]
+    fn preview() {
+        // Setup
+        let mut ob = OrderBook::default();
+        ob.insert(Order::new(101, 10, Side::Ask, 1)).unwrap();
+        ob.insert(Order::new(101, 20, Side::Ask, 2)).unwrap();
+        ob.insert(Order::new(103, 30, Side::Ask, 3)).unwrap();
+
+        // Act
+        let preview = ob.preview(Side::Bid, 50, Some(102));
+        let market = ob.preview(Side::Bid, 50, None);
+
+        // Assert
+        assert_eq!(
+            preview.fills,
+            vec![
+                Order::new(101, 10, Side::Ask, 1),
+                Order::new(101, 20, Side::Ask, 2)
+            ]
+        );
+        assert_eq!(preview.filled_qty, 30);
+        assert_eq!(preview.remaining_qty, 20);
+        assert_eq!(preview.vwap, Some(101.0));
+        assert_eq!(preview.worst_price, Some(101));
+        assert_eq!(
+            market.fills.last(),
+            Some(&Order::new(103, 20, Side::Ask, 3))
+        );
+        assert_eq!(market.vwap, Some((3030.0 + 2060.0) / 50.0));
+        assert_eq!(ob.get_total_qty(101, Side::Ask), Some(30));
+        assert_eq!(ob.preview(Side::Ask, 5, None).worst_price, None);
+    }
 
     #[test]
     fn insert() {
@@ -219,6 +939,40 @@ mod test {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn reduce() {
+        // Setup
+        let mut ob = OrderBook::default();
+        let price = 69;
+        let qty = 420;
+        let side = Side::Ask;
+        let id = 1;
+        let order = Order {
+            price,
+            qty,
+            side,
+            id,
+        };
+        assert!(ob.insert(order).is_ok());
+
+        // Act
+        let res = ob.reduce(id, 20);
+
+        // Assert
+        assert!(res.is_ok());
+        assert_eq!(ob.get_order(id).unwrap().qty, qty - 20);
+        assert_eq!(ob.get_total_qty(price, side), Some(qty - 20));
+
+        // Act
+        let res = ob.reduce(id, qty);
+
+        // Assert
+        assert!(res.is_ok());
+        assert!(ob.get_order(id).is_none());
+        assert_eq!(ob.get_best_price(side), None);
+        assert_eq!(ob.reduce(id, qty), Err(OrderBookError::UnknownId(id)));
+    }
+
     #[test]
     fn get_best_price_ask() {
         // Setup
@@ -252,7 +1006,7 @@ mod test {
         let best_price = ob.get_best_price(side);
 
         // Assert
-        assert_eq!(best_price, Some(&o2.price));
+        assert_eq!(best_price, Some(&o1.price));
     }
 
     #[test]
@@ -288,7 +1042,7 @@ mod test {
         let best_price = ob.get_best_price(side);
 
         // Assert
-        assert_eq!(best_price, Some(&o1.price));
+        assert_eq!(best_price, Some(&o2.price));
     }
 
     #[test]
@@ -342,5 +1096,86 @@ mod test {
         assert_eq!(item.price, price);
         assert_eq!(item.qty, qty);
         assert_eq!(item.side, side);
+
+        // Drained orders are no longer in the book
+        assert!(!ob.orders.contains_key(&id));
+        assert!(!ob.orders.contains_key(&id_2));
+        assert_eq!(ob.get_best_price(side), None);
+    }
+
+    #[test]
+    fn match_order() {
+        // Setup
+        let mut ob = OrderBook::new();
+        let qty = 420;
+        assert!(ob.insert(Order::new(70, qty, Side::Ask, 1)).is_ok());
+        assert!(ob.insert(Order::new(69, qty, Side::Ask, 2)).is_ok());
+        assert!(ob.insert(Order::new(69, qty, Side::Ask, 3)).is_ok());
+        assert!(ob.insert(Order::new(68, qty, Side::Bid, 4)).is_ok());
+
+        // Act
+        let res = ob.match_order(Order::new(70, qty * 3 - 20, Side::Bid, 5));
+
+        // Assert
+        let trades = res.unwrap();
+        let expected = [(2, 69, qty), (3, 69, qty), (1, 70, qty - 20)];
+        assert_eq!(trades.len(), expected.len());
+        for (trade, (maker_id, price, qty)) in trades.iter().zip(expected) {
+            assert_eq!(
+                *trade,
+                Trade {
+                    maker_id,
+                    taker_id: 5,
+                    price,
+                    qty,
+                    side: Side::Bid,
+                }
+            );
+        }
+        assert_eq!(ob.get_depth(Side::Ask), vec![(70, 20)]);
+        assert_eq!(ob.get_order(1).unwrap().qty, 20);
+        assert!(ob.get_order(2).is_none());
+        assert!(ob.get_order(5).is_none());
+    }
+
+    #[test]
+    fn match_order_rests_remaining() {
+        // Setup
+        let mut ob = OrderBook::new();
+        let qty = 420;
+        assert!(ob.insert(Order::new(69, qty, Side::Bid, 1)).is_ok());
+
+        // Act
+        let res = ob.match_order(Order::new(68, qty + 10, Side::Ask, 2));
+
+        // Assert
+        assert_eq!(res.unwrap().len(), 1);
+        assert_eq!(ob.get_best_price(Side::Bid), None);
+        assert_eq!(ob.get_depth(Side::Ask), vec![(68, 10)]);
+        assert_eq!(ob.get_order(2).unwrap().qty, 10);
+        assert_eq!(
+            ob.match_order(Order::new(68, qty, Side::Ask, 2)),
+            Err(OrderBookError::DuplicateOrderId(2))
+        );
+    }
+
+    #[test]
+    fn zero_qty_rejected() {
+        // Setup
+        let mut ob = OrderBook::new();
+
+        // Act
+        let inserted = ob.insert(Order::new(100, 0, Side::Ask, 1));
+        let matched = ob.match_order(Order::new(100, 0, Side::Bid, 2));
+        assert!(ob.insert(Order::new(101, 10, Side::Ask, 3)).is_ok());
+        let trades = ob.match_order(Order::new(101, 5, Side::Bid, 4)).unwrap();
+
+        // Assert
+        assert_eq!(inserted, Err(OrderBookError::InvalidQty(1)));
+        assert_eq!(matched, Err(OrderBookError::InvalidQty(2)));
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].maker_id, 3);
+        assert_eq!(ob.get_best_price(Side::Bid), None);
+        assert_eq!(ob.get_depth(Side::Ask), vec![(101, 5)]);
     }
 }
@@ -1,39 +1,36 @@
 #![allow(unused, clippy::unused_self)]
+use crate::allocation::Allocation;
 use crate::error::OrderBookError;
+use crate::HashMap;
 use crate::{Order, OrderId, Qty, Side};
-use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap, VecDeque};
-use std::time::{SystemTime, UNIX_EPOCH};
-
-type TimeStamp = u128; // SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
-
-#[derive(Debug, Eq, PartialEq)]
-struct TimeStampId {
+use alloc::collections::{BinaryHeap, VecDeque};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Queue entry of an `Order`, the time priority is the insertion sequence within the
+/// `PriceLevel` rather than a wall clock, so matching is deterministic and needs no clock
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct SeqId {
     id: OrderId,
-    ts: TimeStamp,
+    seq: u64,
 }
 
-impl TimeStampId {
-    fn new(id: OrderId) -> Self {
-        Self {
-            id,
-            ts: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis(),
-        }
+impl SeqId {
+    fn new(id: OrderId, seq: u64) -> Self {
+        Self { id, seq }
     }
 }
 
-impl PartialOrd for TimeStampId {
+impl PartialOrd for SeqId {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
-impl Ord for TimeStampId {
+impl Ord for SeqId {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.ts.cmp(&other.ts) {
+        match self.seq.cmp(&other.seq) {
             Ordering::Greater => Ordering::Less,
             Ordering::Less => Ordering::Greater,
             Ordering::Equal => Ordering::Equal,
@@ -41,35 +38,87 @@ impl Ord for TimeStampId {
     }
 }
 
-#[derive(Debug)]
+/// Resting `Order` with the sequence of its queue entry, queue entries of a removed order are
+/// dropped lazily and must not be taken for an order later inserted with the same `OrderId`
+#[derive(Clone, Debug)]
+struct Resting {
+    seq: u64,
+    order: Order,
+}
+
+#[derive(Clone, Debug)]
 pub(super) struct PriceLevel {
-    queue: BinaryHeap<TimeStampId>,
+    queue: BinaryHeap<SeqId>,
     total_qty: Qty,
-    map: HashMap<OrderId, Order>,
+    map: HashMap<OrderId, Resting>,
+    seq: u64,
+    allocation: Allocation,
 }
 
 impl PriceLevel {
     /// Constructor function
-    pub(super) fn new() -> Self {
+    pub(super) fn new(allocation: Allocation) -> Self {
+        Self::with_capacity(allocation, 0)
+    }
+
+    /// Constructor function holding `orders` orders without reallocating
+    pub(super) fn with_capacity(allocation: Allocation, orders: usize) -> Self {
         Self {
-            queue: BinaryHeap::new(),
+            queue: BinaryHeap::with_capacity(orders),
             total_qty: 0,
-            map: HashMap::new(),
+            map: HashMap::with_capacity(orders),
+            seq: 0,
+            allocation,
+        }
+    }
+
+    /// Function removes all orders, keeping the allocated capacity for reuse
+    pub(super) fn clear(&mut self) {
+        self.queue.clear();
+        self.map.clear();
+        self.total_qty = 0;
+        self.seq = 0;
+    }
+
+    /// Function drops queue entries of orders no longer in the `PriceLevel` once they make up
+    /// most of the queue, in place
+    fn compact(&mut self) {
+        if self.queue.len() > 2 * self.map.len() + 8 {
+            let map = &self.map;
+            self.queue
+                .retain(|item| map.get(&item.id).is_some_and(|r| r.seq == item.seq));
         }
     }
 
     /// Function inserts new `Order` into `PriceLevel`
     pub(super) fn insert(&mut self, order: &Order) {
         let id = order.id;
-        self.map.insert(id, *order);
-        self.queue.push(TimeStampId::new(id));
+        self.map.insert(
+            id,
+            Resting {
+                seq: self.seq,
+                order: *order,
+            },
+        );
+        self.queue.push(SeqId::new(id, self.seq));
+        self.seq += 1;
         self.total_qty += order.qty;
     }
 
     /// Function removes `Order` from `PriceLevel`
     pub(super) fn remove(&mut self, id: OrderId) {
-        if let Some(order) = self.map.remove(&id) {
+        if let Some(Resting { order, .. }) = self.map.remove(&id) {
             self.total_qty -= order.qty;
+            self.compact();
+        }
+    }
+
+    /// Function reduces the `Qty` of an `Order` in the `PriceLevel`, keeping its time priority
+    pub(super) fn reduce(&mut self, id: OrderId, qty: Qty) {
+        if let Some(Resting { order, .. }) = self.map.get_mut(&id) {
+            let qty = qty.min(order.qty);
+            order.qty -= qty;
+            self.total_qty -= qty;
         }
     }
 
@@ -77,17 +126,106 @@ impl PriceLevel {
         self.total_qty
     }
 
+    /// Function checks if no orders rest in the `PriceLevel`, orders of zero `Qty` included
+    pub(super) fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Function asserts that `total_qty` is the sum of the resting quantities and that every
+    /// resting order is queued
+    #[cfg(test)]
+    pub(super) fn assert_invariants(&self) {
+        let qty: Qty = self.map.values().map(|resting| resting.order.qty).sum();
+        assert_eq!(self.total_qty, qty, "total_qty of {:?}", self.map);
+        for (id, resting) in &self.map {
+            assert!(
+                self.queue
+                    .iter()
+                    .any(|item| (item.id, item.seq) == (*id, resting.seq)),
+                "OrderId {id} is not queued"
+            );
+        }
+    }
+
+    /// Function gets the resting orders in FIFO order
+    pub(super) fn get_orders(&self) -> Vec<Order> {
+        let mut queue: Vec<_> = self
+            .queue
+            .iter()
+            .filter(|item| {
+                self.map
+                    .get(&item.id)
+                    .is_some_and(|resting| resting.seq == item.seq)
+            })
+            .collect();
+        queue.sort_by(|a, b| b.cmp(a));
+        queue.iter().map(|item| self.map[&item.id].order).collect()
+    }
+
+    /// Function collects the orders `get_orders_till_qty` would drain, without draining them
+    pub(super) fn peek_orders_till_qty(&self, total_qty: Qty) -> (Vec<Order>, Qty) {
+        let orders = self.get_orders();
+        let sizes: Vec<Qty> = orders.iter().map(|order| order.qty).collect();
+        let allocs = self.allocation.allocate(&sizes, total_qty);
+        let mut collected_qty = 0;
+        let orders = orders
+            .into_iter()
+            .zip(allocs)
+            .filter(|&(_, qty)| qty > 0)
+            .map(|(order, qty)| {
+                collected_qty += qty;
+                Order { qty, ..order }
+            })
+            .collect();
+        (orders, collected_qty)
+    }
+
     /// Function drains map on the given `Side` up to the given `Qty`
     ///
     /// Returns [`Some`] with map and total collected `Qty`
     /// Returns [`None`] if there are no map on the given `Side` and `Price` combination
     pub(super) fn get_orders_till_qty(&mut self, total_qty: Qty) -> (Vec<Order>, Qty) {
-        let mut collected_qty = 0;
         let mut orders = vec![];
+        let collected_qty = self.get_orders_till_qty_into(total_qty, &mut orders);
+        (orders, collected_qty)
+    }
+
+    /// Function drains orders up to the given `Qty`, appending them to `orders`
+    ///
+    /// Only allocates for [`Allocation`]'s other than FIFO, or when `orders` has to grow
+    ///
+    /// Returns the total collected `Qty`
+    pub(super) fn get_orders_till_qty_into(
+        &mut self,
+        total_qty: Qty,
+        orders: &mut Vec<Order>,
+    ) -> Qty {
+        // Nothing to drain, the FIFO split below would return an order without `Qty`
+        if total_qty == 0 {
+            return 0;
+        }
+        if self.allocation != Allocation::Fifo {
+            let (drained, collected_qty) = self.peek_orders_till_qty(total_qty);
+            for order in &drained {
+                self.reduce(order.id, order.qty);
+                if self
+                    .map
+                    .get(&order.id)
+                    .is_some_and(|resting| resting.order.qty == 0)
+                {
+                    self.map.remove(&order.id);
+                }
+            }
+            self.compact();
+            orders.extend(drained);
+            return collected_qty;
+        }
+        let mut collected_qty = 0;
 
         // Peek order
         while let Some(item) = self.queue.pop() {
-            if let Some(order) = self.map.get_mut(&item.id) {
+            let resting = self.map.get_mut(&item.id);
+            if let Some(Resting { order, .. }) = resting.filter(|resting| resting.seq == item.seq) {
                 match (collected_qty + order.qty).cmp(&total_qty) {
                     Ordering::Less => {
                         collected_qty += order.qty;
@@ -102,7 +240,7 @@ impl PriceLevel {
                             id: order.id,
                             price: order.price,
                             qty: (order.qty - order_diff),
-                            side: Side::Ask,
+                            side: order.side,
                         });
                         collected_qty = total_qty;
                         order.qty = order_diff;
@@ -120,20 +258,21 @@ impl PriceLevel {
                 }
             }
         }
-        (orders, collected_qty)
+        collected_qty
     }
 }
 
 impl Default for PriceLevel {
     fn default() -> Self {
-        Self::new()
+        Self::new(Allocation::Fifo)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::PriceLevel;
-    use crate::{Order, OrderId, Side};
+    use crate::allocation::{Allocation, Rounding};
+    use crate::{Order, OrderId, Qty, Side};
 
     #[test]
     fn get_single_till_qty_remaining() {
@@ -162,6 +301,21 @@ mod test {
         assert_eq!(total_qty, qty - 2);
     }
 
+    #[test]
+    fn get_till_zero_qty() {
+        // Setup
+        let mut pl = PriceLevel::default();
+        pl.insert(&Order::new(69, 420, Side::Ask, 1));
+
+        // Act
+        let (items, total_qty) = pl.get_orders_till_qty(0);
+
+        // Assert
+        assert!(items.is_empty());
+        assert_eq!(total_qty, 0);
+        assert_eq!(pl.total_qty, 420);
+    }
+
     #[test]
     fn get_single_till_qty_drain_exact() {
         // Setup
@@ -377,4 +531,156 @@ mod test {
         assert!(!pl.map.contains_key(&id));
         assert_eq!(pl.total_qty, 0);
     }
+
+    #[test]
+    fn remove_compacts_queue() {
+        // Setup
+        let mut pl = PriceLevel::default();
+        pl.insert(&Order::new(69, 1, Side::Ask, 0));
+
+        // Act
+        for id in 1..1000 {
+            pl.insert(&Order::new(69, 1, Side::Ask, id));
+            pl.remove(id);
+        }
+
+        // Assert
+        assert!(pl.queue.len() <= 11);
+        assert_eq!(pl.get_orders(), vec![Order::new(69, 1, Side::Ask, 0)]);
+    }
+
+    #[test]
+    fn reinsert_loses_time_priority() {
+        // Setup
+        let mut pl = PriceLevel::default();
+        pl.insert(&Order::new(69, 1, Side::Ask, 1));
+        pl.insert(&Order::new(69, 1, Side::Ask, 2));
+        pl.remove(1);
+
+        // Act
+        pl.insert(&Order::new(69, 1, Side::Ask, 1));
+        let orders = pl.get_orders();
+        let (items, qty) = pl.get_orders_till_qty(1);
+
+        // Assert
+        let ids: Vec<OrderId> = orders.iter().map(|order| order.id).collect();
+        assert_eq!(ids, vec![2, 1]);
+        assert_eq!(items, vec![Order::new(69, 1, Side::Ask, 2)]);
+        assert_eq!(qty, 1);
+        pl.assert_invariants();
+    }
+
+    #[test]
+    fn get_orders_fifo() {
+        // Setup
+        let mut pl = PriceLevel::default();
+        let price = 69;
+        let qty = 420;
+        let side = Side::Bid;
+        for id in 1..=5 {
+            pl.insert(&Order {
+                price,
+                qty,
+                side,
+                id,
+            });
+        }
+        pl.remove(3);
+
+        // Act
+        let orders = pl.get_orders();
+        let (items, _) = pl.get_orders_till_qty(qty * 2 + 1);
+
+        // Assert
+        let ids: Vec<OrderId> = orders.iter().map(|order| order.id).collect();
+        assert_eq!(ids, vec![1, 2, 4, 5]);
+        let ids: Vec<OrderId> = items.iter().map(|order| order.id).collect();
+        assert_eq!(ids, vec![1, 2, 4]);
+        assert_eq!(items[2].side, side);
+        assert_eq!(items[2].qty, 1);
+    }
+
+    #[test]
+    fn peek_till_qty() {
+        // Setup
+        let mut pl = PriceLevel::default();
+        let price = 69;
+        let qty = 420;
+        let side = Side::Ask;
+        for id in 1..=3 {
+            pl.insert(&Order {
+                price,
+                qty,
+                side,
+                id,
+            });
+        }
+
+        // Act
+        let (peeked, peeked_qty) = pl.peek_orders_till_qty(qty + 3);
+
+        // Assert
+        assert_eq!(pl.total_qty, qty * 3);
+        assert_eq!(pl.map.len(), 3);
+        assert_eq!(pl.get_orders_till_qty(qty + 3), (peeked, peeked_qty));
+    }
+
+    #[test]
+    fn get_pro_rata_till_qty() {
+        // Setup
+        let mut pl = PriceLevel::new(Allocation::ProRata {
+            rounding: Rounding::Down,
+            min_qty: 0,
+        });
+        let price = 69;
+        let side = Side::Bid;
+        for (id, qty) in [(1, 10), (2, 20), (3, 30)] {
+            pl.insert(&Order {
+                price,
+                qty,
+                side,
+                id,
+            });
+        }
+
+        // Act
+        let (peeked, _) = pl.peek_orders_till_qty(9);
+        let (items, total_qty) = pl.get_orders_till_qty(9);
+        let (rest, _) = pl.get_orders_till_qty(60);
+
+        // Assert
+        assert_eq!(items, peeked);
+        assert_eq!(total_qty, 9);
+        let qtys: Vec<Qty> = items.iter().map(|order| order.qty).collect();
+        assert_eq!(qtys, vec![2, 3, 4]);
+        let qtys: Vec<Qty> = rest.iter().map(|order| order.qty).collect();
+        assert_eq!(qtys, vec![8, 17, 26]);
+        assert_eq!(pl.total_qty, 0);
+        assert!(pl.map.is_empty());
+    }
+
+    #[test]
+    fn reduce() {
+        // Setup
+        let mut pl = PriceLevel::default();
+        let price = 69;
+        let qty = 420;
+        let side = Side::Ask;
+        let id: OrderId = 1;
+        let order = Order {
+            price,
+            qty,
+            side,
+            id,
+        };
+
+        pl.insert(&order);
+        // Act
+        pl.reduce(id, 20);
+
+        // Assert
+        assert_eq!(pl.total_qty, qty - 20);
+        assert_eq!(pl.map.get(&id).unwrap().order.qty, qty - 20);
+        assert_eq!(pl.queue.len(), 1);
+    }
 }
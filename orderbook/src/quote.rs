@@ -0,0 +1,80 @@
+//! Market maker quotes
+//!
+//! A quote is a set of resting orders on both sides, owned by a market maker. Submitting a new
+//! quote with [`OrderBook::quote`](crate::OrderBook::quote) atomically replaces all orders of the
+//! previous quote of the owner in a single call, and every trade of a quote order is attributed to
+//! its [`QuoteSet`].
+#![allow(clippy::module_name_repetitions)]
+use crate::{OrderBookError, OrderId, Price, Qty, Side, Trade};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Identifies the market maker owning a quote
+pub type OwnerId = u64;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuoteError {
+    #[error(transparent)]
+    OrderBook(#[from] OrderBookError),
+    #[error("Bid {bid} crosses ask {ask} of the same quote")]
+    Crossed { bid: Price, ask: Price },
+}
+
+/// A single order of a quote
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuoteLevel {
+    /// OrderId of the resting order, assigned by the caller like for any [`Order`](crate::Order)
+    pub id: OrderId,
+    pub side: Side,
+    pub price: Price,
+    /// Quantity, levels with a quantity of zero are not quoted
+    pub qty: Qty,
+}
+
+impl QuoteLevel {
+    /// Constructor function
+    #[must_use]
+    pub fn new(id: OrderId, side: Side, price: Price, qty: Qty) -> Self {
+        Self {
+            id,
+            side,
+            price,
+            qty,
+        }
+    }
+}
+
+/// The current quote of an owner
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuoteSet {
+    /// Identifier chosen by the owner, e.g. to match fills to quote updates
+    pub quote_id: u64,
+    /// Levels as submitted
+    pub levels: Vec<QuoteLevel>,
+    /// Trades of the quote orders, as maker or taker, in execution order
+    pub fills: Vec<Trade>,
+}
+
+impl QuoteSet {
+    /// Function gets the total executed quantity of the quote
+    #[must_use]
+    pub fn filled_qty(&self) -> Qty {
+        self.fills.iter().map(|fill| fill.qty).sum()
+    }
+}
+
+/// Function checks that the bids of a quote do not cross its asks
+pub(crate) fn check_crossed(levels: &[QuoteLevel]) -> Result<(), QuoteError> {
+    let best = |side, pick: fn(Price, Price) -> Price| {
+        levels
+            .iter()
+            .filter(|level| level.side == side && level.qty > 0)
+            .map(|level| level.price)
+            .reduce(pick)
+    };
+    match (best(Side::Bid, Price::max), best(Side::Ask, Price::min)) {
+        (Some(bid), Some(ask)) if bid >= ask => Err(QuoteError::Crossed { bid, ask }),
+        _ => Ok(()),
+    }
+}
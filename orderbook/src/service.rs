@@ -0,0 +1,258 @@
+//! Async order book service
+//!
+//! An [`OrderBookHandle`] sends requests to a task owning the [`OrderBook`] and awaits the
+//! response, so async code never holds a lock on the book. Handles are cheap to clone, the task
+//! stops once the last handle is dropped. Every change of the book is broadcast as a
+//! [`BookEvent`] to the receivers of [`OrderBookHandle::subscribe`].
+//!
+//! Requires the `tokio` feature.
+use crate::{Order, OrderBook, OrderBookError, OrderId, Price, Qty, Side, Trade};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc, oneshot};
+
+/// Number of requests queued before callers wait
+const REQUEST_CAPACITY: usize = 1024;
+/// Number of events kept for slow subscribers before they lag
+const EVENT_CAPACITY: usize = 1024;
+
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServiceError {
+    #[error(transparent)]
+    OrderBook(#[from] OrderBookError),
+    #[error("Order book task stopped")]
+    Closed,
+}
+
+/// A change of the book
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BookEvent {
+    /// An execution of an incoming order
+    Trade { trade: Trade },
+    /// An order, or its remaining quantity, was added to the book
+    Rested { order: Order },
+    /// A resting order was removed
+    Removed { id: OrderId },
+}
+
+enum Request {
+    Insert(Order, oneshot::Sender<Result<(), OrderBookError>>),
+    Match(Order, oneshot::Sender<Result<Vec<Trade>, OrderBookError>>),
+    Remove(OrderId, oneshot::Sender<Result<(), OrderBookError>>),
+    BestPrice(Side, oneshot::Sender<Option<Price>>),
+    Depth(Side, oneshot::Sender<Vec<(Price, Qty)>>),
+}
+
+/// Cloneable handle to an [`OrderBook`] owned by a tokio task
+#[derive(Clone, Debug)]
+pub struct OrderBookHandle {
+    requests: mpsc::Sender<Request>,
+    events: broadcast::Sender<BookEvent>,
+}
+
+impl OrderBookHandle {
+    /// Constructor function, spawning the task owning the book
+    ///
+    /// # Panics
+    ///
+    /// Panics if called outside of a tokio runtime
+    #[must_use]
+    pub fn spawn(book: OrderBook) -> Self {
+        let (requests, receiver) = mpsc::channel(REQUEST_CAPACITY);
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        tokio::spawn(run(book, receiver, events.clone()));
+        Self { requests, events }
+    }
+
+    /// Function subscribes to the events of all following requests
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<BookEvent> {
+        self.events.subscribe()
+    }
+
+    /// Function inserts an order without matching, see [`OrderBook::insert`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is already in the book or the task stopped
+    pub async fn insert(&self, order: Order) -> Result<(), ServiceError> {
+        self.request(|tx| Request::Insert(order, tx)).await??;
+        Ok(())
+    }
+
+    /// Function matches an incoming order, see [`OrderBook::match_order`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is already in the book or the task stopped
+    pub async fn match_order(&self, order: Order) -> Result<Vec<Trade>, ServiceError> {
+        Ok(self.request(|tx| Request::Match(order, tx)).await??)
+    }
+
+    /// Function removes an order, see [`OrderBook::remove`]
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the `OrderId` is unknown or the task stopped
+    pub async fn remove(&self, id: OrderId) -> Result<(), ServiceError> {
+        self.request(|tx| Request::Remove(id, tx)).await??;
+        Ok(())
+    }
+
+    /// Function gets the best price of a side
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the task stopped
+    pub async fn best_price(&self, side: Side) -> Result<Option<Price>, ServiceError> {
+        self.request(|tx| Request::BestPrice(side, tx)).await
+    }
+
+    /// Function gets the `Price` and `Qty` per level of a side, best price first
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Err`] if the task stopped
+    pub async fn depth(&self, side: Side) -> Result<Vec<(Price, Qty)>, ServiceError> {
+        self.request(|tx| Request::Depth(side, tx)).await
+    }
+
+    async fn request<T>(
+        &self,
+        request: impl FnOnce(oneshot::Sender<T>) -> Request,
+    ) -> Result<T, ServiceError> {
+        let (tx, rx) = oneshot::channel();
+        self.requests
+            .send(request(tx))
+            .await
+            .map_err(|_| ServiceError::Closed)?;
+        rx.await.map_err(|_| ServiceError::Closed)
+    }
+}
+
+async fn run(
+    mut book: OrderBook,
+    mut requests: mpsc::Receiver<Request>,
+    events: broadcast::Sender<BookEvent>,
+) {
+    // Sending only fails without subscribers, which is fine
+    let publish = |event| {
+        let _ = events.send(event);
+    };
+    while let Some(request) = requests.recv().await {
+        match request {
+            Request::Insert(order, tx) => {
+                let res = book.insert(order);
+                if res.is_ok() {
+                    publish(BookEvent::Rested { order });
+                }
+                let _ = tx.send(res);
+            }
+            Request::Match(order, tx) => {
+                let res = book.match_order(order);
+                if let Ok(trades) = &res {
+                    for &trade in trades {
+                        publish(BookEvent::Trade { trade });
+                    }
+                    if let Some(&rested) = book.get_order(order.id) {
+                        publish(BookEvent::Rested { order: rested });
+                    }
+                }
+                let _ = tx.send(res);
+            }
+            Request::Remove(id, tx) => {
+                let res = book.remove(id);
+                if res.is_ok() {
+                    publish(BookEvent::Removed { id });
+                }
+                let _ = tx.send(res);
+            }
+            Request::BestPrice(side, tx) => {
+                let _ = tx.send(book.get_best_price(side).copied());
+            }
+            Request::Depth(side, tx) => {
+                let _ = tx.send(book.get_depth(side));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{BookEvent, OrderBookHandle, ServiceError};
+    use crate::{Order, OrderBook, OrderBookError, Side, Trade};
+
+    #[tokio::test]
+    async fn requests() {
+        // Setup
+        let handle = OrderBookHandle::spawn(OrderBook::new());
+        let other = handle.clone();
+
+        // Act
+        handle
+            .insert(Order::new(99, 10, Side::Bid, 1))
+            .await
+            .unwrap();
+        other
+            .insert(Order::new(101, 5, Side::Ask, 2))
+            .await
+            .unwrap();
+        let duplicate = handle.insert(Order::new(98, 1, Side::Bid, 1)).await;
+        let unknown = other.remove(7).await;
+
+        // Assert
+        assert_eq!(
+            duplicate,
+            Err(ServiceError::OrderBook(OrderBookError::DuplicateOrderId(1)))
+        );
+        assert_eq!(
+            unknown,
+            Err(ServiceError::OrderBook(OrderBookError::UnknownId(7)))
+        );
+        assert_eq!(handle.best_price(Side::Bid).await, Ok(Some(99)));
+        assert_eq!(other.depth(Side::Ask).await, Ok(vec![(101, 5)]));
+    }
+
+    #[tokio::test]
+    async fn broadcast_events() {
+        // Setup
+        let handle = OrderBookHandle::spawn(OrderBook::new());
+        let mut events = handle.subscribe();
+
+        // Act
+        handle
+            .insert(Order::new(100, 4, Side::Ask, 1))
+            .await
+            .unwrap();
+        let trades = handle
+            .match_order(Order::new(100, 10, Side::Bid, 2))
+            .await
+            .unwrap();
+        handle.remove(2).await.unwrap();
+
+        // Assert
+        let trade = Trade {
+            maker_id: 1,
+            taker_id: 2,
+            price: 100,
+            qty: 4,
+            side: Side::Bid,
+        };
+        assert_eq!(trades, vec![trade]);
+        let expected = [
+            BookEvent::Rested {
+                order: Order::new(100, 4, Side::Ask, 1),
+            },
+            BookEvent::Trade { trade },
+            BookEvent::Rested {
+                order: Order::new(100, 6, Side::Bid, 2),
+            },
+            BookEvent::Removed { id: 2 },
+        ];
+        for event in expected {
+            assert_eq!(events.recv().await, Ok(event));
+        }
+        assert_eq!(handle.depth(Side::Bid).await, Ok(vec![]));
+    }
+}
@@ -0,0 +1,338 @@
+//! Trade tape and OHLCV bars
+//!
+//! A [`TradeTape`] records every [`Trade`] with a sequence number and a caller supplied
+//! timestamp. [`TradeTape::bars`] aggregates the tape into OHLCV [`Bar`]'s by time, volume or
+//! trade count, a [`BarBuilder`] does the same incrementally for live trades.
+use crate::{Price, Qty, Trade};
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use serde::{Deserialize, Serialize};
+
+/// A recorded execution
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TapeEntry {
+    /// Position in the tape, starting at 0
+    pub seq: u64,
+    /// Timestamp supplied by the caller, e.g. nanoseconds since midnight
+    pub time: u64,
+    #[serde(flatten)]
+    pub trade: Trade,
+}
+
+/// Append-only record of executions
+#[derive(Debug, Default)]
+pub struct TradeTape {
+    entries: Vec<TapeEntry>,
+}
+
+impl TradeTape {
+    /// Constructor function
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Function records the trades of a single match in execution order
+    pub fn record(&mut self, time: u64, trades: &[Trade]) {
+        for trade in trades {
+            self.entries.push(TapeEntry {
+                seq: self.entries.len() as u64,
+                time,
+                trade: *trade,
+            });
+        }
+    }
+
+    #[must_use]
+    pub fn entries(&self) -> &[TapeEntry] {
+        &self.entries
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Function aggregates the whole tape into bars, including the last incomplete bar
+    #[must_use]
+    pub fn bars(&self, spec: BarSpec) -> Vec<Bar> {
+        let mut builder = BarBuilder::new(spec);
+        let mut bars: Vec<Bar> = self
+            .entries
+            .iter()
+            .filter_map(|entry| builder.push(entry))
+            .collect();
+        bars.extend(builder.finish());
+        bars
+    }
+}
+
+/// When a [`Bar`] is complete
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "size", rename_all = "snake_case")]
+pub enum BarSpec {
+    /// Fixed intervals of the timestamp, aligned to multiples of the interval
+    Time(u64),
+    /// Once the volume reaches the size, trades are not split across bars
+    Volume(Qty),
+    /// After the number of trades
+    Count(usize),
+}
+
+/// Open, high, low, close and volume of consecutive trades
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bar {
+    /// Interval start for time bars, else the timestamp of the first trade
+    pub start: u64,
+    /// Timestamp of the last trade
+    pub end: u64,
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+    pub close: Price,
+    pub volume: Qty,
+    /// Volume weighted average price
+    pub vwap: f64,
+    pub trade_count: usize,
+}
+
+impl Bar {
+    /// Header line matching [`Bar::to_csv`]
+    pub const CSV_HEADER: &'static str = "start,end,open,high,low,close,volume,vwap,trade_count";
+
+    fn new(start: u64, entry: &TapeEntry) -> Self {
+        let price = entry.trade.price;
+        Self {
+            start,
+            end: entry.time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: 0,
+            vwap: 0.0,
+            trade_count: 0,
+        }
+    }
+
+    /// Function formats the bar as a CSV record, without a line break
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{},{}",
+            self.start,
+            self.end,
+            self.open,
+            self.high,
+            self.low,
+            self.close,
+            self.volume,
+            self.vwap,
+            self.trade_count
+        )
+    }
+}
+
+/// Function formats bars as CSV, including the header
+#[must_use]
+pub fn bars_to_csv(bars: &[Bar]) -> String {
+    let mut csv = format!("{}\n", Bar::CSV_HEADER);
+    for bar in bars {
+        let _ = writeln!(csv, "{}", bar.to_csv());
+    }
+    csv
+}
+
+/// Aggregates trades into bars as they happen
+#[derive(Debug)]
+pub struct BarBuilder {
+    spec: BarSpec,
+    bar: Option<Bar>,
+    notional: u128,
+}
+
+impl BarBuilder {
+    /// Constructor function
+    #[must_use]
+    pub fn new(spec: BarSpec) -> Self {
+        Self {
+            spec,
+            bar: None,
+            notional: 0,
+        }
+    }
+
+    /// Function adds a trade
+    ///
+    /// Returns [`Some`] with the bar completed by, or for time bars before, the trade
+    pub fn push(&mut self, entry: &TapeEntry) -> Option<Bar> {
+        let mut completed = None;
+        let start = match self.spec {
+            BarSpec::Time(interval) => {
+                let start = entry.time - entry.time % interval.max(1);
+                if self.bar.is_some_and(|bar| bar.start != start) {
+                    completed = self.finish();
+                }
+                start
+            }
+            BarSpec::Volume(..) | BarSpec::Count(..) => entry.time,
+        };
+
+        let bar = self.bar.get_or_insert_with(|| Bar::new(start, entry));
+        let trade = &entry.trade;
+        bar.end = entry.time;
+        bar.high = bar.high.max(trade.price);
+        bar.low = bar.low.min(trade.price);
+        bar.close = trade.price;
+        bar.volume += trade.qty;
+        bar.trade_count += 1;
+        self.notional += u128::from(trade.price) * u128::from(trade.qty);
+
+        let full = match self.spec {
+            BarSpec::Time(..) => false,
+            BarSpec::Volume(size) => bar.volume >= size,
+            BarSpec::Count(count) => bar.trade_count >= count,
+        };
+        if full {
+            completed = self.finish();
+        }
+        completed
+    }
+
+    /// Function completes the current bar
+    ///
+    /// Returns [`None`] if there were no trades since the last completed bar
+    pub fn finish(&mut self) -> Option<Bar> {
+        let notional = core::mem::take(&mut self.notional);
+        self.bar.take().map(|mut bar| {
+            if bar.volume > 0 {
+                #[allow(clippy::cast_precision_loss)]
+                let vwap = notional as f64 / bar.volume as f64;
+                bar.vwap = vwap;
+            }
+            bar
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{bars_to_csv, Bar, BarSpec, TradeTape};
+    use crate::{Side, Trade};
+
+    fn tape() -> TradeTape {
+        let mut tape = TradeTape::new();
+        let trade = |price, qty| Trade {
+            maker_id: 1,
+            taker_id: 2,
+            price,
+            qty,
+            side: Side::Bid,
+        };
+        tape.record(5, &[trade(100, 10), trade(101, 30)]);
+        tape.record(12, &[trade(99, 20)]);
+        tape.record(25, &[trade(102, 40)]);
+        tape
+    }
+
+    #[test]
+    fn record_sequence() {
+        // Act
+        let tape = tape();
+
+        // Assert
+        assert_eq!(tape.len(), 4);
+        let seqs: Vec<u64> = tape.entries().iter().map(|entry| entry.seq).collect();
+        assert_eq!(seqs, vec![0, 1, 2, 3]);
+        assert_eq!(tape.entries()[1].time, 5);
+    }
+
+    #[test]
+    fn time_bars() {
+        // Act
+        let bars = tape().bars(BarSpec::Time(10));
+
+        // Assert
+        assert_eq!(
+            bars,
+            vec![
+                Bar {
+                    start: 0,
+                    end: 5,
+                    open: 100,
+                    high: 101,
+                    low: 100,
+                    close: 101,
+                    volume: 40,
+                    vwap: 100.75,
+                    trade_count: 2
+                },
+                Bar {
+                    start: 10,
+                    end: 12,
+                    open: 99,
+                    high: 99,
+                    low: 99,
+                    close: 99,
+                    volume: 20,
+                    vwap: 99.0,
+                    trade_count: 1
+                },
+                Bar {
+                    start: 20,
+                    end: 25,
+                    open: 102,
+                    high: 102,
+                    low: 102,
+                    close: 102,
+                    volume: 40,
+                    vwap: 102.0,
+                    trade_count: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn volume_and_count_bars() {
+        // Act
+        let volume = tape().bars(BarSpec::Volume(50));
+        let count = tape().bars(BarSpec::Count(3));
+
+        // Assert
+        assert_eq!(volume.len(), 2);
+        assert_eq!((volume[0].volume, volume[0].trade_count), (60, 3));
+        assert_eq!(
+            (volume[0].open, volume[0].low, volume[0].close),
+            (100, 99, 99)
+        );
+        assert_eq!((volume[1].volume, volume[1].start), (40, 25));
+        assert_eq!(count.len(), 2);
+        assert_eq!(count[0].trade_count, 3);
+        assert_eq!(count[1].trade_count, 1);
+    }
+
+    #[test]
+    fn csv_and_json() {
+        // Setup
+        let bars = tape().bars(BarSpec::Count(4));
+
+        // Act
+        let csv = bars_to_csv(&bars);
+        let json = serde_json::to_string(&bars).unwrap();
+
+        // Assert
+        assert_eq!(
+            csv,
+            "start,end,open,high,low,close,volume,vwap,trade_count\n5,25,100,102,99,102,100,100.9,4\n"
+        );
+        assert_eq!(serde_json::from_str::<Vec<Bar>>(&json).unwrap(), bars);
+    }
+}
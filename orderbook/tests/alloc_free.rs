@@ -0,0 +1,154 @@
+use orderbook::concurrent::ConcurrentOrderBook;
+use orderbook::{Capacity, Order, OrderBook, Side, Trade};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Counts the allocations of the current thread while armed
+struct CountingAllocator;
+
+thread_local! {
+    static ARMED: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+fn count() {
+    // Ignores allocations of threads being torn down
+    let _ = ARMED.try_with(|armed| {
+        if armed.get() {
+            ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        }
+    });
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count();
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count();
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn allocations(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|allocations| allocations.set(0));
+    ARMED.with(|armed| armed.set(true));
+    f();
+    ARMED.with(|armed| armed.set(false));
+    ALLOCATIONS.with(Cell::get)
+}
+
+/// Fills both sides with two orders on each of 10 levels, sweeps part of the asks, partially
+/// fills a bid level, reduces an order, then cancels whatever is left
+fn cycle(book: &mut OrderBook, trades: &mut Vec<Trade>, round: u64) {
+    let base = round * 1000;
+    for level in 0..10 {
+        for n in 0..2 {
+            let id = base + level * 2 + n;
+            book.insert(Order::new(101 + level, 10, Side::Ask, id))
+                .unwrap();
+            book.insert(Order::new(99 - level, 10, Side::Bid, id + 100))
+                .unwrap();
+        }
+    }
+    trades.clear();
+    book.match_order_into(Order::new(104, 75, Side::Bid, base + 500), trades)
+        .unwrap();
+    book.match_order_into(Order::new(99, 15, Side::Ask, base + 501), trades)
+        .unwrap();
+    book.reduce(base + 19, 5).unwrap();
+    for id in base..base + 502 {
+        let _ = book.remove(id);
+    }
+}
+
+#[test]
+fn steady_state_does_not_allocate() {
+    // Setup
+    let mut book = OrderBook::with_capacity(Capacity {
+        orders: 1024,
+        levels: 64,
+        orders_per_level: 64,
+    });
+    let mut trades = Vec::with_capacity(64);
+    for round in 0..10 {
+        cycle(&mut book, &mut trades, round);
+    }
+
+    // Act
+    let count = allocations(|| {
+        for round in 10..1010 {
+            cycle(&mut book, &mut trades, round);
+        }
+    });
+
+    // Assert
+    assert_eq!(count, 0);
+    assert_eq!(trades.len(), 10);
+    assert!(book.get_depth(Side::Ask).is_empty());
+    assert!(book.get_depth(Side::Bid).is_empty());
+}
+
+#[test]
+fn publishing_does_not_allocate() {
+    // Setup
+    let book = OrderBook::with_capacity(Capacity {
+        orders: 1024,
+        levels: 64,
+        orders_per_level: 64,
+    });
+    let mut book = ConcurrentOrderBook::with_book(book, 5);
+    let reader = book.reader();
+    for round in 0..10 {
+        book.insert(Order::new(100 + round, 10, Side::Ask, round))
+            .unwrap();
+        book.remove(round).unwrap();
+    }
+
+    // Act
+    let count = allocations(|| {
+        for round in 10..1010 {
+            for level in 0..10 {
+                book.insert(Order::new(101 + level, 10, Side::Ask, round * 100 + level))
+                    .unwrap();
+                book.insert(Order::new(
+                    99 - level,
+                    10,
+                    Side::Bid,
+                    round * 100 + level + 50,
+                ))
+                .unwrap();
+            }
+            for level in 0..10 {
+                book.remove(round * 100 + level).unwrap();
+                book.remove(round * 100 + level + 50).unwrap();
+            }
+        }
+    });
+
+    // Assert
+    assert_eq!(count, 0);
+    assert_eq!(reader.best_price(Side::Ask), None);
+    assert_eq!(reader.best_price(Side::Bid), None);
+}
+
+#[test]
+fn allocations_are_counted() {
+    // Act
+    let count = allocations(|| {
+        let mut book = OrderBook::new();
+        book.insert(Order::new(100, 1, Side::Bid, 1)).unwrap();
+    });
+
+    // Assert
+    assert!(count > 0);
+}
@@ -0,0 +1,232 @@
+//! The `HashMap` and ladder backends behave the same for prices on the ladder
+use orderbook::allocation::{Allocation, Rounding};
+use orderbook::{Backend, BookConfig, Order, OrderBook, OrderBookError, Side, Trade};
+use proptest::prelude::*;
+
+const TICK: u64 = 5;
+
+/// A ladder of 201 levels, spanning several bitmap words
+const LADDER: Backend = Backend::Ladder {
+    min_price: 1000,
+    max_price: 2000,
+    tick: TICK,
+};
+
+fn backends() -> [Backend; 2] {
+    [Backend::HashMap, LADDER]
+}
+
+/// Runs the check against a book of every backend
+fn each_backend(check: impl Fn(OrderBook)) {
+    for backend in backends() {
+        check(OrderBook::with_backend(backend));
+    }
+}
+
+/// Bid and ask depth
+type Depth = (Vec<(u64, u64)>, Vec<(u64, u64)>);
+
+fn depth(book: &OrderBook) -> Depth {
+    (book.get_depth(Side::Bid), book.get_depth(Side::Ask))
+}
+
+#[test]
+fn insert_remove_depth() {
+    each_backend(|mut book| {
+        // Setup
+        let orders = [
+            (1500, 10, Side::Ask),
+            (1505, 20, Side::Ask),
+            (1500, 5, Side::Ask),
+            (1495, 7, Side::Bid),
+            (1000, 3, Side::Bid),
+        ];
+        for (id, (price, qty, side)) in (1..).zip(orders) {
+            book.insert(Order::new(price, qty, side, id)).unwrap();
+        }
+
+        // Act
+        book.remove(2).unwrap();
+        let unknown = book.remove(2);
+
+        // Assert
+        assert_eq!(unknown, Err(OrderBookError::UnknownId(2)));
+        assert_eq!(depth(&book), (vec![(1495, 7), (1000, 3)], vec![(1500, 15)]));
+        assert_eq!(book.get_best_price(Side::Ask), Some(&1500));
+        assert_eq!(book.get_best_price(Side::Bid), Some(&1495));
+        assert_eq!(book.get_total_qty(1505, Side::Ask), None);
+        let ids: Vec<u64> = book
+            .get_orders(1500, Side::Ask)
+            .unwrap()
+            .iter()
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(ids, vec![1, 3]);
+    });
+}
+
+#[test]
+fn best_price_moves_when_levels_empty() {
+    each_backend(|mut book| {
+        // Setup
+        for (id, price) in (1..).zip([1600, 1760, 1900, 2000]) {
+            book.insert(Order::new(price, 1, Side::Ask, id)).unwrap();
+        }
+        for (id, price) in (11..).zip([1000, 1140, 1500]) {
+            book.insert(Order::new(price, 1, Side::Bid, id)).unwrap();
+        }
+
+        // Act & Assert
+        book.remove(1).unwrap();
+        assert_eq!(book.get_best_price(Side::Ask), Some(&1760));
+        book.remove(2).unwrap();
+        book.remove(3).unwrap();
+        assert_eq!(book.get_best_price(Side::Ask), Some(&2000));
+        book.remove(4).unwrap();
+        assert_eq!(book.get_best_price(Side::Ask), None);
+        book.remove(13).unwrap();
+        assert_eq!(book.get_best_price(Side::Bid), Some(&1140));
+        book.remove(12).unwrap();
+        assert_eq!(book.get_best_price(Side::Bid), Some(&1000));
+        book.remove(11).unwrap();
+        assert_eq!(book.get_best_price(Side::Bid), None);
+    });
+}
+
+#[test]
+fn match_sweeps_levels() {
+    each_backend(|mut book| {
+        // Setup
+        for (id, price) in (1..).zip([1500, 1500, 1510, 1520]) {
+            book.insert(Order::new(price, 10, Side::Ask, id)).unwrap();
+        }
+
+        // Act
+        let trades = book
+            .match_order(Order::new(1510, 35, Side::Bid, 9))
+            .unwrap();
+        book.reduce(4, 4).unwrap();
+
+        // Assert
+        let executed: Vec<(u64, u64, u64)> = trades
+            .iter()
+            .map(|trade| (trade.maker_id, trade.price, trade.qty))
+            .collect();
+        assert_eq!(executed, vec![(1, 1500, 10), (2, 1500, 10), (3, 1510, 10)]);
+        assert_eq!(depth(&book), (vec![(1510, 5)], vec![(1520, 6)]));
+    });
+}
+
+#[test]
+fn pro_rata_allocation() {
+    for backend in backends() {
+        // Setup
+        let mut book = OrderBook::with_config(BookConfig {
+            allocation: Allocation::ProRata {
+                rounding: Rounding::Down,
+                min_qty: 0,
+            },
+            backend,
+            ..BookConfig::default()
+        });
+        for (id, qty) in (1..).zip([10, 20, 30]) {
+            book.insert(Order::new(1500, qty, Side::Bid, id)).unwrap();
+        }
+
+        // Act
+        let trades = book.match_order(Order::new(1500, 9, Side::Ask, 9)).unwrap();
+
+        // Assert
+        let qtys: Vec<u64> = trades.iter().map(|trade| trade.qty).collect();
+        assert_eq!(qtys, vec![2, 3, 4], "{backend:?}");
+    }
+}
+
+#[test]
+fn ladder_rejects_prices_off_the_ladder() {
+    // Setup
+    let mut book = OrderBook::with_backend(LADDER);
+    book.insert(Order::new(1500, 10, Side::Ask, 1)).unwrap();
+
+    // Act
+    let below = book.insert(Order::new(995, 1, Side::Bid, 2));
+    let above = book.insert(Order::new(2005, 1, Side::Ask, 3));
+    let off_tick = book.match_order(Order::new(1502, 5, Side::Bid, 4));
+
+    // Assert
+    assert_eq!(
+        below,
+        Err(OrderBookError::InvalidPrice { id: 2, price: 995 })
+    );
+    assert_eq!(
+        above,
+        Err(OrderBookError::InvalidPrice { id: 3, price: 2005 })
+    );
+    assert_eq!(
+        off_tick,
+        Err(OrderBookError::InvalidPrice { id: 4, price: 1502 })
+    );
+    // Nothing executed against the rejected order
+    assert_eq!(book.get_total_qty(1500, Side::Ask), Some(10));
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Match(Order),
+    Insert(Order),
+    Remove(u64),
+    Reduce(u64, u64),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let side = prop_oneof![Just(Side::Ask), Just(Side::Bid)];
+    let order = (0..=200u64, 1..50u64, side, 1..80u64)
+        .prop_map(|(tick, qty, side, id)| Order::new(1000 + tick * TICK, qty, side, id));
+    prop_oneof![
+        3 => order.clone().prop_map(Op::Match),
+        1 => order.prop_map(Op::Insert),
+        2 => (1..80u64).prop_map(Op::Remove),
+        1 => (1..80u64, 1..30u64).prop_map(|(id, qty)| Op::Reduce(id, qty)),
+    ]
+}
+
+/// Applies an operation, returning its outcome
+fn apply(book: &mut OrderBook, op: &Op) -> Result<Vec<Trade>, OrderBookError> {
+    match *op {
+        Op::Match(order) => book.match_order(order),
+        Op::Insert(order) => {
+            // Inserting without matching may cross the book, which only matching prevents
+            let crosses = book
+                .get_best_price(!order.side)
+                .is_some_and(|&best| match order.side {
+                    Side::Ask => best >= order.price,
+                    Side::Bid => best <= order.price,
+                });
+            if crosses {
+                book.match_order(order)
+            } else {
+                book.insert(order).map(|()| vec![])
+            }
+        }
+        Op::Remove(id) => book.remove(id).map(|()| vec![]),
+        Op::Reduce(id, qty) => book.reduce(id, qty).map(|()| vec![]),
+    }
+}
+
+proptest! {
+    #[test]
+    fn backends_agree(ops in prop::collection::vec(op(), 1..200)) {
+        let mut map = OrderBook::with_backend(Backend::HashMap);
+        let mut ladder = OrderBook::with_backend(LADDER);
+        for op in &ops {
+            prop_assert_eq!(apply(&mut map, op), apply(&mut ladder, op), "{:?}", op);
+            prop_assert_eq!(depth(&map), depth(&ladder));
+            for side in [Side::Ask, Side::Bid] {
+                prop_assert_eq!(map.get_best_price(side), ladder.get_best_price(side));
+                if let Some(&best) = map.get_best_price(side) {
+                    prop_assert_eq!(map.get_orders(best, side), ladder.get_orders(best, side));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use orderbook::concurrent::{ConcurrentOrderBook, Snapshot};
+use orderbook::{Order, Side};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+
+const LEVELS: u64 = 8;
+const UPDATES: u64 = 20_000;
+const READERS: usize = 4;
+
+/// Every published state `n` has `LEVELS` bids below and asks above `1000 + n`, each with
+/// quantity `n`, so any mix of two states is detected
+fn check(snapshot: &Snapshot) {
+    let version = snapshot.version;
+    if version == 0 {
+        assert!(snapshot.bids.is_empty() && snapshot.asks.is_empty());
+        return;
+    }
+    let mid = 1000 + version;
+    let bids: Vec<_> = (1..=LEVELS).map(|level| (mid - level, version)).collect();
+    let asks: Vec<_> = (1..=LEVELS).map(|level| (mid + level, version)).collect();
+    assert_eq!(snapshot.bids, bids, "torn bids at version {version}");
+    assert_eq!(snapshot.asks, asks, "torn asks at version {version}");
+}
+
+#[test]
+fn readers_never_observe_torn_levels() {
+    let mut book = ConcurrentOrderBook::new(LEVELS as usize);
+    let done = AtomicBool::new(false);
+
+    thread::scope(|scope| {
+        let readers: Vec<_> = (0..READERS)
+            .map(|_| {
+                let reader = book.reader();
+                let done = &done;
+                scope.spawn(move || {
+                    let mut snapshot = Snapshot::default();
+                    let mut last = 0;
+                    let mut reads = 0_u64;
+                    while !done.load(Ordering::Relaxed) {
+                        reader.snapshot_into(&mut snapshot);
+                        check(&snapshot);
+                        assert!(snapshot.version >= last, "version went backwards");
+                        last = snapshot.version;
+                        reads += 1;
+                    }
+                    reads
+                })
+            })
+            .collect();
+
+        let mut ids = Vec::new();
+        for version in 1..=UPDATES {
+            book.update(|book| {
+                for id in ids.drain(..) {
+                    book.remove(id).unwrap();
+                }
+                let mid = 1000 + version;
+                for level in 1..=LEVELS {
+                    let id = version * 100 + level;
+                    book.insert(Order::new(mid - level, version, Side::Bid, id))
+                        .unwrap();
+                    book.insert(Order::new(mid + level, version, Side::Ask, id + 50))
+                        .unwrap();
+                    ids.extend([id, id + 50]);
+                }
+            });
+        }
+        done.store(true, Ordering::Relaxed);
+
+        for reader in readers {
+            assert!(reader.join().unwrap() > 0);
+        }
+    });
+
+    check(&book.reader().snapshot());
+}
@@ -0,0 +1,90 @@
+//! The preview of an incoming order equals its real execution on a cloned book
+use orderbook::allocation::{Allocation, Rounding};
+use orderbook::{Order, OrderBook, Side};
+use proptest::prelude::*;
+
+fn side() -> impl Strategy<Value = Side> {
+    prop_oneof![Just(Side::Ask), Just(Side::Bid)]
+}
+
+fn allocation() -> impl Strategy<Value = Allocation> {
+    let rounding = prop_oneof![Just(Rounding::Down), Just(Rounding::Nearest)];
+    prop_oneof![
+        Just(Allocation::Fifo),
+        (rounding.clone(), 0..5u64)
+            .prop_map(|(rounding, min_qty)| Allocation::ProRata { rounding, min_qty }),
+        (0..50u64, 0..=100u8, rounding, 0..5u64).prop_map(
+            |(top_order_max, pro_rata_percent, rounding, min_qty)| Allocation::Hybrid {
+                top_order_max,
+                pro_rata_percent,
+                rounding,
+                min_qty,
+            }
+        ),
+    ]
+}
+
+/// Resting orders built by matching random orders, so the book is never crossed
+fn book() -> impl Strategy<Value = OrderBook> {
+    (
+        allocation(),
+        prop::collection::vec((90..110u64, 1..100u64, side()), 0..60),
+    )
+        .prop_map(|(allocation, orders)| {
+            let mut book = OrderBook::with_allocation(allocation);
+            for (id, (price, qty, side)) in (1..).zip(orders) {
+                book.match_order(Order::new(price, qty, side, id)).unwrap();
+            }
+            book
+        })
+}
+
+fn snapshot(book: &OrderBook) -> Vec<Vec<(u64, u64)>> {
+    vec![book.get_depth(Side::Bid), book.get_depth(Side::Ask)]
+}
+
+proptest! {
+    #[test]
+    fn preview_equals_execution(
+        book in book(),
+        side in side(),
+        price in 85..115u64,
+        qty in 1..2000u64,
+        limited in any::<bool>(),
+    ) {
+        let before = snapshot(&book);
+        let limit = limited.then_some(price);
+        let preview = book.preview(side, qty, limit);
+
+        let price = limit.unwrap_or(match side {
+            Side::Bid => u64::MAX,
+            Side::Ask => 0,
+        });
+        let id = u64::MAX;
+        let mut executed = book.clone();
+        let trades = executed.match_order(Order::new(price, qty, side, id)).unwrap();
+
+        // The preview left the book untouched
+        prop_assert_eq!(snapshot(&book), before);
+        let fills: Vec<_> = preview
+            .fills
+            .iter()
+            .map(|fill| (fill.id, fill.price, fill.qty))
+            .collect();
+        let trades: Vec<_> = trades
+            .iter()
+            .map(|trade| (trade.maker_id, trade.price, trade.qty))
+            .collect();
+        prop_assert_eq!(&fills, &trades);
+        prop_assert_eq!(preview.filled_qty, trades.iter().map(|&(_, _, qty)| qty).sum::<u64>());
+        let rested = executed.get_order(id).map_or(0, |order| order.qty);
+        prop_assert_eq!(preview.remaining_qty, rested);
+        prop_assert_eq!(preview.worst_price, trades.last().map(|&(_, price, _)| price));
+        if let Some(vwap) = preview.vwap {
+            let notional: u64 = trades.iter().map(|&(_, price, qty)| price * qty).sum();
+            #[allow(clippy::cast_precision_loss)]
+            let expected = notional as f64 / preview.filled_qty as f64;
+            prop_assert!((vwap - expected).abs() < 1e-9);
+        }
+    }
+}